@@ -0,0 +1,51 @@
+//! Cost of one frame of a tool over a 4000x4000 canvas.
+//!
+//! Compares the tools borrowing the canvas with copying it into the user state every frame,
+//! as the window used to. Run with `cargo bench --bench canvas_frame`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use raylib::color::Color;
+use raylib::math::Vector2;
+use PaintIt::actions::input::InputEvent;
+use PaintIt::actions::pencil::PencilState;
+use PaintIt::actions::update_execute_action::apply_input;
+use PaintIt::pixel_buffer::PixelBuffer;
+use PaintIt::user_state::{UserState, WindowVector2};
+
+const SIZE: i32 = 4000;
+const FRAMES: usize = 120;
+
+/// A pencil stroke across the canvas, one frame at a time
+fn frames(mut frame: impl FnMut(&UserState, &InputEvent)) -> Duration {
+    let mut user_state = UserState::default();
+    let input = InputEvent { pressed: true, elapsed: 1f32 / 60f32, ..Default::default() };
+    let start = Instant::now();
+    for i in 0..FRAMES {
+        let position = (i * SIZE as usize / FRAMES) as f32;
+        user_state.mouse_position = WindowVector2(Vector2::new(position, position));
+        frame(&user_state, &input);
+    }
+    start.elapsed() / FRAMES as u32
+}
+
+fn main() {
+    let mut canvas = PixelBuffer::new(SIZE, SIZE, Color::WHITE);
+    let mut tool = PencilState::default();
+    let borrowed = frames(|user_state, input| {
+        black_box(apply_input(&mut tool, user_state, input, &mut canvas));
+    });
+
+    let mut canvas = PixelBuffer::new(SIZE, SIZE, Color::WHITE);
+    let mut tool = PencilState::default();
+    let copied = frames(|user_state, input| {
+        // Clones share tiles now, so copy the pixels themselves the way the old clone did
+        let copy = black_box(canvas.pixels().collect::<Vec<Color>>());
+        black_box(apply_input(&mut tool, user_state, input, &mut canvas));
+        drop(copy);
+    });
+
+    println!("{}x{} canvas, {} frames of the pencil", SIZE, SIZE, FRAMES);
+    println!("canvas borrowed by the tool:  {:>10.3?} per frame", borrowed);
+    println!("canvas copied every frame:    {:>10.3?} per frame", copied);
+}
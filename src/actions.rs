@@ -1,5 +1,4 @@
 pub mod line;
-pub mod action_state;
 pub mod rectangle;
 pub mod pencil;
 pub mod update_execute_action;
@@ -10,5 +9,9 @@ pub mod bucket;
 pub mod ellipse;
 pub mod color_picker;
 pub mod rounded_rectangle;
-mod polygon;
+pub mod fill_mode;
+pub mod tool_registry;
+pub mod input;
+pub mod preview;
+pub mod polygon;
 mod select;
\ No newline at end of file
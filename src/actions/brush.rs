@@ -1,10 +1,11 @@
+use crate::actions::input::InputEvent;
+use crate::actions::preview::{ToolCursor, ToolPreview};
 use crate::actions::update_execute_action::UpdateExecuteAction;
+use crate::pixel_buffer::PixelBuffer;
 use crate::user_state::{CanvasVector2, UserState};
 use raylib::color::Color;
 use raylib::drawing::RaylibDrawHandle;
 use raylib::math::Vector2;
-use raylib::prelude::Image;
-use raylib::{RaylibHandle, RaylibThread};
 
 #[derive(Debug, PartialEq, Copy, Clone, Default)]
 pub enum BrushSize {
@@ -43,7 +44,7 @@ pub struct BrushState {
 }
 
 impl BrushState {
-    fn draw_shape(self, image: &mut Image, position: Vector2) {
+    fn draw_shape(self, image: &mut PixelBuffer, position: Vector2) {
         match self.brush_type {
             BrushType::Circle => {
                 image.draw_circle(
@@ -73,7 +74,7 @@ impl BrushState {
 }
 
 impl UpdateExecuteAction for BrushState {
-    fn update_pressed(&mut self, user_state: &UserState, _: &mut RaylibHandle, _: &RaylibThread) {
+    fn update_pressed(&mut self, user_state: &UserState, _: &InputEvent, _: &PixelBuffer) {
         self.old_mouse_position = self.mouse_position;
         self.mouse_position = Option::from(user_state.to_canvas(user_state.mouse_position));
         self.size = user_state.brush_size;
@@ -81,14 +82,14 @@ impl UpdateExecuteAction for BrushState {
         self.color = user_state.current_colors[0];
     }
 
-    fn update_unpressed(&mut self, _: &UserState, _: &mut RaylibHandle, _: &RaylibThread) {
+    fn update_unpressed(&mut self, _: &UserState, _: &InputEvent, _: &PixelBuffer) {
         self.old_mouse_position = None;
         self.mouse_position = None;
     }
 
     fn update_after_draw(&mut self, _: &UserState) {}
 
-    fn draw(&mut self, image: &mut Image) -> bool {
+    fn draw(&mut self, image: &mut PixelBuffer) -> bool {
         match (self.old_mouse_position, self.mouse_position) {
             (None, None) => {
                 // Nothing
@@ -136,9 +137,24 @@ impl UpdateExecuteAction for BrushState {
         }
     }
 
-    fn draw_state(&self, _: &UserState, _: &mut RaylibDrawHandle, _: &RaylibThread) {}
-
     fn get_color(&self) -> Option<Color> {
         None
     }
+
+    fn get_drawn_colors(&self) -> Vec<Color> {
+        vec![self.color]
+    }
+}
+
+impl ToolPreview for BrushState {
+    fn draw_state(&self, _: &UserState, _: &mut RaylibDrawHandle) {}
+
+    /// The size and shape of the next stroke, from the options rather than the last stroke
+    fn cursor(&self, user_state: &UserState) -> Option<ToolCursor> {
+        let width = user_state.brush_size.width();
+        Some(match user_state.brush_type {
+            BrushType::Circle => ToolCursor::Circle(width / 2f32),
+            _ => ToolCursor::Square(width as i32),
+        })
+    }
 }
@@ -1,35 +1,184 @@
+use crate::actions::input::InputEvent;
+use crate::actions::preview::ToolPreview;
 use crate::actions::update_execute_action::UpdateExecuteAction;
+use crate::pixel_buffer::PixelBuffer;
 use crate::user_state::{CanvasVector2, UserState};
 use raylib::color::Color;
-use raylib::drawing::RaylibDrawHandle;
-use raylib::prelude::Image;
-use raylib::{RaylibHandle, RaylibThread};
+use raylib::drawing::{RaylibDraw, RaylibDrawHandle};
+use raylib::math::Rectangle;
+
+/// Pixels shown on each side of the loupe
+const LOUPE_PIXELS: i32 = 11;
+/// Size on screen of each pixel in the loupe
+const LOUPE_ZOOM: i32 = 6;
+/// Distance between the cursor and the loupe
+const LOUPE_OFFSET: i32 = 16;
 
 #[derive(Debug, PartialEq, Copy, Clone, Default)]
+pub enum SampleSize {
+    #[default]
+    One,
+    Three,
+    Five,
+}
+
+impl SampleSize {
+    pub const ALL: [SampleSize; 3] = [SampleSize::One, SampleSize::Three, SampleSize::Five];
+
+    /// Pixels sampled on each side of the center pixel
+    fn radius(self) -> i32 {
+        match self {
+            SampleSize::One => 0,
+            SampleSize::Three => 1,
+            SampleSize::Five => 2,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SampleSize::One => "1x1",
+            SampleSize::Three => "3x3",
+            SampleSize::Five => "5x5",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
 pub struct ColorPickerState {
     position: Option<CanvasVector2>,
+    hover_position: Option<CanvasVector2>,
+    sample_size: SampleSize,
     color: Color,
+    /// Set once the mouse is released over the canvas
+    picked: Option<Color>,
+    /// Pixels around the cursor, row by row, LOUPE_PIXELS on each side
+    loupe: Vec<Color>,
+}
+
+impl ColorPickerState {
+    /// Average of the pixels in the sample area that fall inside the image
+    fn sample(&self, image: &PixelBuffer, position: CanvasVector2) -> Color {
+        let x = position.0.x as i32;
+        let y = position.0.y as i32;
+        let radius = self.sample_size.radius();
+
+        let mut sum = [0u32; 4];
+        let mut count = 0u32;
+        for sample_y in (y - radius)..=(y + radius) {
+            for sample_x in (x - radius)..=(x + radius) {
+                if sample_x < 0 || sample_y < 0 || sample_x >= image.width() || sample_y >= image.height() {
+                    continue;
+                }
+
+                let color = image.get_color(sample_x, sample_y);
+                sum[0] += color.r as u32;
+                sum[1] += color.g as u32;
+                sum[2] += color.b as u32;
+                sum[3] += color.a as u32;
+                count += 1;
+            }
+        }
+
+        if count == 0 {
+            return self.color;
+        }
+
+        Color::new(
+            (sum[0] / count) as u8,
+            (sum[1] / count) as u8,
+            (sum[2] / count) as u8,
+            (sum[3] / count) as u8,
+        )
+    }
 }
 
 impl UpdateExecuteAction for ColorPickerState {
-    fn update_pressed(&mut self, user_state: &UserState, _: &mut RaylibHandle, _: &RaylibThread) {
+    fn update_pressed(&mut self, user_state: &UserState, _: &InputEvent, _: &PixelBuffer) {
         self.position = Option::from(user_state.to_canvas(user_state.mouse_position));
+        self.hover_position = self.position;
+        self.sample_size = user_state.sample_size;
     }
 
-    fn update_unpressed(&mut self, _: &UserState, _: &mut RaylibHandle, _: &RaylibThread) {}
+    fn update_unpressed(&mut self, user_state: &UserState, _: &InputEvent, image: &PixelBuffer) {
+        if self.position.is_some() {
+            self.picked = Option::from(self.color);
+            self.position = None;
+        }
+
+        let mouse_position = user_state.to_canvas(user_state.mouse_position);
+        let in_canvas = 0f32 <= mouse_position.0.x && mouse_position.0.x < image.width() as f32
+            && 0f32 <= mouse_position.0.y && mouse_position.0.y < image.height() as f32;
+        self.hover_position = if in_canvas { Option::from(mouse_position) } else { None };
+        self.sample_size = user_state.sample_size;
+    }
 
     fn update_after_draw(&mut self, _: &UserState) {}
 
-    fn draw(&mut self, image: &mut Image) -> bool {
-        if let Some(position)  = self.position {
-            self.color = image.get_color(position.0.x as i32, position.0.y as i32);
+    fn draw(&mut self, image: &mut PixelBuffer) -> bool {
+        if let Some(position) = self.position {
+            self.color = self.sample(image, position);
+        }
+
+        self.loupe.clear();
+        if let Some(position) = self.hover_position {
+            let half = LOUPE_PIXELS / 2;
+            let (x, y) = (position.0.x as i32, position.0.y as i32);
+            for loupe_y in (y - half)..=(y + half) {
+                for loupe_x in (x - half)..=(x + half) {
+                    let outside = loupe_x < 0 || loupe_y < 0
+                        || loupe_x >= image.width() || loupe_y >= image.height();
+                    let color = if outside { Color::GRAY } else { image.get_color(loupe_x, loupe_y) };
+                    self.loupe.push(color);
+                }
+            }
         }
         false
     }
 
-    fn draw_state(&self, _: &UserState, _: &mut RaylibDrawHandle, _: &RaylibThread) {}
-
     fn get_color(&self) -> Option<Color> {
-        Option::from(self.color)
+        self.picked
+    }
+
+    fn get_drawn_colors(&self) -> Vec<Color> {
+        Vec::new()
+    }
+}
+
+impl ToolPreview for ColorPickerState {
+    fn draw_state(&self, user_state: &UserState, handle: &mut RaylibDrawHandle) {
+        if self.loupe.is_empty() {
+            return;
+        }
+
+        let mouse_position = user_state.mouse_position.0;
+        let origin_x = mouse_position.x as i32 + LOUPE_OFFSET;
+        let origin_y = mouse_position.y as i32 + LOUPE_OFFSET;
+        let size = LOUPE_PIXELS * LOUPE_ZOOM;
+
+        // White under the pixels, as under the canvas
+        handle.draw_rectangle(origin_x, origin_y, size, size, Color::WHITE);
+        for (i, color) in self.loupe.iter().enumerate() {
+            let i = i as i32;
+            handle.draw_rectangle(
+                origin_x + (i % LOUPE_PIXELS) * LOUPE_ZOOM,
+                origin_y + (i / LOUPE_PIXELS) * LOUPE_ZOOM,
+                LOUPE_ZOOM,
+                LOUPE_ZOOM,
+                *color
+            );
+        }
+        handle.draw_rectangle_lines(origin_x, origin_y, size, size, Color::BLACK);
+
+        // Sampled area
+        let radius = self.sample_size.radius();
+        let sample_start = (LOUPE_PIXELS / 2 - radius) * LOUPE_ZOOM;
+        let sample_size = (2 * radius + 1) * LOUPE_ZOOM;
+        let sample_area = Rectangle {
+            x: (origin_x + sample_start) as f32,
+            y: (origin_y + sample_start) as f32,
+            width: sample_size as f32,
+            height: sample_size as f32,
+        };
+        handle.draw_rectangle_lines_ex(sample_area, 1f32, Color::RED);
     }
 }
@@ -1,31 +1,40 @@
+use crate::actions::fill_mode::FillMode;
+use crate::actions::input::InputEvent;
+use crate::actions::preview::{ToolCursor, ToolPreview};
 use crate::actions::update_execute_action::UpdateExecuteAction;
+use crate::pixel_buffer::PixelBuffer;
+use crate::antialiasing::AntialiasedImage;
+use crate::rasterization;
 use crate::user_state::{CanvasVector2, UserState};
 use raylib::color::Color;
 use raylib::drawing::{RaylibDraw, RaylibDrawHandle};
-use raylib::prelude::Image;
-use raylib::{RaylibHandle, RaylibThread};
-use std::f32::consts::TAU;
 
 #[derive(Debug, PartialEq, Copy, Clone, Default)]
 pub struct EllipseState {
     start: Option<CanvasVector2>,
     end: Option<CanvasVector2>,
     draw_now: bool,
-    color: Color,
+    colors: [Color; 2],
+    antialiasing: bool,
+    line_width: i32,
+    fill_mode: FillMode,
 }
 
 impl UpdateExecuteAction for EllipseState {
-    fn update_pressed(&mut self, user_state: &UserState, _: &mut RaylibHandle, _: &RaylibThread) {
+    fn update_pressed(&mut self, user_state: &UserState, _: &InputEvent, _: &PixelBuffer) {
         if self.start == None {
             self.start = Option::from(user_state.to_canvas(user_state.mouse_position));
         } else {
             self.end = Option::from(user_state.to_canvas(user_state.mouse_position));
         }
 
-        self.color = user_state.current_colors[0];
+        self.colors = user_state.current_colors;
+        self.antialiasing = user_state.antialiasing;
+        self.line_width = user_state.line_width;
+        self.fill_mode = user_state.fill_mode;
     }
 
-    fn update_unpressed(&mut self, _: &UserState, _: &mut RaylibHandle, _: &RaylibThread) {
+    fn update_unpressed(&mut self, _: &UserState, _: &InputEvent, _: &PixelBuffer) {
         if self.start != None && self.end != None {
             self.draw_now = true;
         }
@@ -39,56 +48,82 @@ impl UpdateExecuteAction for EllipseState {
         }
     }
 
-    fn draw(&mut self, image: &mut Image) -> bool {
+    fn draw(&mut self, image: &mut PixelBuffer) -> bool {
         if !self.draw_now {
             return false;
         }
 
         let p0 = self.start.unwrap().0;
         let p1 = self.end.unwrap().0;
+        let (x0, y0, x1, y1) = (p0.x as i32, p0.y as i32, p1.x as i32, p1.y as i32);
+        let (outline, fill) = self.fill_mode.colors(self.colors);
 
-        let middle = (p0 + p1) / 2.0;
-
-        let a = (p0.x - p1.x).abs() / 2.0;
-        let b = (p0.y - p1.y).abs() / 2.0;
+        if let Some(fill) = fill {
+            for span in rasterization::ellipse_spans(x0, y0, x1, y1) {
+                image.draw_rectangle(span.x_start, span.y, span.x_end - span.x_start + 1, 1, fill);
+            }
+        }
 
-        let mut t = 0f32;
-        while t < TAU {
-            let px = middle.x + a * f32::cos(t);
-            let py = middle.y + b * f32::sin(t);
+        if let Some(outline) = outline {
+            if self.antialiasing {
+                let middle = (p0 + p1) / 2.0;
 
-            image.draw_pixel(px as i32, py as i32, self.color);
+                let a = (p0.x - p1.x).abs() / 2.0;
+                let b = (p0.y - p1.y).abs() / 2.0;
 
-            t += 0.001;
+                // Up to the smaller radius, so the ring keeps a hole in the middle
+                let width = (self.line_width as f32).min(a.min(b));
+                let mut antialiased = AntialiasedImage::new(image);
+                if width > 1f32 {
+                    antialiased.draw_ellipse_ring(middle, a, b, width, outline);
+                } else {
+                    antialiased.draw_ellipse_lines(middle, a, b, outline);
+                }
+            } else {
+                for (x, y) in rasterization::ellipse_stroke(x0, y0, x1, y1, self.line_width) {
+                    image.draw_pixel(x, y, outline);
+                }
+            }
         }
         true
     }
 
-    fn draw_state(&self, user_state: &UserState, handle: &mut RaylibDrawHandle, _: &RaylibThread) {
+    fn get_color(&self) -> Option<Color> {
+        None
+    }
+
+    fn get_drawn_colors(&self) -> Vec<Color> {
+        let (outline, fill) = self.fill_mode.colors(self.colors);
+        outline.into_iter().chain(fill).collect()
+    }
+}
+
+impl ToolPreview for EllipseState {
+    fn draw_state(&self, user_state: &UserState, handle: &mut RaylibDrawHandle) {
         if let Some(start) = self.start {
             if let Some(end) = self.end {
                 let p0 = user_state.to_window(start).0;
                 let p1 = user_state.to_window(end).0;
+                let (x0, y0, x1, y1) = (p0.x as i32, p0.y as i32, p1.x as i32, p1.y as i32);
+                let (outline, fill) = self.fill_mode.colors(self.colors);
+
+                if let Some(fill) = fill {
+                    for span in rasterization::ellipse_spans(x0, y0, x1, y1) {
+                        handle.draw_rectangle(span.x_start, span.y, span.x_end - span.x_start + 1, 1,
+                                              fill);
+                    }
+                }
 
-                let middle = (p0 + p1) / 2.0;
-
-                let a = (p0.x - p1.x).abs() / 2.0;
-                let b = (p0.y - p1.y).abs() / 2.0;
-
-                let mut t = 0f32;
-                while t < TAU {
-                    let px = middle.x + a * f32::cos(t);
-                    let py = middle.y + b * f32::sin(t);
-
-                    handle.draw_pixel(px as i32, py as i32, self.color);
-
-                    t += 0.001;
+                if let Some(outline) = outline {
+                    for (x, y) in rasterization::ellipse_stroke(x0, y0, x1, y1, self.line_width) {
+                        handle.draw_pixel(x, y, outline);
+                    }
                 }
             }
         }
     }
 
-    fn get_color(&self) -> Option<Color> {
-        None
+    fn cursor(&self, _: &UserState) -> Option<ToolCursor> {
+        Some(ToolCursor::Crosshair)
     }
 }
@@ -0,0 +1,24 @@
+use raylib::color::Color;
+
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+pub enum FillMode {
+    #[default]
+    Outline,
+    /// Outline with the primary color, filled with the secondary color
+    OutlineFill,
+    /// Filled with the primary color, without an outline
+    Fill,
+}
+
+impl FillMode {
+    pub const ALL: [FillMode; 3] = [FillMode::Outline, FillMode::OutlineFill, FillMode::Fill];
+
+    /// Colors of the outline and of the fill, from the primary and secondary colors
+    pub fn colors(self, colors: [Color; 2]) -> (Option<Color>, Option<Color>) {
+        match self {
+            FillMode::Outline => (Some(colors[0]), None),
+            FillMode::OutlineFill => (Some(colors[0]), Some(colors[1])),
+            FillMode::Fill => (None, Some(colors[0])),
+        }
+    }
+}
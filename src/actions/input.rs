@@ -0,0 +1,18 @@
+/// Modifier keys held during the event
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+pub struct Modifiers {
+    pub control: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+/// Input given to a tool each frame, independent of the window system.
+/// The pointer position travels in `UserState::mouse_position`.
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+pub struct InputEvent {
+    /// Left button held down over the canvas
+    pub pressed: bool,
+    pub modifiers: Modifiers,
+    /// Seconds since the previous event
+    pub elapsed: f32,
+}
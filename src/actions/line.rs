@@ -1,9 +1,11 @@
+use crate::actions::input::InputEvent;
+use crate::actions::preview::{ToolCursor, ToolPreview};
 use crate::actions::update_execute_action::UpdateExecuteAction;
+use crate::pixel_buffer::PixelBuffer;
+use crate::antialiasing::AntialiasedImage;
 use crate::user_state::{CanvasVector2, UserState};
 use raylib::color::Color;
 use raylib::drawing::{RaylibDraw, RaylibDrawHandle};
-use raylib::prelude::Image;
-use raylib::{RaylibHandle, RaylibThread};
 
 #[derive(Debug, PartialEq, Copy, Clone, Default)]
 pub struct LineState {
@@ -11,19 +13,21 @@ pub struct LineState {
     end: Option<CanvasVector2>,
     draw_now: bool,
     color: Color,
+    antialiasing: bool,
 }
 
 impl UpdateExecuteAction for LineState {
-    fn update_pressed(&mut self, user_state: &UserState, _: &mut RaylibHandle, _: &RaylibThread) {
+    fn update_pressed(&mut self, user_state: &UserState, _: &InputEvent, _: &PixelBuffer) {
         if self.start == None {
             self.start = Option::from(user_state.to_canvas(user_state.mouse_position));
         } else {
             self.end = Option::from(user_state.to_canvas(user_state.mouse_position));
         }
         self.color = user_state.current_colors[0];
+        self.antialiasing = user_state.antialiasing;
     }
 
-    fn update_unpressed(&mut self, _: &UserState, _: &mut RaylibHandle, _: &RaylibThread) {
+    fn update_unpressed(&mut self, _: &UserState, _: &InputEvent, _: &PixelBuffer) {
         if self.start != None && self.end != None {
             self.draw_now = true;
         }
@@ -37,16 +41,31 @@ impl UpdateExecuteAction for LineState {
         }
     }
 
-    fn draw(&mut self, image: &mut Image) -> bool {
+    fn draw(&mut self, image: &mut PixelBuffer) -> bool {
         if self.draw_now {
-            image.draw_line_v(self.start.unwrap().0, self.end.unwrap().0, self.color);
+            if self.antialiasing {
+                AntialiasedImage::new(image).draw_line_v(self.start.unwrap().0, self.end.unwrap().0,
+                                                         self.color);
+            } else {
+                image.draw_line_v(self.start.unwrap().0, self.end.unwrap().0, self.color);
+            }
             return true;
         }
 
         return false;
     }
 
-    fn draw_state(&self, user_state: &UserState, handle: &mut RaylibDrawHandle, _: &RaylibThread) {
+    fn get_color(&self) -> Option<Color> {
+        None
+    }
+
+    fn get_drawn_colors(&self) -> Vec<Color> {
+        vec![self.color]
+    }
+}
+
+impl ToolPreview for LineState {
+    fn draw_state(&self, user_state: &UserState, handle: &mut RaylibDrawHandle) {
         if !(self.start != None && self.end != None) {
             return;
         }
@@ -57,7 +76,7 @@ impl UpdateExecuteAction for LineState {
         handle.draw_line_v(p0.0, p1.0, self.color);
     }
 
-    fn get_color(&self) -> Option<Color> {
-        None
+    fn cursor(&self, _: &UserState) -> Option<ToolCursor> {
+        Some(ToolCursor::Crosshair)
     }
 }
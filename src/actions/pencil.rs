@@ -1,9 +1,10 @@
+use crate::actions::input::InputEvent;
+use crate::actions::preview::{ToolCursor, ToolPreview};
 use crate::actions::update_execute_action::UpdateExecuteAction;
+use crate::pixel_buffer::PixelBuffer;
 use crate::user_state::{CanvasVector2, UserState};
 use raylib::color::Color;
 use raylib::drawing::RaylibDrawHandle;
-use raylib::prelude::Image;
-use raylib::{RaylibHandle, RaylibThread};
 
 #[derive(Debug, PartialEq, Copy, Clone, Default)]
 pub struct PencilState {
@@ -13,13 +14,13 @@ pub struct PencilState {
 }
 
 impl UpdateExecuteAction for PencilState {
-    fn update_pressed(&mut self, user_state: &UserState, _: &mut RaylibHandle, _: &RaylibThread) {
+    fn update_pressed(&mut self, user_state: &UserState, _: &InputEvent, _: &PixelBuffer) {
         self.old_mouse_position = self.mouse_position;
         self.mouse_position = Option::from(user_state.to_canvas(user_state.mouse_position));
         self.color = user_state.current_colors[0];
     }
 
-    fn update_unpressed(&mut self, user_state: &UserState, _: &mut RaylibHandle, _: &RaylibThread) {
+    fn update_unpressed(&mut self, user_state: &UserState, _: &InputEvent, _: &PixelBuffer) {
         self.old_mouse_position = None;
         self.mouse_position = None;
         self.color = user_state.current_colors[0];
@@ -27,7 +28,7 @@ impl UpdateExecuteAction for PencilState {
 
     fn update_after_draw(&mut self, _: &UserState) {}
 
-    fn draw(&mut self, image: &mut Image) -> bool {
+    fn draw(&mut self, image: &mut PixelBuffer) -> bool {
         match (self.old_mouse_position, self.mouse_position) {
             (None, None) => {
                 // Nothing
@@ -46,9 +47,19 @@ impl UpdateExecuteAction for PencilState {
         }
     }
 
-    fn draw_state(&self, _: &UserState, _: &mut RaylibDrawHandle, _: &RaylibThread) {}
-
     fn get_color(&self) -> Option<Color> {
         None
     }
+
+    fn get_drawn_colors(&self) -> Vec<Color> {
+        vec![self.color]
+    }
+}
+
+impl ToolPreview for PencilState {
+    fn draw_state(&self, _: &UserState, _: &mut RaylibDrawHandle) {}
+
+    fn cursor(&self, _: &UserState) -> Option<ToolCursor> {
+        Some(ToolCursor::Square(1))
+    }
 }
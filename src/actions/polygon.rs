@@ -1,9 +1,12 @@
+use crate::actions::input::InputEvent;
+use crate::actions::preview::{ToolCursor, ToolPreview};
 use crate::actions::update_execute_action::UpdateExecuteAction;
+use crate::pixel_buffer::PixelBuffer;
+use crate::antialiasing::AntialiasedImage;
 use crate::user_state::{CanvasVector2, UserState};
 use raylib::color::Color;
 use raylib::drawing::{RaylibDraw, RaylibDrawHandle};
-use raylib::prelude::Image;
-use raylib::{RaylibHandle, RaylibThread};
+use raylib::math::Vector2;
 
 #[derive(Debug, PartialEq, Clone, Default)]
 pub struct PolygonState {
@@ -11,12 +14,25 @@ pub struct PolygonState {
     // Invariant: new_point != None => points.len() >= 1
     new_point: Option<CanvasVector2>,
     color: Color,
+    antialiasing: bool,
 }
 
 const POLYGON_CLOSE_DISTANCE: f32 = 5.0;
 
+/// Closed outline through the points, from the last one back to the first
+pub fn draw_outline(image: &mut PixelBuffer, points: &[Vector2], color: Color, antialiasing: bool) {
+    let closing = points.last().zip(points.first());
+    for (p0, p1) in points.windows(2).map(|pair| (&pair[0], &pair[1])).chain(closing) {
+        if antialiasing {
+            AntialiasedImage::new(image).draw_line_v(*p0, *p1, color);
+        } else {
+            image.draw_line_v(*p0, *p1, color);
+        }
+    }
+}
+
 impl UpdateExecuteAction for PolygonState {
-    fn update_pressed(&mut self, user_state: &UserState, _: &mut RaylibHandle, _: &RaylibThread) {
+    fn update_pressed(&mut self, user_state: &UserState, _: &InputEvent, _: &PixelBuffer) {
         // First point gets added immediately, after the first, add them on press
         if self.points.is_empty() {
             self.points.push(user_state.to_canvas(user_state.mouse_position));
@@ -24,9 +40,10 @@ impl UpdateExecuteAction for PolygonState {
             self.new_point = Some(user_state.to_canvas(user_state.mouse_position));
         }
         self.color = user_state.current_colors[0];
+        self.antialiasing = user_state.antialiasing;
     }
 
-    fn update_unpressed(&mut self, _: &UserState, _: &mut RaylibHandle, _: &RaylibThread) {
+    fn update_unpressed(&mut self, _: &UserState, _: &InputEvent, _: &PixelBuffer) {
         if let Some(new_point) = self.new_point {
             let first = self.points.first().unwrap();
 
@@ -48,7 +65,7 @@ impl UpdateExecuteAction for PolygonState {
     fn update_after_draw(&mut self, _: &UserState) {
     }
 
-    fn draw(&mut self, image: &mut Image) -> bool {
+    fn draw(&mut self, image: &mut PixelBuffer) -> bool {
         if self.points.len() < 2 {
             return false;
         }
@@ -64,19 +81,10 @@ impl UpdateExecuteAction for PolygonState {
 
         {
             // Ignore the last point (intended behaviour)
-            let actual_points = &self.points[0..self.points.len()-1];
-            let actual_last = self.points[actual_points.len() - 1].0;
-            let actual_first = self.points.first().unwrap().0;
-
-            for i in 1..actual_points.len() {
-                let p0 = actual_points[i - 1].0;
-                let p1 = actual_points[i].0;
-
-                image.draw_line_v(p0, p1, self.color);
-            }
-
-            // Ignore the last point (intended behaviour)
-            image.draw_line_v(actual_last, actual_first, self.color);
+            let actual_points: Vec<Vector2> = self.points[0..self.points.len()-1].iter()
+                .map(|point| point.0)
+                .collect();
+            draw_outline(image, &actual_points, self.color, self.antialiasing);
 
             self.new_point = None;
             self.points.clear();
@@ -84,7 +92,17 @@ impl UpdateExecuteAction for PolygonState {
         }
     }
 
-    fn draw_state(&self, user_state: &UserState, handle: &mut RaylibDrawHandle, _: &RaylibThread) {
+    fn get_color(&self) -> Option<Color> {
+        None
+    }
+
+    fn get_drawn_colors(&self) -> Vec<Color> {
+        vec![self.color]
+    }
+}
+
+impl ToolPreview for PolygonState {
+    fn draw_state(&self, user_state: &UserState, handle: &mut RaylibDrawHandle) {
         for i in 1..self.points.len() {
             let p0 = user_state.to_window(self.points[i-1]).0;
             let p1 = user_state.to_window(self.points[i]).0;
@@ -100,7 +118,7 @@ impl UpdateExecuteAction for PolygonState {
         }
     }
 
-    fn get_color(&self) -> Option<Color> {
-        None
+    fn cursor(&self, _: &UserState) -> Option<ToolCursor> {
+        Some(ToolCursor::Crosshair)
     }
 }
@@ -0,0 +1,75 @@
+use raylib::color::Color;
+use raylib::drawing::{RaylibDraw, RaylibDrawHandle};
+use raylib::math::Vector2;
+use raylib::{RaylibHandle, RaylibThread};
+use crate::actions::update_execute_action::UpdateExecuteAction;
+use crate::user_state::{CanvasVector2, UserState};
+
+/// Pixels between the crosshair's center and its arms, so the pixel under it stays visible
+const CROSSHAIR_GAP: i32 = 2;
+const CROSSHAIR_ARM: i32 = 6;
+
+/// Shape drawn at the mouse over the canvas, in place of the system cursor
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ToolCursor {
+    /// Outline around the square of pixels a click paints, that many pixels wide
+    Square(i32),
+    /// Circle of that radius, around where a click paints
+    Circle(f32),
+    /// Lines crossing on the pixel under the mouse
+    Crosshair,
+}
+
+impl ToolCursor {
+    /// Black with a white edge, so it shows on dark and light colors
+    pub fn draw(self, user_state: &UserState, handle: &mut RaylibDrawHandle) {
+        let mouse = user_state.to_canvas(user_state.mouse_position).0;
+        let pixel = user_state.to_window(CanvasVector2(Vector2::new(mouse.x.floor(), mouse.y.floor()))).0;
+        let (x, y) = (pixel.x as i32, pixel.y as i32);
+        match self {
+            ToolCursor::Square(size) => {
+                let (left, top) = (x - size / 2, y - size / 2);
+                handle.draw_rectangle_lines(left - 2, top - 2, size + 4, size + 4, Color::WHITE);
+                handle.draw_rectangle_lines(left - 1, top - 1, size + 2, size + 2, Color::BLACK);
+            },
+            ToolCursor::Circle(radius) => {
+                handle.draw_circle_lines(x, y, radius + 1f32, Color::WHITE);
+                handle.draw_circle_lines(x, y, radius, Color::BLACK);
+            },
+            ToolCursor::Crosshair => {
+                let (near, far) = (CROSSHAIR_GAP + 1, CROSSHAIR_GAP + CROSSHAIR_ARM);
+                let arms = [
+                    (x + near, y, CROSSHAIR_ARM, 1),
+                    (x - far, y, CROSSHAIR_ARM, 1),
+                    (x, y + near, 1, CROSSHAIR_ARM),
+                    (x, y - far, 1, CROSSHAIR_ARM),
+                ];
+                for (left, top, width, height) in arms {
+                    handle.draw_rectangle(left - 1, top - 1, width + 2, height + 2, Color::WHITE);
+                }
+                for (left, top, width, height) in arms {
+                    handle.draw_rectangle(left, top, width, height, Color::BLACK);
+                }
+            },
+        }
+    }
+}
+
+/// Window rendering of a tool, kept apart from the tool logic so that can run without a display
+pub trait ToolPreview {
+    /// Called once per frame before drawing, to upload anything the preview needs to the GPU
+    fn prepare(&mut self, _rl: &mut RaylibHandle, _thread: &RaylibThread) {}
+
+    /// Draw onto the temporary layer over the canvas, this layer will be cleared after each frame
+    fn draw_state(&self, user_state: &UserState, handle: &mut RaylibDrawHandle);
+
+    /// Shape shown at the mouse while it is over the canvas, None keeps the system cursor
+    fn cursor(&self, _user_state: &UserState) -> Option<ToolCursor> {
+        None
+    }
+}
+
+/// A tool usable from the window: its logic and its preview
+pub trait Tool: UpdateExecuteAction + ToolPreview {}
+
+impl<T: UpdateExecuteAction + ToolPreview> Tool for T {}
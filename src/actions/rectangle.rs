@@ -1,31 +1,37 @@
+use crate::actions::fill_mode::FillMode;
+use crate::actions::input::InputEvent;
+use crate::actions::preview::{ToolCursor, ToolPreview};
 use crate::actions::update_execute_action::UpdateExecuteAction;
+use crate::pixel_buffer::PixelBuffer;
 use crate::user_state::{CanvasVector2, UserState};
 use raylib::color::Color;
 use raylib::drawing::{RaylibDraw, RaylibDrawHandle};
 use raylib::math::Rectangle;
-use raylib::prelude::Image;
-use raylib::{RaylibHandle, RaylibThread};
 
 #[derive(Debug, PartialEq, Copy, Clone, Default)]
 pub struct RectangleState {
     start: Option<CanvasVector2>,
     end: Option<CanvasVector2>,
     draw_now: bool,
-    color: Color,
+    colors: [Color; 2],
+    line_width: i32,
+    fill_mode: FillMode,
 }
 
 impl UpdateExecuteAction for RectangleState {
-    fn update_pressed(&mut self, user_state: &UserState, _: &mut RaylibHandle, _: &RaylibThread) {
+    fn update_pressed(&mut self, user_state: &UserState, _: &InputEvent, _: &PixelBuffer) {
         if self.start == None {
             self.start = Option::from(user_state.to_canvas(user_state.mouse_position));
         } else {
             self.end = Option::from(user_state.to_canvas(user_state.mouse_position));
         }
 
-        self.color = user_state.current_colors[0];
+        self.colors = user_state.current_colors;
+        self.line_width = user_state.line_width;
+        self.fill_mode = user_state.fill_mode;
     }
 
-    fn update_unpressed(&mut self, _: &UserState, _: &mut RaylibHandle, _: &RaylibThread) {
+    fn update_unpressed(&mut self, _: &UserState, _: &InputEvent, _: &PixelBuffer) {
         if self.start != None && self.end != None {
             self.draw_now = true;
         }
@@ -39,7 +45,7 @@ impl UpdateExecuteAction for RectangleState {
         }
     }
 
-    fn draw(&mut self, image: &mut Image) -> bool {
+    fn draw(&mut self, image: &mut PixelBuffer) -> bool {
         if !(self.draw_now) {
             return false;
         }
@@ -55,11 +61,29 @@ impl UpdateExecuteAction for RectangleState {
             width: size.x.abs(),
             height: size.y.abs(),
         };
-        image.draw_rectangle_lines(rectangle, 1, self.color);
+        let (outline, fill) = self.fill_mode.colors(self.colors);
+        if let Some(fill) = fill {
+            image.draw_rectangle(rectangle.x as i32, rectangle.y as i32,
+                                 rectangle.width as i32, rectangle.height as i32, fill);
+        }
+        if let Some(outline) = outline {
+            image.draw_rectangle_lines(rectangle, self.line_width, outline);
+        }
         true
     }
 
-    fn draw_state(&self, user_state: &UserState, handle: &mut RaylibDrawHandle, _: &RaylibThread) {
+    fn get_color(&self) -> Option<Color> {
+        None
+    }
+
+    fn get_drawn_colors(&self) -> Vec<Color> {
+        let (outline, fill) = self.fill_mode.colors(self.colors);
+        outline.into_iter().chain(fill).collect()
+    }
+}
+
+impl ToolPreview for RectangleState {
+    fn draw_state(&self, user_state: &UserState, handle: &mut RaylibDrawHandle) {
         if !(self.start != None && self.end != None) {
             return;
         }
@@ -75,10 +99,16 @@ impl UpdateExecuteAction for RectangleState {
             width: size.x.abs(),
             height: size.y.abs(),
         };
-        handle.draw_rectangle_lines_ex(rectangle, 1f32, self.color);
+        let (outline, fill) = self.fill_mode.colors(self.colors);
+        if let Some(fill) = fill {
+            handle.draw_rectangle_rec(rectangle, fill);
+        }
+        if let Some(outline) = outline {
+            handle.draw_rectangle_lines_ex(rectangle, self.line_width as f32, outline);
+        }
     }
 
-    fn get_color(&self) -> Option<Color> {
-        None
+    fn cursor(&self, _: &UserState) -> Option<ToolCursor> {
+        Some(ToolCursor::Crosshair)
     }
 }
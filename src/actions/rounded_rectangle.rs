@@ -1,106 +1,59 @@
+use crate::actions::fill_mode::FillMode;
+use crate::actions::input::InputEvent;
+use crate::actions::preview::{ToolCursor, ToolPreview};
 use crate::actions::update_execute_action::UpdateExecuteAction;
+use crate::pixel_buffer::PixelBuffer;
+use crate::antialiasing::AntialiasedImage;
+use crate::rasterization;
 use crate::user_state::{CanvasVector2, UserState};
 use raylib::color::Color;
 use raylib::drawing::{RaylibDraw, RaylibDrawHandle};
-use raylib::math::{Rectangle, Vector2};
-use raylib::prelude::Image;
-use raylib::{RaylibHandle, RaylibThread};
-use std::f32::consts::TAU;
+use raylib::math::Vector2;
+
+/// Corner radius the window starts with, in pixels
+pub const CORNER_RADIUS_DEFAULT: i32 = 10;
+/// Largest corner radius the options box goes to
+pub const CORNER_RADIUS_MAX: i32 = 100;
 
 #[derive(Debug, PartialEq, Copy, Clone, Default)]
 pub struct RoundedRectangleState {
     start: Option<CanvasVector2>,
     end: Option<CanvasVector2>,
     draw_now: bool,
-    color: Color,
-}
-
-macro_rules! draw_rounded_rectangle {
-    ($base: ident, $p0: ident, $p1: ident, $color: ident) => {
-        let size = $p0 - $p1;
-
-        let r = Rectangle {
-            x: f32::min($p0.x, $p1.x),
-            y: f32::min($p0.y, $p1.y),
-            width: size.x.abs(),
-            height: size.y.abs(),
-        };
-
-        let segments = 10;
-        let line_thick = 1.0;
-        let step_length = (TAU / 4.0)/(segments as f32);
-        let radius = f32::min(10.0, f32::min(r.height/2.0, r.width/2.0));
-        let outer_radius = radius + line_thick;
-        let inner_radius = radius;
-        let angles: [f32; 4] = [TAU / 2.0, TAU / 4.0 * 3.0, 0.0, TAU / 4.0];
-        let point: [Vector2; 16] = [
-            Vector2::new(r.x + inner_radius, r.y - line_thick),
-            Vector2::new((r.x + r.width) - inner_radius, r.y - line_thick),
-            Vector2::new( r.x + r.width + line_thick, r.y + inner_radius ),
-            Vector2::new(r.x + r.width + line_thick, (r.y + r.height) - inner_radius),
-            Vector2::new((r.x + r.width) - inner_radius, r.y + r.height + line_thick),
-            Vector2::new(r.x + inner_radius, r.y + r.height + line_thick),
-            Vector2::new( r.x - line_thick, (r.y + r.height) - inner_radius),
-            Vector2::new(r.x - line_thick, r.y + inner_radius),
-            Vector2::new(r.x + inner_radius, r.y),
-            Vector2::new((r.x + r.width) - inner_radius, r.y),
-            Vector2::new( r.x + r.width, r.y + inner_radius ),
-            Vector2::new(r.x + r.width, (r.y + r.height) - inner_radius),
-            Vector2::new((r.x + r.width) - inner_radius, r.y + r.height),
-            Vector2::new(r.x + inner_radius, r.y + r.height),
-            Vector2::new(r.x, (r.y + r.height) - inner_radius),
-            Vector2::new(r.x, r.y + inner_radius),
-        ];
-        let centers: [Vector2; 4] = [
-            Vector2::new(r.x + inner_radius, r.y + inner_radius),
-            Vector2::new((r.x + r.width) - inner_radius, r.y + inner_radius),
-            Vector2::new((r.x + r.width) - inner_radius, (r.y + r.height) - inner_radius),
-            Vector2::new(r.x + inner_radius, (r.y + r.height) - inner_radius),
-        ];
-
-        for k in 0..4 {
-            let mut angle = angles[k];
-            let center = centers[k];
-
-            for _ in 0..segments {
-                $base.draw_line_v(
-                    Vector2::new(
-                        center.x + f32::cos(angle)*outer_radius,
-                        center.y + f32::sin(angle)*outer_radius
-                    ),
-                    Vector2::new(
-                        center.x + f32::cos(angle + step_length)*outer_radius,
-                        center.y + f32::sin(angle + step_length)*outer_radius
-                    ),
-                    $color,
-                );
-                angle += step_length;
-            }
-        }
-
-        // And now the remaining 4 lines
-        for i in (0..8).step_by(2) {
-            $base.draw_line_v(
-                Vector2::new(point[i].x, point[i].y),
-                Vector2::new(point[i + 1].x, point[i + 1].y),
-                $color,
-            );
-        }
-    };
+    colors: [Color; 2],
+    antialiasing: bool,
+    line_width: i32,
+    fill_mode: FillMode,
+    radius: i32,
+    /// Mouse position and radius when the corner radius drag started
+    radius_drag: Option<(CanvasVector2, i32)>,
 }
 
 impl UpdateExecuteAction for RoundedRectangleState {
-    fn update_pressed(&mut self, user_state: &UserState, _: &mut RaylibHandle, _: &RaylibThread) {
+    fn update_pressed(&mut self, user_state: &UserState, input: &InputEvent, _: &PixelBuffer) {
+        let mouse_position = user_state.to_canvas(user_state.mouse_position);
+        // Hold control while dragging to change the corner radius instead of the size
+        let modifier_down = input.modifiers.control;
+
         if self.start == None {
-            self.start = Option::from(user_state.to_canvas(user_state.mouse_position));
+            self.start = Option::from(mouse_position);
+            self.radius = user_state.corner_radius;
+        } else if self.end != None && modifier_down {
+            let (anchor, radius) = *self.radius_drag.get_or_insert((mouse_position, self.radius));
+            self.radius = (radius + (mouse_position.0.x - anchor.0.x) as i32).max(0);
         } else {
-            self.end = Option::from(user_state.to_canvas(user_state.mouse_position));
+            self.radius_drag = None;
+            self.end = Option::from(mouse_position);
         }
 
-        self.color = user_state.current_colors[0];
+        self.colors = user_state.current_colors;
+        self.antialiasing = user_state.antialiasing;
+        self.line_width = user_state.line_width;
+        self.fill_mode = user_state.fill_mode;
     }
 
-    fn update_unpressed(&mut self, _: &UserState, _: &mut RaylibHandle, _: &RaylibThread) {
+    fn update_unpressed(&mut self, _: &UserState, _: &InputEvent, _: &PixelBuffer) {
+        self.radius_drag = None;
         if self.start != None && self.end != None {
             self.draw_now = true;
         }
@@ -114,34 +67,86 @@ impl UpdateExecuteAction for RoundedRectangleState {
         }
     }
 
-    fn draw(&mut self, image: &mut Image) -> bool {
+    fn draw(&mut self, image: &mut PixelBuffer) -> bool {
         if !(self.draw_now) {
             return false;
         }
 
-        if let Some(start) = self.start {
-            if let Some(end) = self.end {
-                let p0 = start.0;
-                let p1 = end.0;
-                let color = self.color;
-                draw_rounded_rectangle!(image, p0, p1, color);
+        let p0 = self.start.unwrap().0;
+        let p1 = self.end.unwrap().0;
+        let (x0, y0, x1, y1) = (p0.x as i32, p0.y as i32, p1.x as i32, p1.y as i32);
+        let (outline, fill) = self.fill_mode.colors(self.colors);
+
+        if let Some(fill) = fill {
+            for span in rasterization::rounded_rectangle_spans(x0, y0, x1, y1, self.radius) {
+                image.draw_rectangle(span.x_start, span.y, span.x_end - span.x_start + 1, 1, fill);
+            }
+        }
+
+        if let Some(outline) = outline {
+            if self.antialiasing {
+                let top_left = Vector2::new(p0.x.min(p1.x), p0.y.min(p1.y));
+                let bottom_right = Vector2::new(p0.x.max(p1.x), p0.y.max(p1.y));
+
+                // Past half the smaller side the rings would turn inside out and draw over each other
+                let size = bottom_right - top_left;
+                let max_inset = (size.x.min(size.y) / 2f32) as i32;
+
+                let mut antialiased = AntialiasedImage::new(image);
+                for inset in 0..self.line_width.clamp(1, max_inset + 1) {
+                    let offset = Vector2::new(inset as f32, inset as f32);
+                    antialiased.draw_rounded_rectangle_lines(top_left + offset, bottom_right - offset,
+                                                             (self.radius - inset) as f32, outline);
+                }
+            } else {
+                let pixels = rasterization::rounded_rectangle_stroke(x0, y0, x1, y1, self.radius,
+                                                                     self.line_width);
+                for (x, y) in pixels {
+                    image.draw_pixel(x, y, outline);
+                }
             }
         }
         true
     }
 
-    fn draw_state(&self, user_state: &UserState, handle: &mut RaylibDrawHandle, _: &RaylibThread) {
+    fn get_color(&self) -> Option<Color> {
+        None
+    }
+
+    fn get_drawn_colors(&self) -> Vec<Color> {
+        let (outline, fill) = self.fill_mode.colors(self.colors);
+        outline.into_iter().chain(fill).collect()
+    }
+}
+
+impl ToolPreview for RoundedRectangleState {
+    fn draw_state(&self, user_state: &UserState, handle: &mut RaylibDrawHandle) {
         if let Some(start) = self.start {
             if let Some(end) = self.end {
                 let p0 = user_state.to_window(start).0;
                 let p1 = user_state.to_window(end).0;
-                let color = self.color;
-                draw_rounded_rectangle!(handle, p0, p1, color);
+                let (x0, y0, x1, y1) = (p0.x as i32, p0.y as i32, p1.x as i32, p1.y as i32);
+                let (outline, fill) = self.fill_mode.colors(self.colors);
+
+                if let Some(fill) = fill {
+                    for span in rasterization::rounded_rectangle_spans(x0, y0, x1, y1, self.radius) {
+                        handle.draw_rectangle(span.x_start, span.y, span.x_end - span.x_start + 1, 1,
+                                              fill);
+                    }
+                }
+
+                if let Some(outline) = outline {
+                    let pixels = rasterization::rounded_rectangle_stroke(x0, y0, x1, y1, self.radius,
+                                                                         self.line_width);
+                    for (x, y) in pixels {
+                        handle.draw_pixel(x, y, outline);
+                    }
+                }
             }
         }
     }
 
-    fn get_color(&self) -> Option<Color> {
-        None
+    fn cursor(&self, _: &UserState) -> Option<ToolCursor> {
+        Some(ToolCursor::Crosshair)
     }
 }
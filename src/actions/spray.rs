@@ -1,21 +1,32 @@
+use crate::actions::input::InputEvent;
+use crate::actions::preview::{ToolCursor, ToolPreview};
 use crate::actions::update_execute_action::UpdateExecuteAction;
+use crate::pixel_buffer::PixelBuffer;
 use crate::user_state::{CanvasVector2, UserState};
 use raylib::color::Color;
 use raylib::drawing::RaylibDrawHandle;
 use raylib::math::Vector2;
-use raylib::prelude::Image;
-use raylib::{RaylibHandle, RaylibThread};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use std::f32::consts::TAU;
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+/// Particles per second emitted by default
+pub const SPRAY_DENSITY_DEFAULT: f32 = 600f32;
+pub const SPRAY_DENSITY_MIN: f32 = 60f32;
+pub const SPRAY_DENSITY_MAX: f32 = 6000f32;
+
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
 pub enum SpraySize {
+    #[default]
     SizeOne,
     SizeTwo,
     SizeThree,
 }
 
 impl SpraySize {
-    fn radius(self) -> f32 {
+    pub const ALL: [SpraySize; 3] = [SpraySize::SizeOne, SpraySize::SizeTwo, SpraySize::SizeThree];
+
+    pub fn radius(self) -> f32 {
         match self {
             SpraySize::SizeOne => 5f32,
             SpraySize::SizeTwo => 10f32,
@@ -24,46 +35,99 @@ impl SpraySize {
     }
 }
 
-#[derive(Debug, PartialEq, Copy, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct SprayState {
     mouse_position: Option<CanvasVector2>,
     radius: f32,
     color: Color,
+    /// Particles per second
+    density: f32,
+    /// Particles owed since the last draw, the fractional part carries over to the next frame
+    pending: f32,
+    rng: ChaCha8Rng,
+}
+
+impl Default for SprayState {
+    fn default() -> Self {
+        Self::with_seed(rand::random())
+    }
+}
+
+impl SprayState {
+    /// Create a spray whose output is fully determined by the seed and the input it receives
+    pub fn with_seed(seed: u64) -> Self {
+        SprayState {
+            mouse_position: None,
+            radius: SpraySize::default().radius(),
+            color: Color::BLACK,
+            density: SPRAY_DENSITY_DEFAULT,
+            pending: 0f32,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    /// Accumulate the particles to emit during `elapsed` seconds
+    pub fn advance(&mut self, elapsed: f32) {
+        self.pending += self.density * elapsed;
+    }
 }
 
 impl UpdateExecuteAction for SprayState {
-    fn update_pressed(&mut self, user_state: &UserState, _: &mut RaylibHandle, _: &RaylibThread) {
+    fn update_pressed(&mut self, user_state: &UserState, input: &InputEvent, _: &PixelBuffer) {
         self.mouse_position = Option::from(user_state.to_canvas(user_state.mouse_position));
         self.radius = user_state.spray_size.radius();
+        self.density = user_state.spray_density;
         self.color = user_state.current_colors[0];
+        self.advance(input.elapsed);
     }
 
-    fn update_unpressed(&mut self, _: &UserState, _: &mut RaylibHandle, _: &RaylibThread) {
+    fn update_unpressed(&mut self, _: &UserState, _: &InputEvent, _: &PixelBuffer) {
         self.mouse_position = None;
+        self.pending = 0f32;
     }
 
     fn update_after_draw(&mut self, _: &UserState) {}
 
-    fn draw(&mut self, image: &mut Image) -> bool {
+    fn draw(&mut self, image: &mut PixelBuffer) -> bool {
         match self.mouse_position {
             Some(mouse_position_in_canvas) => {
-                let theta = rand::random::<f32>() * TAU;
-                let radius = rand::random::<f32>() * self.radius;
-                let position = Vector2 {
-                    x: radius * f32::cos(theta),
-                    y: radius * f32::sin(theta),
-                };
-
-                image.draw_pixel_v(mouse_position_in_canvas.0 + position, self.color);
-                true
+                let particles = self.pending.floor();
+                self.pending -= particles;
+
+                for _ in 0..particles as u32 {
+                    let theta = self.rng.random::<f32>() * TAU;
+                    let radius = self.rng.random::<f32>() * self.radius;
+                    let position = Vector2 {
+                        x: radius * f32::cos(theta),
+                        y: radius * f32::sin(theta),
+                    };
+
+                    image.draw_pixel_v(mouse_position_in_canvas.0 + position, self.color);
+                }
+
+                particles > 0f32
             }
             None => false,
         }
     }
 
-    fn draw_state(&self, _: &UserState, _: &mut RaylibDrawHandle, _: &RaylibThread) {}
-
     fn get_color(&self) -> Option<Color> {
         None
     }
+
+    fn get_drawn_colors(&self) -> Vec<Color> {
+        vec![self.color]
+    }
+
+    fn status_text(&self, user_state: &UserState) -> Option<String> {
+        Some(format!("Spray: {} particles/s", user_state.spray_density))
+    }
+}
+
+impl ToolPreview for SprayState {
+    fn draw_state(&self, _: &UserState, _: &mut RaylibDrawHandle) {}
+
+    fn cursor(&self, user_state: &UserState) -> Option<ToolCursor> {
+        Some(ToolCursor::Circle(user_state.spray_size.radius()))
+    }
 }
@@ -0,0 +1,219 @@
+use raylib::consts::KeyboardKey;
+use crate::actions::brush::BrushState;
+use crate::actions::bucket::BucketState;
+use crate::actions::color_picker::ColorPickerState;
+use crate::actions::ellipse::EllipseState;
+use crate::actions::eraser::EraserState;
+use crate::actions::line::LineState;
+use crate::actions::pencil::PencilState;
+use crate::actions::polygon::PolygonState;
+use crate::actions::rectangle::RectangleState;
+use crate::actions::rounded_rectangle::RoundedRectangleState;
+use crate::actions::select::SelectState;
+use crate::actions::spray::SprayState;
+use crate::actions::preview::Tool;
+
+/// Setting a tool reads from the user state, shown in the options box
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ToolOption {
+    SpraySize,
+    SampleSize,
+    Antialiasing,
+    LineWidth,
+    FillMode,
+    CornerRadius,
+}
+
+/// Makes a tool from a seed, which only tools drawing at random use
+type CreateTool = fn(u64) -> Box<dyn Tool>;
+
+fn create<T: Tool + Default + 'static>(_seed: u64) -> Box<dyn Tool> {
+    Box::<T>::default()
+}
+
+fn create_spray(seed: u64) -> Box<dyn Tool> {
+    Box::new(SprayState::with_seed(seed))
+}
+
+/// A toolbar button and the tool behind it
+pub struct ToolEntry {
+    /// Index of the icon in the tools atlas
+    pub icon: usize,
+    pub name: &'static str,
+    /// Description shown in the status bar while the mouse is on the button
+    pub tooltip: &'static str,
+    /// Key selecting the tool, unless the keymap file binds another
+    pub shortcut: Option<KeyboardKey>,
+    /// Options shown while the tool is selected, from top to bottom
+    pub options: &'static [ToolOption],
+    create: Option<CreateTool>,
+    /// Seed of the last tool made, stepped on every reset
+    seed: u64,
+    /// None for tools that are not implemented yet
+    pub tool: Option<Box<dyn Tool>>,
+}
+
+impl ToolEntry {
+    pub fn new(icon: usize, name: &'static str, tooltip: &'static str, create: CreateTool) -> Self {
+        ToolEntry {
+            icon,
+            name,
+            tooltip,
+            shortcut: None,
+            options: &[],
+            create: Some(create),
+            seed: 0,
+            tool: Some(create(0)),
+        }
+    }
+
+    /// Button shown greyed out, for a tool that can't be used yet
+    pub fn disabled(icon: usize, name: &'static str, tooltip: &'static str) -> Self {
+        ToolEntry {
+            icon,
+            name,
+            tooltip,
+            shortcut: None,
+            options: &[],
+            create: None,
+            seed: 0,
+            tool: None,
+        }
+    }
+
+    pub fn with_shortcut(mut self, shortcut: KeyboardKey) -> Self {
+        self.shortcut = Some(shortcut);
+        self
+    }
+
+    pub fn with_options(mut self, options: &'static [ToolOption]) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.create.is_some()
+    }
+
+    /// Start the tool over, as when its button is pressed
+    pub fn reset(&mut self) {
+        self.seed = self.seed.wrapping_add(1);
+        self.tool = self.create.map(|create| create(self.seed));
+    }
+}
+
+/// Tools in toolbar order
+#[derive(Default)]
+pub struct ToolRegistry {
+    entries: Vec<ToolEntry>,
+}
+
+impl ToolRegistry {
+    /// Add a tool after the ones already registered, returning its index
+    pub fn register(&mut self, entry: ToolEntry) -> usize {
+        self.entries.push(entry);
+        self.entries.len() - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&ToolEntry> {
+        self.entries.get(index)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut ToolEntry> {
+        self.entries.get_mut(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ToolEntry> {
+        self.entries.iter()
+    }
+
+    /// Make every tool again from the seed, so the same input draws the same random strokes
+    pub fn seed(&mut self, seed: u64) {
+        for entry in &mut self.entries {
+            entry.seed = seed;
+            entry.reset();
+        }
+    }
+
+    /// Index of the enabled tool with this shortcut
+    pub fn find_shortcut(&self, key: KeyboardKey) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.is_enabled() && entry.shortcut == Some(key))
+    }
+
+    /// The classic paint toolbox, in the order of the icons in the atlas
+    pub fn with_default_tools() -> Self {
+        use ToolOption::*;
+
+        let mut registry = ToolRegistry::default();
+        registry.register(ToolEntry::disabled(0, "Free-Form Select", "Selects a free-form part of the picture"));
+        registry.register(
+            ToolEntry::new(1, "Select", "Selects a rectangular part of the picture", create::<SelectState>)
+                .with_shortcut(KeyboardKey::KEY_S)
+        );
+        registry.register(
+            ToolEntry::new(2, "Eraser", "Erases a portion of the picture", create::<EraserState>)
+                .with_shortcut(KeyboardKey::KEY_E)
+        );
+        registry.register(
+            ToolEntry::new(3, "Fill With Color", "Fills an area with the current color", create::<BucketState>)
+                .with_shortcut(KeyboardKey::KEY_F)
+        );
+        registry.register(
+            ToolEntry::new(4, "Pick Color", "Picks up a color from the picture", create::<ColorPickerState>)
+                .with_shortcut(KeyboardKey::KEY_I)
+                .with_options(&[SampleSize])
+        );
+        registry.register(ToolEntry::disabled(5, "Magnifier", "Changes the magnification"));
+        registry.register(
+            ToolEntry::new(6, "Pencil", "Draws a free-form line one pixel wide", create::<PencilState>)
+                .with_shortcut(KeyboardKey::KEY_P)
+        );
+        registry.register(
+            ToolEntry::new(7, "Brush", "Draws using a brush with the selected shape and size", create::<BrushState>)
+                .with_shortcut(KeyboardKey::KEY_B)
+        );
+        registry.register(
+            ToolEntry::new(8, "Airbrush", "Draws using an airbrush of the selected size", create_spray)
+                .with_shortcut(KeyboardKey::KEY_A)
+                .with_options(&[SpraySize])
+        );
+        registry.register(ToolEntry::disabled(9, "Text", "Inserts text into the picture"));
+        registry.register(
+            ToolEntry::new(10, "Line", "Draws a straight line with the selected line width", create::<LineState>)
+                .with_shortcut(KeyboardKey::KEY_L)
+                .with_options(&[Antialiasing])
+        );
+        registry.register(ToolEntry::disabled(11, "Curve", "Draws a curved line with the selected line width"));
+        registry.register(
+            ToolEntry::new(12, "Rectangle", "Draws a rectangle with the selected fill style", create::<RectangleState>)
+                .with_shortcut(KeyboardKey::KEY_R)
+                .with_options(&[LineWidth, FillMode])
+        );
+        registry.register(
+            ToolEntry::new(13, "Polygon", "Draws a polygon with the selected fill style", create::<PolygonState>)
+                .with_shortcut(KeyboardKey::KEY_G)
+                .with_options(&[Antialiasing])
+        );
+        registry.register(
+            ToolEntry::new(14, "Ellipse", "Draws an ellipse with the selected fill style", create::<EllipseState>)
+                .with_shortcut(KeyboardKey::KEY_O)
+                .with_options(&[Antialiasing, LineWidth, FillMode])
+        );
+        registry.register(
+            ToolEntry::new(15, "Rounded Rectangle", "Draws a rounded rectangle with the selected fill style",
+                           create::<RoundedRectangleState>)
+                .with_shortcut(KeyboardKey::KEY_U)
+                .with_options(&[Antialiasing, LineWidth, FillMode, CornerRadius])
+        );
+        registry.seed(rand::random());
+        registry
+    }
+}
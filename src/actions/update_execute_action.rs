@@ -1,20 +1,63 @@
+use std::fmt::Debug;
 use raylib::color::Color;
-use crate::user_state::UserState;
-use raylib::drawing::RaylibDrawHandle;
-use raylib::prelude::Image;
-use raylib::{RaylibHandle, RaylibThread};
-
-pub trait UpdateExecuteAction {
-    /// Called when the pointer inside the canvas, and the left mouse button is pressed
-    fn update_pressed(&mut self, user_state: &UserState, rl: &mut RaylibHandle, thread: &RaylibThread);
+use crate::actions::input::InputEvent;
+use crate::pixel_buffer::PixelBuffer;
+use crate::user_state::{CanvasVector2, UserState};
+
+/// Tool logic, from input to pixels, with no dependency on the window
+pub trait UpdateExecuteAction: Debug {
+    /// Called when the pointer inside the canvas, and the left mouse button is pressed.
+    /// The canvas is borrowed as it was before this frame's draw.
+    fn update_pressed(&mut self, user_state: &UserState, input: &InputEvent, canvas: &PixelBuffer);
     /// Called when the pointer inside the canvas, and the left mouse button is not pressed
-    fn update_unpressed(&mut self, user_state: &UserState, rl: &mut RaylibHandle, thread: &RaylibThread);
+    fn update_unpressed(&mut self, user_state: &UserState, input: &InputEvent, canvas: &PixelBuffer);
     fn update_after_draw(&mut self, user_state: &UserState);
 
     /// Draw onto the canvas
-    fn draw(&mut self, image: &mut Image) -> bool;
-    /// Draw onto the temporary layer over the canvas, this layer will be cleared after each frame
-    fn draw_state(&self, user_state: &UserState, handle: &mut RaylibDrawHandle, thread: &RaylibThread);
+    fn draw(&mut self, image: &mut PixelBuffer) -> bool;
 
     fn get_color(&self) -> Option<Color>;
+    /// Colors put onto the canvas by the last draw that returned true
+    fn get_drawn_colors(&self) -> Vec<Color>;
+
+    /// Pixels the tool holds apart from the canvas, such as a selection
+    fn selected_image(&self) -> Option<&PixelBuffer> {
+        None
+    }
+
+    /// Shown in the status bar while the tool is selected
+    fn status_text(&self, _user_state: &UserState) -> Option<String> {
+        None
+    }
+}
+
+/// Run one frame of the tool: update it with the input, then let it draw onto the canvas.
+/// Returns whether the canvas changed.
+pub fn apply_input(tool: &mut dyn UpdateExecuteAction, user_state: &UserState, input: &InputEvent,
+                   canvas: &mut PixelBuffer) -> bool {
+    if input.pressed {
+        tool.update_pressed(user_state, input, canvas);
+    } else {
+        tool.update_unpressed(user_state, input, canvas);
+    }
+
+    let dirty = tool.draw(canvas);
+    if dirty {
+        tool.update_after_draw(user_state);
+    }
+    dirty
+}
+
+/// Run the tool over pointer positions in canvas coordinates, one frame each, with the button
+/// pressed or not. Returns whether the canvas changed.
+pub fn apply_frames(tool: &mut dyn UpdateExecuteAction, user_state: &UserState, frames: &[(CanvasVector2, bool)],
+                    canvas: &mut PixelBuffer) -> bool {
+    let mut user_state = user_state.clone();
+    let mut dirty = false;
+    for (position, pressed) in frames {
+        user_state.mouse_position = user_state.to_window(*position);
+        let input = InputEvent { pressed: *pressed, ..Default::default() };
+        dirty |= apply_input(tool, &user_state, &input, canvas);
+    }
+    dirty
 }
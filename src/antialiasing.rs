@@ -0,0 +1,265 @@
+use raylib::color::Color;
+use raylib::math::Vector2;
+use crate::pixel_buffer::PixelBuffer;
+use std::collections::HashMap;
+
+fn fpart(x: f32) -> f32 {
+    x - x.floor()
+}
+
+fn rfpart(x: f32) -> f32 {
+    1f32 - fpart(x)
+}
+
+/// Wrapper over a pixel buffer that draws with coverage-based alpha blending instead of
+/// setting pixels outright
+pub struct AntialiasedImage<'a> {
+    image: &'a mut PixelBuffer,
+}
+
+impl<'a> AntialiasedImage<'a> {
+    pub fn new(image: &'a mut PixelBuffer) -> Self {
+        AntialiasedImage { image }
+    }
+
+    /// Blend `color` over the pixel, with its alpha scaled by `coverage` (0 to 1)
+    pub fn blend_pixel(&mut self, x: i32, y: i32, color: Color, coverage: f32) {
+        if !self.image.contains(x, y) {
+            return;
+        }
+
+        let src_alpha = coverage.clamp(0f32, 1f32) * (color.a as f32 / 255f32);
+        if src_alpha <= 0f32 {
+            return;
+        }
+
+        let dst = self.image.get_color(x, y);
+        let dst_alpha = dst.a as f32 / 255f32;
+
+        // Straight alpha "over" operator, the canvas starts out transparent
+        let out_alpha = src_alpha + dst_alpha * (1f32 - src_alpha);
+        let channel = |src: u8, dst: u8| {
+            ((src as f32 * src_alpha + dst as f32 * dst_alpha * (1f32 - src_alpha)) / out_alpha)
+                .round() as u8
+        };
+
+        let blended = Color::new(
+            channel(color.r, dst.r),
+            channel(color.g, dst.g),
+            channel(color.b, dst.b),
+            (out_alpha * 255f32).round() as u8,
+        );
+        self.image.draw_pixel(x, y, blended);
+    }
+
+    /// Xiaolin Wu's line algorithm
+    pub fn draw_line_v(&mut self, start: Vector2, end: Vector2, color: Color) {
+        let (mut x0, mut y0, mut x1, mut y1) = (start.x, start.y, end.x, end.y);
+
+        let steep = (y1 - y0).abs() > (x1 - x0).abs();
+        if steep {
+            std::mem::swap(&mut x0, &mut y0);
+            std::mem::swap(&mut x1, &mut y1);
+        }
+        if x0 > x1 {
+            std::mem::swap(&mut x0, &mut x1);
+            std::mem::swap(&mut y0, &mut y1);
+        }
+
+        let dx = x1 - x0;
+        let dy = y1 - y0;
+        let gradient = if dx == 0f32 { 1f32 } else { dy / dx };
+
+        let mut plot = |x: i32, y: i32, coverage: f32| {
+            if steep {
+                self.blend_pixel(y, x, color, coverage);
+            } else {
+                self.blend_pixel(x, y, color, coverage);
+            }
+        };
+
+        // First endpoint
+        let x_end = x0.round();
+        let y_end = y0 + gradient * (x_end - x0);
+        let x_gap = rfpart(x0 + 0.5);
+        let x_pixel_start = x_end as i32;
+        let y_pixel = y_end.floor() as i32;
+        plot(x_pixel_start, y_pixel, rfpart(y_end) * x_gap);
+        plot(x_pixel_start, y_pixel + 1, fpart(y_end) * x_gap);
+        let mut intersection_y = y_end + gradient;
+
+        // Second endpoint
+        let x_end = x1.round();
+        let y_end = y1 + gradient * (x_end - x1);
+        let x_gap = fpart(x1 + 0.5);
+        let x_pixel_end = x_end as i32;
+        let y_pixel = y_end.floor() as i32;
+        if x_pixel_end != x_pixel_start {
+            plot(x_pixel_end, y_pixel, rfpart(y_end) * x_gap);
+            plot(x_pixel_end, y_pixel + 1, fpart(y_end) * x_gap);
+        }
+
+        for x in (x_pixel_start + 1)..x_pixel_end {
+            plot(x, intersection_y.floor() as i32, rfpart(intersection_y));
+            plot(x, intersection_y.floor() as i32 + 1, fpart(intersection_y));
+            intersection_y += gradient;
+        }
+    }
+
+    /// Outline of an axis aligned ellipse
+    pub fn draw_ellipse_lines(&mut self, center: Vector2, radius_h: f32, radius_v: f32, color: Color) {
+        if radius_h < 1f32 || radius_v < 1f32 {
+            let start = center - Vector2::new(radius_h, radius_v);
+            let end = center + Vector2::new(radius_h, radius_v);
+            self.draw_line_v(start, end, color);
+            return;
+        }
+
+        let coverage = quadrant_coverage([center; 4], radius_h, radius_v);
+        self.blend_coverage(coverage, color);
+    }
+
+    /// Outline of an axis aligned ellipse, `width` pixels thick inwards from the curve. Every pixel
+    /// is blended once, with the part of it that lies between the outer and the inner curve.
+    pub fn draw_ellipse_ring(&mut self, center: Vector2, radius_h: f32, radius_v: f32, width: f32,
+                             color: Color) {
+        // The curve runs through the middle of the pixels, so the ring reaches half a pixel out
+        let outer = (radius_h + 0.5, radius_v + 0.5);
+        let inner = (outer.0 - width, outer.1 - width);
+
+        let top = (center.y - outer.1).floor() as i32;
+        let bottom = (center.y + outer.1).ceil() as i32;
+        for y in top..=bottom {
+            let (row_top, row_bottom) = (y as f32 - 0.5 - center.y, y as f32 + 0.5 - center.y);
+            let crosses_middle = row_top <= 0f32 && row_bottom >= 0f32;
+            let nearest = if crosses_middle { 0f32 } else { row_top.abs().min(row_bottom.abs()) };
+            let farthest = row_top.abs().max(row_bottom.abs());
+            let reach = half_width(outer, nearest);
+            // Pixels within this of the center are in the hole for the whole row
+            let hole = half_width(inner, farthest);
+
+            let left = (center.x - reach - 0.5).floor() as i32;
+            let right = (center.x + reach + 0.5).ceil() as i32;
+            for x in left..=right {
+                let (column_left, column_right) = (x as f32 - 0.5 - center.x, x as f32 + 0.5 - center.x);
+                if -hole <= column_left && column_right <= hole {
+                    continue;
+                }
+                let coverage = ring_coverage(outer, inner, column_left, row_top);
+                self.blend_pixel(x, y, color, coverage);
+            }
+        }
+    }
+
+    /// Outline of the rectangle with corners `start` and `end`, with its corners rounded to
+    /// quarter circles of `radius`
+    pub fn draw_rounded_rectangle_lines(&mut self, start: Vector2, end: Vector2, radius: f32,
+                                        color: Color) {
+        let (left, right) = (start.x.min(end.x), start.x.max(end.x));
+        let (top, bottom) = (start.y.min(end.y), start.y.max(end.y));
+        let radius = radius.clamp(0f32, ((right - left) / 2f32).min((bottom - top) / 2f32));
+
+        let mut coverage = if radius >= 1f32 {
+            quadrant_coverage([
+                Vector2::new(right - radius, bottom - radius),
+                Vector2::new(left + radius, bottom - radius),
+                Vector2::new(left + radius, top + radius),
+                Vector2::new(right - radius, top + radius),
+            ], radius, radius)
+        } else {
+            HashMap::new()
+        };
+
+        // The straight edges are axis aligned, so they cover whole pixels
+        let (left_px, right_px) = (left.round() as i32, right.round() as i32);
+        let (top_px, bottom_px) = (top.round() as i32, bottom.round() as i32);
+        let inset = radius.round() as i32;
+        for x in (left_px + inset)..=(right_px - inset) {
+            coverage.insert((x, top_px), 1f32);
+            coverage.insert((x, bottom_px), 1f32);
+        }
+        for y in (top_px + inset)..=(bottom_px - inset) {
+            coverage.insert((left_px, y), 1f32);
+            coverage.insert((right_px, y), 1f32);
+        }
+
+        self.blend_coverage(coverage, color);
+    }
+
+    fn blend_coverage(&mut self, coverage: HashMap<(i32, i32), f32>, color: Color) {
+        for ((x, y), value) in coverage {
+            self.blend_pixel(x, y, color, value);
+        }
+    }
+}
+
+/// Sub-pixel samples along each side of a pixel
+const RING_SAMPLES: i32 = 4;
+
+fn inside_ellipse((radius_h, radius_v): (f32, f32), x: f32, y: f32) -> bool {
+    radius_h > 0f32 && radius_v > 0f32 && x * x / (radius_h * radius_h) + y * y / (radius_v * radius_v) <= 1f32
+}
+
+/// Half the width of an ellipse around the origin on the row at `y`, 0 past its top and bottom
+fn half_width((radius_h, radius_v): (f32, f32), y: f32) -> f32 {
+    if radius_h <= 0f32 || radius_v <= 0f32 {
+        return 0f32;
+    }
+    radius_h * (1f32 - y * y / (radius_v * radius_v)).max(0f32).sqrt()
+}
+
+/// Part of the pixel with its top left corner at (left, top) that is inside the outer ellipse
+/// and outside the inner one, both around the origin
+fn ring_coverage(outer: (f32, f32), inner: (f32, f32), left: f32, top: f32) -> f32 {
+    let step = 1f32 / RING_SAMPLES as f32;
+    let hits = (0..RING_SAMPLES * RING_SAMPLES)
+        .map(|i| ((i % RING_SAMPLES) as f32 + 0.5, (i / RING_SAMPLES) as f32 + 0.5))
+        .map(|(column, row)| (left + column * step, top + row * step))
+        .filter(|&(x, y)| inside_ellipse(outer, x, y) && !inside_ellipse(inner, x, y))
+        .count();
+    hits as f32 / (RING_SAMPLES * RING_SAMPLES) as f32
+}
+
+/// Coverage of the four quadrants of an axis aligned ellipse, each quadrant around its own center
+/// so that the ellipse can be split apart into the corners of a rounded rectangle. The centers are
+/// given clockwise from the bottom right quadrant. The curve is sampled once per column where it
+/// is flat and once per row where it is steep, pixels sampled twice keep their highest coverage.
+fn quadrant_coverage(centers: [Vector2; 4], radius_h: f32, radius_v: f32) -> HashMap<(i32, i32), f32> {
+    const SIGNS: [(f32, f32); 4] = [(1f32, 1f32), (-1f32, 1f32), (-1f32, -1f32), (1f32, -1f32)];
+
+    let mut coverage = HashMap::new();
+    let mut add = |x: i32, y: i32, value: f32| {
+        let entry = coverage.entry((x, y)).or_insert(0f32);
+        *entry = entry.max(value);
+    };
+
+    let a2 = radius_h * radius_h;
+    let b2 = radius_v * radius_v;
+
+    // Point where the slope of the curve is 1
+    let x_limit = (a2 / (a2 + b2).sqrt()).round() as i32;
+    for i in 0..=x_limit {
+        let dx = i as f32;
+        let dy = radius_v * (1f32 - dx * dx / a2).max(0f32).sqrt();
+        for (center, (sign_x, sign_y)) in centers.iter().zip(SIGNS) {
+            let x = (center.x + sign_x * dx).round() as i32;
+            let y = center.y + sign_y * dy;
+            add(x, y.floor() as i32, rfpart(y));
+            add(x, y.floor() as i32 + 1, fpart(y));
+        }
+    }
+
+    let y_limit = (b2 / (a2 + b2).sqrt()).round() as i32;
+    for i in 0..=y_limit {
+        let dy = i as f32;
+        let dx = radius_h * (1f32 - dy * dy / b2).max(0f32).sqrt();
+        for (center, (sign_x, sign_y)) in centers.iter().zip(SIGNS) {
+            let y = (center.y + sign_y * dy).round() as i32;
+            let x = center.x + sign_x * dx;
+            add(x.floor() as i32, y, rfpart(x));
+            add(x.floor() as i32 + 1, y, fpart(x));
+        }
+    }
+
+    coverage
+}
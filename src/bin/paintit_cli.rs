@@ -0,0 +1,74 @@
+use std::io::Read;
+use std::path::Path;
+use std::process;
+use raylib::color::Color;
+use PaintIt::document::Document;
+use PaintIt::pixel_buffer::MAX_IMAGE_SIZE;
+use PaintIt::script::{self, Command, ScriptError};
+
+const USAGE: &str = "usage: paintit-cli (<input image> | --new <width>x<height>) <script | -> <output image>
+
+Runs a drawing script over the image without opening a window, '-' reads the script from stdin.
+The output is saved as PNG or QOI, chosen by its extension.
+See PaintIt::script::Command for the commands.";
+
+fn new_document(size: &str) -> Option<Document> {
+    let (width, height) = size.split_once('x')?;
+    let (width, height) = (width.parse().ok()?, height.parse().ok()?);
+    if !(1..=MAX_IMAGE_SIZE).contains(&width) || !(1..=MAX_IMAGE_SIZE).contains(&height) {
+        return None;
+    }
+    Some(Document::new(width, height, Color::WHITE))
+}
+
+fn load_script(source: &str) -> Result<Vec<Command>, ScriptError> {
+    if source == "-" {
+        let mut text = String::new();
+        std::io::stdin().read_to_string(&mut text)?;
+        script::parse(&text)
+    } else {
+        script::load(Path::new(source))
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (mut document, rest) = match args.as_slice() {
+        [new, size, rest @ ..] if new == "--new" => match new_document(size) {
+            Some(document) => (document, rest),
+            None => {
+                eprintln!("'{}' is not a size like 640x480\n{}", size, USAGE);
+                process::exit(2);
+            }
+        },
+        [input, rest @ ..] if !input.starts_with("--") => match Document::open(Path::new(input)) {
+            Ok(document) => (document, rest),
+            Err(error) => {
+                eprintln!("Could not load image {}: {}", input, error);
+                process::exit(1);
+            }
+        },
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    let [script_source, output] = rest else {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    };
+
+    let commands = match load_script(script_source) {
+        Ok(commands) => commands,
+        Err(error) => {
+            eprintln!("Could not load script {}: {}", script_source, error);
+            process::exit(1);
+        }
+    };
+
+    document.run_script(&commands);
+    if let Err(error) = document.save(Path::new(output)) {
+        eprintln!("Could not save image {}: {}", output, error);
+        process::exit(1);
+    }
+}
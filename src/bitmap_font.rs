@@ -0,0 +1,147 @@
+use raylib::color::Color;
+use crate::pixel_buffer::PixelBuffer;
+
+/// Height of the glyphs at size 10, the size they are drawn at without scaling
+pub const GLYPH_HEIGHT: i32 = 10;
+
+/// raylib's default font, so text drawn into a pixel buffer looks like `draw_text` in the window.
+/// Width and rows of the printable ASCII characters starting from space, the lowest bit is the leftmost pixel.
+const GLYPHS: [(i32, [u8; 10]); 95] = [
+    (3, [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]), // space
+    (1, [0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x00, 0x01, 0x00, 0x00]), // !
+    (4, [0x00, 0x0a, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]), // "
+    (6, [0x00, 0x12, 0x3f, 0x12, 0x12, 0x12, 0x3f, 0x12, 0x00, 0x00]), // #
+    (5, [0x04, 0x1f, 0x05, 0x05, 0x1f, 0x14, 0x14, 0x1f, 0x04, 0x00]), // $
+    (7, [0x00, 0x47, 0x25, 0x17, 0x08, 0x74, 0x52, 0x71, 0x00, 0x00]), // %
+    (6, [0x00, 0x00, 0x0f, 0x09, 0x26, 0x19, 0x19, 0x2f, 0x00, 0x00]), // &
+    (2, [0x00, 0x02, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]), // '
+    (3, [0x06, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x06, 0x00]), // (
+    (3, [0x03, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x03, 0x00]), // )
+    (5, [0x00, 0x00, 0x0a, 0x04, 0x1f, 0x04, 0x0a, 0x00, 0x00, 0x00]), // *
+    (5, [0x00, 0x00, 0x04, 0x04, 0x1f, 0x04, 0x04, 0x00, 0x00, 0x00]), // +
+    (2, [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x01, 0x00]), // ,
+    (4, [0x00, 0x00, 0x00, 0x00, 0x0f, 0x00, 0x00, 0x00, 0x00, 0x00]), // -
+    (1, [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00]), // .
+    (7, [0x00, 0x40, 0x20, 0x10, 0x08, 0x04, 0x02, 0x01, 0x00, 0x00]), // /
+    (5, [0x00, 0x1f, 0x11, 0x11, 0x11, 0x11, 0x11, 0x1f, 0x00, 0x00]), // 0
+    (2, [0x00, 0x03, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x00, 0x00]), // 1
+    (5, [0x00, 0x1f, 0x10, 0x10, 0x1f, 0x01, 0x01, 0x1f, 0x00, 0x00]), // 2
+    (5, [0x00, 0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x1f, 0x00, 0x00]), // 3
+    (5, [0x00, 0x11, 0x11, 0x11, 0x1f, 0x10, 0x10, 0x10, 0x00, 0x00]), // 4
+    (5, [0x00, 0x1f, 0x01, 0x01, 0x1f, 0x10, 0x10, 0x1f, 0x00, 0x00]), // 5
+    (5, [0x00, 0x1f, 0x01, 0x01, 0x1f, 0x11, 0x11, 0x1f, 0x00, 0x00]), // 6
+    (5, [0x00, 0x1f, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x00, 0x00]), // 7
+    (5, [0x00, 0x1f, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x1f, 0x00, 0x00]), // 8
+    (5, [0x00, 0x1f, 0x11, 0x11, 0x1f, 0x10, 0x10, 0x1f, 0x00, 0x00]), // 9
+    (1, [0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00]), // :
+    (1, [0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x01, 0x00]), // ;
+    (3, [0x00, 0x00, 0x04, 0x02, 0x01, 0x02, 0x04, 0x00, 0x00, 0x00]), // <
+    (4, [0x00, 0x00, 0x00, 0x0f, 0x00, 0x0f, 0x00, 0x00, 0x00, 0x00]), // =
+    (3, [0x00, 0x00, 0x01, 0x02, 0x04, 0x02, 0x01, 0x00, 0x00, 0x00]), // >
+    (6, [0x00, 0x3f, 0x21, 0x21, 0x3c, 0x04, 0x00, 0x04, 0x00, 0x00]), // ?
+    (7, [0x00, 0x00, 0x7f, 0x41, 0x5d, 0x55, 0x7d, 0x01, 0x7f, 0x00]), // @
+    (6, [0x00, 0x3f, 0x21, 0x21, 0x3f, 0x21, 0x21, 0x21, 0x00, 0x00]), // A
+    (6, [0x00, 0x3f, 0x21, 0x21, 0x1f, 0x21, 0x21, 0x3f, 0x00, 0x00]), // B
+    (6, [0x00, 0x3f, 0x21, 0x01, 0x01, 0x01, 0x21, 0x3f, 0x00, 0x00]), // C
+    (6, [0x00, 0x1f, 0x21, 0x21, 0x21, 0x21, 0x21, 0x1f, 0x00, 0x00]), // D
+    (6, [0x00, 0x3f, 0x01, 0x01, 0x1f, 0x01, 0x01, 0x3f, 0x00, 0x00]), // E
+    (6, [0x00, 0x3f, 0x01, 0x01, 0x1f, 0x01, 0x01, 0x01, 0x00, 0x00]), // F
+    (6, [0x00, 0x3f, 0x21, 0x01, 0x31, 0x21, 0x21, 0x3f, 0x00, 0x00]), // G
+    (6, [0x00, 0x21, 0x21, 0x21, 0x3f, 0x21, 0x21, 0x21, 0x00, 0x00]), // H
+    (3, [0x00, 0x07, 0x02, 0x02, 0x02, 0x02, 0x02, 0x07, 0x00, 0x00]), // I
+    (5, [0x00, 0x1f, 0x10, 0x10, 0x10, 0x10, 0x11, 0x0e, 0x00, 0x00]), // J
+    (6, [0x00, 0x21, 0x21, 0x11, 0x0f, 0x11, 0x21, 0x21, 0x00, 0x00]), // K
+    (5, [0x00, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x1f, 0x00, 0x00]), // L
+    (7, [0x00, 0x41, 0x63, 0x55, 0x49, 0x41, 0x41, 0x41, 0x00, 0x00]), // M
+    (6, [0x00, 0x21, 0x21, 0x23, 0x25, 0x29, 0x31, 0x21, 0x00, 0x00]), // N
+    (6, [0x00, 0x3f, 0x21, 0x21, 0x21, 0x21, 0x21, 0x3f, 0x00, 0x00]), // O
+    (6, [0x00, 0x3f, 0x21, 0x21, 0x3f, 0x01, 0x01, 0x01, 0x00, 0x00]), // P
+    (6, [0x00, 0x3f, 0x21, 0x21, 0x21, 0x21, 0x29, 0x3f, 0x10, 0x00]), // Q
+    (6, [0x00, 0x3f, 0x21, 0x21, 0x3f, 0x09, 0x11, 0x21, 0x00, 0x00]), // R
+    (6, [0x00, 0x3f, 0x01, 0x01, 0x3f, 0x20, 0x20, 0x3f, 0x00, 0x00]), // S
+    (7, [0x00, 0x7f, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x00, 0x00]), // T
+    (6, [0x00, 0x21, 0x21, 0x21, 0x21, 0x21, 0x21, 0x3f, 0x00, 0x00]), // U
+    (7, [0x00, 0x41, 0x41, 0x41, 0x41, 0x22, 0x14, 0x08, 0x00, 0x00]), // V
+    (7, [0x00, 0x41, 0x49, 0x49, 0x49, 0x49, 0x49, 0x7f, 0x00, 0x00]), // W
+    (6, [0x00, 0x21, 0x21, 0x21, 0x1e, 0x21, 0x21, 0x21, 0x00, 0x00]), // X
+    (6, [0x00, 0x21, 0x21, 0x21, 0x3f, 0x20, 0x20, 0x3f, 0x00, 0x00]), // Y
+    (6, [0x00, 0x3f, 0x20, 0x10, 0x08, 0x04, 0x02, 0x3f, 0x00, 0x00]), // Z
+    (2, [0x03, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x03, 0x00]), // [
+    (7, [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x00, 0x00]), // \
+    (2, [0x03, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x03, 0x00]), // ]
+    (3, [0x00, 0x02, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]), // ^
+    (5, [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1f, 0x00, 0x00]), // _
+    (2, [0x00, 0x01, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]), // `
+    (5, [0x00, 0x00, 0x00, 0x1f, 0x10, 0x1f, 0x11, 0x1f, 0x00, 0x00]), // a
+    (5, [0x00, 0x01, 0x01, 0x1f, 0x11, 0x11, 0x11, 0x1f, 0x00, 0x00]), // b
+    (5, [0x00, 0x00, 0x00, 0x1f, 0x01, 0x01, 0x01, 0x1f, 0x00, 0x00]), // c
+    (5, [0x00, 0x10, 0x10, 0x1f, 0x11, 0x11, 0x11, 0x1f, 0x00, 0x00]), // d
+    (5, [0x00, 0x00, 0x00, 0x1f, 0x11, 0x1f, 0x01, 0x1f, 0x00, 0x00]), // e
+    (4, [0x00, 0x00, 0x0f, 0x01, 0x01, 0x07, 0x01, 0x01, 0x01, 0x01]), // f
+    (5, [0x00, 0x00, 0x00, 0x1f, 0x11, 0x11, 0x11, 0x1f, 0x10, 0x1f]), // g
+    (5, [0x00, 0x01, 0x01, 0x1f, 0x11, 0x11, 0x11, 0x11, 0x00, 0x00]), // h
+    (1, [0x00, 0x01, 0x00, 0x01, 0x01, 0x01, 0x01, 0x01, 0x00, 0x00]), // i
+    (2, [0x00, 0x02, 0x00, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02, 0x03]), // j
+    (5, [0x00, 0x01, 0x01, 0x11, 0x09, 0x07, 0x09, 0x11, 0x00, 0x00]), // k
+    (2, [0x00, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x03, 0x00, 0x00]), // l
+    (5, [0x00, 0x00, 0x00, 0x1f, 0x15, 0x15, 0x15, 0x15, 0x00, 0x00]), // m
+    (5, [0x00, 0x00, 0x00, 0x1f, 0x11, 0x11, 0x11, 0x11, 0x00, 0x00]), // n
+    (5, [0x00, 0x00, 0x00, 0x1f, 0x11, 0x11, 0x11, 0x1f, 0x00, 0x00]), // o
+    (5, [0x00, 0x00, 0x00, 0x1f, 0x11, 0x11, 0x11, 0x1f, 0x01, 0x01]), // p
+    (5, [0x00, 0x00, 0x00, 0x1f, 0x11, 0x11, 0x11, 0x1f, 0x10, 0x10]), // q
+    (5, [0x00, 0x00, 0x00, 0x1f, 0x01, 0x01, 0x01, 0x01, 0x00, 0x00]), // r
+    (5, [0x00, 0x00, 0x00, 0x1f, 0x01, 0x1f, 0x10, 0x1f, 0x00, 0x00]), // s
+    (4, [0x00, 0x01, 0x01, 0x07, 0x01, 0x01, 0x01, 0x0f, 0x00, 0x00]), // t
+    (5, [0x00, 0x00, 0x00, 0x11, 0x11, 0x11, 0x11, 0x1f, 0x00, 0x00]), // u
+    (5, [0x00, 0x00, 0x00, 0x11, 0x11, 0x11, 0x0a, 0x04, 0x00, 0x00]), // v
+    (5, [0x00, 0x00, 0x00, 0x11, 0x15, 0x15, 0x15, 0x1f, 0x00, 0x00]), // w
+    (5, [0x00, 0x00, 0x00, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x00, 0x00]), // x
+    (5, [0x00, 0x00, 0x00, 0x11, 0x11, 0x11, 0x11, 0x1f, 0x10, 0x1f]), // y
+    (5, [0x00, 0x00, 0x00, 0x1f, 0x08, 0x04, 0x02, 0x1f, 0x00, 0x00]), // z
+    (3, [0x06, 0x02, 0x02, 0x02, 0x01, 0x02, 0x02, 0x02, 0x06, 0x00]), // {
+    (1, [0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x00]), // |
+    (3, [0x03, 0x02, 0x02, 0x02, 0x04, 0x02, 0x02, 0x02, 0x03, 0x00]), // }
+    (4, [0x00, 0x00, 0x00, 0x00, 0x0a, 0x05, 0x00, 0x00, 0x00, 0x00]), // ~
+];
+
+fn glyph(c: char) -> &'static (i32, [u8; 10]) {
+    let index = (c as usize).wrapping_sub(' ' as usize);
+    GLYPHS.get(index).unwrap_or(&GLYPHS[('?' as usize) - (' ' as usize)])
+}
+
+/// Pixels per glyph pixel, sizes below 10 are drawn at 10 like raylib does
+fn scale(size: i32) -> i32 {
+    (size / GLYPH_HEIGHT).max(1)
+}
+
+/// Width of the longest line
+pub fn measure_text(text: &str, size: i32) -> i32 {
+    let scale = scale(size);
+    text.lines()
+        .map(|line| {
+            let width: i32 = line.chars().map(|c| (glyph(c).0 + 1) * scale).sum();
+            (width - scale).max(0)
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Draw the text with its top left corner at the position, characters missing from the font are drawn as '?'
+pub fn draw_text(image: &mut PixelBuffer, text: &str, x: i32, y: i32, size: i32, color: Color) {
+    let scale = scale(size);
+    for (line_number, line) in text.lines().enumerate() {
+        let top = y + line_number as i32 * GLYPH_HEIGHT * 3 / 2 * scale;
+        let mut left = x;
+        for c in line.chars() {
+            let (width, rows) = glyph(c);
+            for (row, bits) in rows.iter().enumerate() {
+                for column in 0..*width {
+                    if bits & (1 << column) != 0 {
+                        image.draw_rectangle(left + column * scale, top + row as i32 * scale, scale, scale, color);
+                    }
+                }
+            }
+            left += (width + 1) * scale;
+        }
+    }
+}
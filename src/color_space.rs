@@ -0,0 +1,78 @@
+use raylib::color::Color;
+
+/// Hue in degrees [0, 360), saturation and value in [0, 1]
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+pub struct Hsv {
+    pub hue: f32,
+    pub saturation: f32,
+    pub value: f32,
+}
+
+impl Hsv {
+    pub fn from_color(color: Color) -> Hsv {
+        let r = color.r as f32 / 255f32;
+        let g = color.g as f32 / 255f32;
+        let b = color.b as f32 / 255f32;
+
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+
+        let hue = if delta == 0f32 {
+            0f32
+        } else if max == r {
+            60f32 * ((g - b) / delta).rem_euclid(6f32)
+        } else if max == g {
+            60f32 * ((b - r) / delta + 2f32)
+        } else {
+            60f32 * ((r - g) / delta + 4f32)
+        };
+
+        let saturation = if max == 0f32 { 0f32 } else { delta / max };
+
+        Hsv { hue, saturation, value: max }
+    }
+
+    pub fn to_color(self, alpha: u8) -> Color {
+        let hue = self.hue.rem_euclid(360f32);
+        let saturation = self.saturation.clamp(0f32, 1f32);
+        let value = self.value.clamp(0f32, 1f32);
+
+        let chroma = value * saturation;
+        let x = chroma * (1f32 - ((hue / 60f32).rem_euclid(2f32) - 1f32).abs());
+        let m = value - chroma;
+
+        let (r, g, b) = match (hue / 60f32) as u32 {
+            0 => (chroma, x, 0f32),
+            1 => (x, chroma, 0f32),
+            2 => (0f32, chroma, x),
+            3 => (0f32, x, chroma),
+            4 => (x, 0f32, chroma),
+            _ => (chroma, 0f32, x),
+        };
+
+        let channel = |c: f32| ((c + m) * 255f32).round() as u8;
+        Color::new(channel(r), channel(g), channel(b), alpha)
+    }
+}
+
+/// Format as #RRGGBB, or #RRGGBBAA when not opaque
+pub fn to_hex(color: Color) -> String {
+    if color.a == 255 {
+        format!("#{:02X}{:02X}{:02X}", color.r, color.g, color.b)
+    } else {
+        format!("#{:02X}{:02X}{:02X}{:02X}", color.r, color.g, color.b, color.a)
+    }
+}
+
+/// Parse RRGGBB or RRGGBBAA, with or without a leading '#'
+pub fn parse_hex(text: &str) -> Option<Color> {
+    let digits = text.trim().trim_start_matches('#');
+    if !(digits.len() == 6 || digits.len() == 8) || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).ok();
+    let alpha = if digits.len() == 8 { channel(6)? } else { 255 };
+    Some(Color::new(channel(0)?, channel(2)?, channel(4)?, alpha))
+}
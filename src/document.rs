@@ -0,0 +1,212 @@
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use raylib::color::Color;
+use raylib::texture::Image;
+use crate::actions::update_execute_action::{apply_frames, UpdateExecuteAction};
+use crate::operation::Operation;
+use crate::pixel_buffer::PixelBuffer;
+use crate::raylib_extensions::{buffer_from_image, image_from_buffer};
+use crate::script::{self, Command};
+use crate::user_state::{CanvasVector2, UserState};
+
+/// Undo steps kept, the oldest are forgotten first
+pub const UNDO_LIMIT: usize = 50;
+
+/// Extensions raylib can save to
+const SAVE_EXTENSIONS: [&str; 2] = ["png", "qoi"];
+
+#[derive(Debug)]
+pub enum DocumentError {
+    /// raylib's message
+    Load(String),
+    UnsupportedFormat,
+    /// raylib did not write the image
+    Save,
+    Io(std::io::Error),
+}
+
+impl Display for DocumentError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DocumentError::Load(message) => write!(f, "{}", message),
+            DocumentError::UnsupportedFormat => write!(f, "can only save {}", SAVE_EXTENSIONS.join(", ")),
+            DocumentError::Save => write!(f, "could not write the image"),
+            DocumentError::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for DocumentError {}
+
+impl From<std::io::Error> for DocumentError {
+    fn from(error: std::io::Error) -> Self {
+        DocumentError::Io(error)
+    }
+}
+
+/// An image edited without a window: the same tools as the window, with undo and redo.
+///
+/// Every call that changes pixels is one undo step. The options of the tools, such as the
+/// colors and the line width, are kept in `options` and stay between calls.
+#[derive(Debug, Clone)]
+pub struct Document {
+    canvas: PixelBuffer,
+    /// The positions are ignored, the tools are given the document's pixels
+    pub options: UserState,
+    /// Canvases with their versions
+    undo_stack: Vec<(PixelBuffer, u64)>,
+    redo_stack: Vec<(PixelBuffer, u64)>,
+    /// Tells the canvases apart, every undo step makes a new one
+    version: u64,
+    last_version: u64,
+    /// Version last written to a file, or the one the document started with
+    saved_version: Option<u64>,
+}
+
+impl Document {
+    pub fn new(width: i32, height: i32, background: Color) -> Self {
+        Document::from_buffer(PixelBuffer::new(width, height, background))
+    }
+
+    pub fn from_buffer(canvas: PixelBuffer) -> Self {
+        Document {
+            canvas,
+            options: UserState::default(),
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            version: 0,
+            last_version: 0,
+            saved_version: Some(0),
+        }
+    }
+
+    /// Any format raylib can load
+    pub fn open(path: &Path) -> Result<Self, DocumentError> {
+        let path = path.to_str().ok_or_else(|| DocumentError::Load("path is not valid UTF-8".to_string()))?;
+        let image = Image::load_image(path).map_err(DocumentError::Load)?;
+        Ok(Document::from_buffer(buffer_from_image(&image)))
+    }
+
+    /// PNG or QOI, chosen by the extension. Replaces the file if there is one, so callers ask first.
+    pub fn save(&mut self, path: &Path) -> Result<(), DocumentError> {
+        let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase())
+            .filter(|e| SAVE_EXTENSIONS.contains(&e.as_str()))
+            .ok_or(DocumentError::UnsupportedFormat)?;
+
+        // raylib only reports failures in its log, so write next to the target and check the file
+        // is there, which also leaves the previous file untouched when saving fails
+        let file_name = path.file_name().and_then(|name| name.to_str()).ok_or(DocumentError::Save)?;
+        let partial = path.with_file_name(format!(".{}.partial.{}", file_name, extension));
+        image_from_buffer(&self.canvas).export_image(partial.to_str().ok_or(DocumentError::Save)?);
+        if !partial.exists() {
+            return Err(DocumentError::Save);
+        }
+        std::fs::rename(&partial, path)?;
+        self.saved_version = Some(self.version);
+        Ok(())
+    }
+
+    pub fn width(&self) -> i32 {
+        self.canvas.width()
+    }
+
+    pub fn height(&self) -> i32 {
+        self.canvas.height()
+    }
+
+    pub fn canvas(&self) -> &PixelBuffer {
+        &self.canvas
+    }
+
+    /// None outside the image
+    pub fn get_pixel(&self, x: i32, y: i32) -> Option<Color> {
+        self.canvas.contains(x, y).then(|| self.canvas.get_color(x, y))
+    }
+
+    /// For edits spread over several frames, such as a stroke in the window. They only become
+    /// an undo step once the canvas from before them is given to `push_undo`.
+    pub fn canvas_mut(&mut self) -> &mut PixelBuffer {
+        &mut self.canvas
+    }
+
+    /// Changed since it was opened, made or saved, undoing back to that point counts as unchanged
+    pub fn is_modified(&self) -> bool {
+        self.saved_version != Some(self.version)
+    }
+
+    /// Make the canvas from before an edit the last undo step, which drops the redo steps
+    pub fn push_undo(&mut self, before: PixelBuffer) {
+        self.undo_stack.push((before, self.version));
+        self.last_version += 1;
+        self.version = self.last_version;
+        if self.undo_stack.len() > UNDO_LIMIT {
+            self.undo_stack.remove(0);
+        }
+        self.redo_stack.clear();
+    }
+
+    /// Make the change as one undo step, only kept if it changed a pixel
+    fn edit(&mut self, change: impl FnOnce(&UserState, &mut PixelBuffer)) -> bool {
+        let before = self.canvas.clone();
+        change(&self.options, &mut self.canvas);
+        if self.canvas == before {
+            return false;
+        }
+
+        self.push_undo(before);
+        true
+    }
+
+    /// Returns whether a pixel changed
+    pub fn apply(&mut self, operation: &Operation) -> bool {
+        self.edit(|options, canvas| operation.apply(options, canvas))
+    }
+
+    /// Run any tool over pointer positions in canvas pixels, one frame each, with the button
+    /// pressed or not, such as a pencil stroke. Returns whether a pixel changed.
+    pub fn apply_tool(&mut self, tool: &mut dyn UpdateExecuteAction, frames: &[(CanvasVector2, bool)]) -> bool {
+        self.edit(|options, canvas| {
+            apply_frames(tool, options, frames, canvas);
+        })
+    }
+
+    /// The whole script is one undo step, the options it sets stay in `options`
+    pub fn run_script(&mut self, commands: &[Command]) -> bool {
+        let mut options = self.options.clone();
+        let changed = self.edit(|_, canvas| script::run(commands, &mut options, canvas));
+        self.options = options;
+        changed
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Returns false when there is nothing to undo
+    pub fn undo(&mut self) -> bool {
+        match self.undo_stack.pop() {
+            Some((previous, version)) => {
+                self.redo_stack.push((std::mem::replace(&mut self.canvas, previous), self.version));
+                self.version = version;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Returns false when there is nothing to redo
+    pub fn redo(&mut self) -> bool {
+        match self.redo_stack.pop() {
+            Some((next, version)) => {
+                self.undo_stack.push((std::mem::replace(&mut self.canvas, next), self.version));
+                self.version = version;
+                true
+            },
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,321 @@
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use raylib::consts::KeyboardKey;
+use crate::actions::input::Modifiers;
+use crate::actions::tool_registry::ToolRegistry;
+use crate::session::FrameInput;
+use crate::settings;
+
+const FILE_NAME: &str = "keymap";
+
+const HEADER: &str = "\
+# PaintIt keymap, one binding per line: <action> = <key>
+# Keys are a letter, a digit, F1-F12 or a name such as Space, Delete or PageUp,
+# after any of Ctrl+, Shift+ and Alt+. 'none' removes the binding.
+";
+
+/// Names used in the keymap file
+const KEY_NAMES: [(&str, KeyboardKey); 64] = [
+    ("A", KeyboardKey::KEY_A), ("B", KeyboardKey::KEY_B), ("C", KeyboardKey::KEY_C),
+    ("D", KeyboardKey::KEY_D), ("E", KeyboardKey::KEY_E), ("F", KeyboardKey::KEY_F),
+    ("G", KeyboardKey::KEY_G), ("H", KeyboardKey::KEY_H), ("I", KeyboardKey::KEY_I),
+    ("J", KeyboardKey::KEY_J), ("K", KeyboardKey::KEY_K), ("L", KeyboardKey::KEY_L),
+    ("M", KeyboardKey::KEY_M), ("N", KeyboardKey::KEY_N), ("O", KeyboardKey::KEY_O),
+    ("P", KeyboardKey::KEY_P), ("Q", KeyboardKey::KEY_Q), ("R", KeyboardKey::KEY_R),
+    ("S", KeyboardKey::KEY_S), ("T", KeyboardKey::KEY_T), ("U", KeyboardKey::KEY_U),
+    ("V", KeyboardKey::KEY_V), ("W", KeyboardKey::KEY_W), ("X", KeyboardKey::KEY_X),
+    ("Y", KeyboardKey::KEY_Y), ("Z", KeyboardKey::KEY_Z), ("0", KeyboardKey::KEY_ZERO),
+    ("1", KeyboardKey::KEY_ONE), ("2", KeyboardKey::KEY_TWO), ("3", KeyboardKey::KEY_THREE),
+    ("4", KeyboardKey::KEY_FOUR), ("5", KeyboardKey::KEY_FIVE), ("6", KeyboardKey::KEY_SIX),
+    ("7", KeyboardKey::KEY_SEVEN), ("8", KeyboardKey::KEY_EIGHT), ("9", KeyboardKey::KEY_NINE),
+    ("F1", KeyboardKey::KEY_F1), ("F2", KeyboardKey::KEY_F2), ("F3", KeyboardKey::KEY_F3),
+    ("F4", KeyboardKey::KEY_F4), ("F5", KeyboardKey::KEY_F5), ("F6", KeyboardKey::KEY_F6),
+    ("F7", KeyboardKey::KEY_F7), ("F8", KeyboardKey::KEY_F8), ("F9", KeyboardKey::KEY_F9),
+    ("F10", KeyboardKey::KEY_F10), ("F11", KeyboardKey::KEY_F11), ("F12", KeyboardKey::KEY_F12),
+    ("Space", KeyboardKey::KEY_SPACE), ("Enter", KeyboardKey::KEY_ENTER), ("Escape", KeyboardKey::KEY_ESCAPE),
+    ("Tab", KeyboardKey::KEY_TAB), ("Backspace", KeyboardKey::KEY_BACKSPACE),
+    ("Delete", KeyboardKey::KEY_DELETE), ("Home", KeyboardKey::KEY_HOME), ("End", KeyboardKey::KEY_END),
+    ("PageUp", KeyboardKey::KEY_PAGE_UP), ("PageDown", KeyboardKey::KEY_PAGE_DOWN),
+    ("Left", KeyboardKey::KEY_LEFT), ("Right", KeyboardKey::KEY_RIGHT), ("Up", KeyboardKey::KEY_UP),
+    ("Down", KeyboardKey::KEY_DOWN), ("Minus", KeyboardKey::KEY_MINUS), ("Equal", KeyboardKey::KEY_EQUAL),
+];
+
+/// Key written with the name in the keymap file, such as "P" or "PageUp", in any case
+pub fn key_named(name: &str) -> Option<KeyboardKey> {
+    KEY_NAMES.iter().find(|(known, _)| known.eq_ignore_ascii_case(name)).map(|(_, key)| *key)
+}
+
+/// Key pressed with exactly these modifiers held
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct KeyBinding {
+    pub key: KeyboardKey,
+    pub modifiers: Modifiers,
+}
+
+impl KeyBinding {
+    pub fn key(key: KeyboardKey) -> Self {
+        KeyBinding { key, modifiers: Modifiers::default() }
+    }
+
+    pub fn control(key: KeyboardKey) -> Self {
+        KeyBinding { key, modifiers: Modifiers { control: true, shift: false, alt: false } }
+    }
+
+    pub fn control_shift(key: KeyboardKey) -> Self {
+        KeyBinding { key, modifiers: Modifiers { control: true, shift: true, alt: false } }
+    }
+
+    /// Like "Ctrl+Shift+P", the modifiers in any order and case
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let key_name = parts.pop()?;
+        let key = key_named(key_name)?;
+
+        let mut modifiers = Modifiers::default();
+        for part in parts {
+            let held = match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => &mut modifiers.control,
+                "shift" => &mut modifiers.shift,
+                "alt" => &mut modifiers.alt,
+                _ => return None,
+            };
+            *held = true;
+        }
+        Some(KeyBinding { key, modifiers })
+    }
+
+    pub fn is_pressed(&self, input: &FrameInput) -> bool {
+        input.modifiers == self.modifiers && input.is_key_pressed(self.key)
+    }
+}
+
+impl Display for KeyBinding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (held, name) in [(self.modifiers.control, "Ctrl+"), (self.modifiers.shift, "Shift+"), (self.modifiers.alt, "Alt+")] {
+            if held {
+                write!(f, "{}", name)?;
+            }
+        }
+        let name = KEY_NAMES.iter().find(|(_, key)| *key == self.key).map_or("?", |(name, _)| *name);
+        write!(f, "{}", name)
+    }
+}
+
+/// Something a shortcut can do
+#[derive(Debug, PartialEq, Clone)]
+pub enum Action {
+    /// Select the tool, by its name in the registry written in lower case with dashes
+    Tool(String),
+    NewImage,
+    Open,
+    Save,
+    SaveAs,
+    Undo,
+    Redo,
+    ToggleColorBox,
+    ToggleStatusBar,
+    FlipHorizontal,
+    FlipVertical,
+    ClearImage,
+    EditColors,
+    SwapColors,
+    DefaultColors,
+    ImportPalette,
+    ExportPalette,
+    ExtractPalette,
+}
+
+impl Action {
+    const COMMANDS: [(&'static str, Action); 17] = [
+        ("new-image", Action::NewImage),
+        ("open", Action::Open),
+        ("save", Action::Save),
+        ("save-as", Action::SaveAs),
+        ("undo", Action::Undo),
+        ("redo", Action::Redo),
+        ("toggle-color-box", Action::ToggleColorBox),
+        ("toggle-status-bar", Action::ToggleStatusBar),
+        ("flip-horizontal", Action::FlipHorizontal),
+        ("flip-vertical", Action::FlipVertical),
+        ("clear-image", Action::ClearImage),
+        ("edit-colors", Action::EditColors),
+        ("swap-colors", Action::SwapColors),
+        ("default-colors", Action::DefaultColors),
+        ("import-palette", Action::ImportPalette),
+        ("export-palette", Action::ExportPalette),
+        ("extract-palette", Action::ExtractPalette),
+    ];
+
+    pub fn tool(name: &str) -> Self {
+        Action::Tool(name.to_ascii_lowercase().replace(' ', "-"))
+    }
+
+    /// As written in the keymap file
+    pub fn name(&self) -> String {
+        match self {
+            Action::Tool(tool) => format!("tool.{}", tool),
+            _ => Action::COMMANDS.iter().find(|(_, action)| action == self).unwrap().0.to_string(),
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        if let Some(tool) = name.strip_prefix("tool.") {
+            return Some(Action::Tool(tool.to_string()));
+        }
+        Action::COMMANDS.iter().find(|(command, _)| *command == name).map(|(_, action)| action.clone())
+    }
+}
+
+#[derive(Debug)]
+pub enum KeymapError {
+    /// Line number starting at 1
+    Line(usize, String),
+    Io(std::io::Error),
+}
+
+impl Display for KeymapError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeymapError::Line(line, message) => write!(f, "line {}: {}", line, message),
+            KeymapError::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for KeymapError {}
+
+impl From<std::io::Error> for KeymapError {
+    fn from(error: std::io::Error) -> Self {
+        KeymapError::Io(error)
+    }
+}
+
+/// $XDG_CONFIG_HOME/PaintIt/keymap, falling back to ~/.config/PaintIt/keymap
+pub fn default_path() -> Option<PathBuf> {
+    settings::config_directory().map(|directory| directory.join(FILE_NAME))
+}
+
+/// Which key does what, at most one action per key
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Keymap {
+    /// In the order they are written to the file
+    bindings: Vec<(Action, Option<KeyBinding>)>,
+}
+
+impl Keymap {
+    /// The tools' own shortcuts, then the commands
+    pub fn defaults(tools: &ToolRegistry) -> Self {
+        let mut bindings: Vec<(Action, Option<KeyBinding>)> = tools.iter()
+            .filter(|entry| entry.is_enabled())
+            .map(|entry| (Action::tool(entry.name), entry.shortcut.map(KeyBinding::key)))
+            .collect();
+        bindings.extend([
+            (Action::NewImage, Some(KeyBinding::control(KeyboardKey::KEY_N))),
+            (Action::Open, Some(KeyBinding::control(KeyboardKey::KEY_O))),
+            (Action::Save, Some(KeyBinding::control(KeyboardKey::KEY_S))),
+            (Action::SaveAs, Some(KeyBinding::control_shift(KeyboardKey::KEY_S))),
+            (Action::Undo, Some(KeyBinding::control(KeyboardKey::KEY_Z))),
+            (Action::Redo, Some(KeyBinding::control(KeyboardKey::KEY_Y))),
+            (Action::ToggleColorBox, Some(KeyBinding::control(KeyboardKey::KEY_L))),
+            (Action::ToggleStatusBar, None),
+            (Action::FlipHorizontal, Some(KeyBinding::control_shift(KeyboardKey::KEY_H))),
+            (Action::FlipVertical, Some(KeyBinding::control_shift(KeyboardKey::KEY_V))),
+            (Action::ClearImage, Some(KeyBinding::control_shift(KeyboardKey::KEY_N))),
+            (Action::EditColors, None),
+            (Action::SwapColors, Some(KeyBinding::key(KeyboardKey::KEY_X))),
+            (Action::DefaultColors, Some(KeyBinding::key(KeyboardKey::KEY_D))),
+            (Action::ImportPalette, None),
+            (Action::ExportPalette, Some(KeyBinding::control_shift(KeyboardKey::KEY_P))),
+            (Action::ExtractPalette, Some(KeyBinding::control_shift(KeyboardKey::KEY_E))),
+        ]);
+        Keymap { bindings }
+    }
+
+    pub fn binding(&self, action: &Action) -> Option<KeyBinding> {
+        self.bindings.iter().find(|(bound, _)| bound == action).and_then(|(_, binding)| *binding)
+    }
+
+    /// The action whose key was pressed this frame
+    pub fn pressed(&self, input: &FrameInput) -> Option<&Action> {
+        self.bindings.iter()
+            .find(|(_, binding)| binding.is_some_and(|binding| binding.is_pressed(input)))
+            .map(|(action, _)| action)
+    }
+
+    /// Apply the lines of a keymap file over these bindings. Actions the keymap does not know are
+    /// errors, and so is a key bound to two actions, naming the action it is already bound to.
+    pub fn parse(&self, text: &str) -> Result<Keymap, KeymapError> {
+        let mut keymap = self.clone();
+        let mut assigned_lines = Vec::new();
+        for (line_number, line) in text.lines().enumerate().map(|(i, line)| (i + 1, line.trim())) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (name, keys) = line.split_once('=')
+                .ok_or_else(|| KeymapError::Line(line_number, "expected <action> = <key>".to_string()))?;
+            let (name, keys) = (name.trim(), keys.trim());
+            let action = Action::parse(name)
+                .filter(|action| keymap.bindings.iter().any(|(known, _)| known == action))
+                .ok_or_else(|| KeymapError::Line(line_number, format!("unknown action '{}'", name)))?;
+            let binding = match keys {
+                "none" => None,
+                _ => Some(KeyBinding::parse(keys)
+                    .ok_or_else(|| KeymapError::Line(line_number, format!("'{}' is not a key", keys)))?),
+            };
+
+            let (_, bound) = keymap.bindings.iter_mut().find(|(known, _)| *known == action).unwrap();
+            *bound = binding;
+            assigned_lines.push((action, line_number));
+        }
+
+        keymap.check_conflicts(&assigned_lines)?;
+        Ok(keymap)
+    }
+
+    /// Point at the line of the later binding in the file, or of the one clashing with a default
+    fn check_conflicts(&self, assigned_lines: &[(Action, usize)]) -> Result<(), KeymapError> {
+        let line_of = |action: &Action| assigned_lines.iter().rev().find(|(a, _)| a == action).map(|(_, line)| *line);
+        for (i, (first, binding)) in self.bindings.iter().enumerate() {
+            let Some(binding) = binding else {
+                continue;
+            };
+            for (second, other) in &self.bindings[i + 1..] {
+                if *other != Some(*binding) {
+                    continue;
+                }
+                let line = line_of(first).max(line_of(second)).unwrap_or(0);
+                let (earlier, later) = if line_of(first) == Some(line) { (second, first) } else { (first, second) };
+                return Err(KeymapError::Line(line, format!("{} is already bound to {}, set {} = none to free it",
+                                                           binding, earlier.name(), later.name())));
+            }
+        }
+        Ok(())
+    }
+
+    /// Defaults with the file at the path applied, written out for editing if it does not exist yet
+    pub fn load(tools: &ToolRegistry, path: &Path) -> Result<Keymap, KeymapError> {
+        let defaults = Keymap::defaults(tools);
+        match fs::read_to_string(path) {
+            Ok(text) => defaults.parse(&text),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                if let Err(error) = path.parent().map_or(Ok(()), fs::create_dir_all)
+                    .and_then(|_| fs::write(path, defaults.serialize())) {
+                    eprintln!("Could not write the default keymap to {}: {}", path.display(), error);
+                }
+                Ok(defaults)
+            },
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut text = HEADER.to_string();
+        for (action, binding) in &self.bindings {
+            let keys = binding.map_or("none".to_string(), |binding| binding.to_string());
+            text += &format!("{} = {}\n", action.name(), keys);
+        }
+        text
+    }
+}
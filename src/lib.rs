@@ -1,4 +1,20 @@
+//! PaintIt, a small paint program. `ui::Ui` is the window; `document::Document` edits images
+//! with the same tools from other programs, without a window.
+
 pub mod actions;
 pub mod user_state;
 pub mod raylib_extensions;
+pub mod pixel_buffer;
+pub mod antialiasing;
+pub mod rasterization;
+pub mod color_space;
+pub mod palette;
+pub mod quantization;
+pub mod session;
+pub mod bitmap_font;
+pub mod operation;
+pub mod script;
+pub mod document;
+pub mod keymap;
+pub mod settings;
 pub mod ui;
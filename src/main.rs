@@ -1,6 +1,51 @@
+use std::path::PathBuf;
+use std::process;
 use PaintIt::ui::Ui;
 
+const USAGE: &str = "usage: PaintIt [--open <image>] [--record <session.log>] [--replay <session.log>]";
+
 fn main() {
+    let mut open = None;
+    let mut record = None;
+    let mut replay = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let target = match arg.as_str() {
+            "--open" => &mut open,
+            "--record" => &mut record,
+            "--replay" => &mut replay,
+            _ => {
+                eprintln!("Unknown argument {}\n{}", arg, USAGE);
+                process::exit(2);
+            }
+        };
+        match args.next() {
+            Some(path) => *target = Some(PathBuf::from(path)),
+            None => {
+                eprintln!("{} needs a file\n{}", arg, USAGE);
+                process::exit(2);
+            }
+        }
+    }
+
     let mut ui = Ui::init();
+    if let Some(path) = open {
+        if let Err(error) = ui.open_image(&path) {
+            eprintln!("Could not open image {}: {}", path.display(), error);
+            process::exit(1);
+        }
+    }
+    if let Some(path) = replay {
+        if let Err(error) = ui.replay(&path) {
+            eprintln!("Could not load session {}: {}", path.display(), error);
+            process::exit(1);
+        }
+    }
+    if let Some(path) = record {
+        if let Err(error) = ui.record(&path) {
+            eprintln!("Could not record session {}: {}", path.display(), error);
+            process::exit(1);
+        }
+    }
     ui.run();
-}
\ No newline at end of file
+}
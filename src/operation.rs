@@ -0,0 +1,80 @@
+use raylib::math::Vector2;
+use crate::actions::bucket::BucketState;
+use crate::actions::ellipse::EllipseState;
+use crate::actions::line::LineState;
+use crate::actions::polygon;
+use crate::actions::rectangle::RectangleState;
+use crate::actions::update_execute_action::{apply_frames, UpdateExecuteAction};
+use crate::bitmap_font;
+use crate::pixel_buffer::{PixelBuffer, MAX_IMAGE_SIZE};
+use crate::user_state::{CanvasVector2, UserState};
+
+/// One edit of the canvas, done without a window. The shapes and the fill are drawn by the
+/// same tools as the toolbar, with the colors and options of the user state.
+/// Positions are in canvas pixels.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Operation {
+    Line { from: Vector2, to: Vector2 },
+    /// From one corner to the opposite one
+    Rectangle { from: Vector2, to: Vector2 },
+    /// Inside the rectangle between the two corners
+    Ellipse { from: Vector2, to: Vector2 },
+    /// Closed outline through the points, at least three
+    Polygon(Vec<Vector2>),
+    /// Flood fill from the point
+    Fill(Vector2),
+    /// In raylib's default font, with its top left corner at the position
+    Text { position: Vector2, text: String, size: i32 },
+    /// Fill the whole image with the secondary color
+    Clear,
+    FlipHorizontal,
+    FlipVertical,
+    /// Stretch to the size, in pixels, ignored beyond `MAX_IMAGE_SIZE`
+    Resize { width: i32, height: i32 },
+}
+
+/// Press on the first point, drag to the second, then release
+fn drag(from: Vector2, to: Vector2) -> Vec<(CanvasVector2, bool)> {
+    vec![(CanvasVector2(from), true), (CanvasVector2(to), true), (CanvasVector2(to), false)]
+}
+
+fn click(position: Vector2) -> Vec<(CanvasVector2, bool)> {
+    vec![(CanvasVector2(position), true), (CanvasVector2(position), false)]
+}
+
+fn run_tool(mut tool: impl UpdateExecuteAction, frames: &[(CanvasVector2, bool)], user_state: &UserState,
+            canvas: &mut PixelBuffer) {
+    apply_frames(&mut tool, user_state, frames, canvas);
+}
+
+impl Operation {
+    pub fn apply(&self, user_state: &UserState, canvas: &mut PixelBuffer) {
+        match self {
+            Operation::Line { from, to } => run_tool(LineState::default(), &drag(*from, *to), user_state, canvas),
+            Operation::Rectangle { from, to } => {
+                run_tool(RectangleState::default(), &drag(*from, *to), user_state, canvas)
+            },
+            Operation::Ellipse { from, to } => {
+                run_tool(EllipseState::default(), &drag(*from, *to), user_state, canvas)
+            },
+            Operation::Polygon(points) => {
+                polygon::draw_outline(canvas, points, user_state.current_colors[0], user_state.antialiasing);
+            },
+            Operation::Fill(position) => run_tool(BucketState::default(), &click(*position), user_state, canvas),
+            Operation::Text { position, text, size } => {
+                bitmap_font::draw_text(canvas, text, position.x as i32, position.y as i32, *size,
+                                       user_state.current_colors[0]);
+            },
+            Operation::Clear => {
+                canvas.draw_rectangle(0, 0, canvas.width(), canvas.height(), user_state.current_colors[1]);
+            },
+            Operation::FlipHorizontal => canvas.flip_horizontal(),
+            Operation::FlipVertical => canvas.flip_vertical(),
+            Operation::Resize { width, height } => {
+                if (1..=MAX_IMAGE_SIZE).contains(width) && (1..=MAX_IMAGE_SIZE).contains(height) {
+                    *canvas = canvas.resized(*width, *height);
+                }
+            },
+        }
+    }
+}
@@ -0,0 +1,307 @@
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use raylib::color::Color;
+use crate::color_space::{parse_hex, to_hex, Hsv};
+
+/// Colors of the classic paint palette, top row first
+pub const DEFAULT_PALETTE: [Color; 28] = [
+    Color::new(0, 0, 0, 255),
+    Color::new(128, 128, 128, 255),
+    Color::new(128, 0, 0, 255),
+    Color::new(128, 128, 0, 255),
+    Color::new(0, 128, 0, 255),
+    Color::new(0, 128, 128, 255),
+    Color::new(0, 0, 128, 255),
+    Color::new(128, 0, 128, 255),
+    Color::new(128, 128, 64, 255),
+    Color::new(0, 64, 64, 255),
+    Color::new(0, 128, 255, 255),
+    Color::new(0, 64, 128, 255),
+    Color::new(128, 0, 255, 255),
+    Color::new(128, 64, 0, 255),
+    Color::new(255, 255, 255, 255),
+    Color::new(192, 192, 192, 255),
+    Color::new(255, 0, 0, 255),
+    Color::new(255, 255, 0, 255),
+    Color::new(0, 255, 0, 255),
+    Color::new(0, 255, 255, 255),
+    Color::new(0, 0, 255, 255),
+    Color::new(255, 0, 255, 255),
+    Color::new(255, 255, 128, 255),
+    Color::new(0, 255, 128, 255),
+    Color::new(128, 255, 255, 255),
+    Color::new(128, 128, 255, 255),
+    Color::new(255, 0, 128, 255),
+    Color::new(255, 128, 64, 255),
+];
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum PaletteFormat {
+    /// GIMP palette
+    Gpl,
+    /// Paint Shop Pro palette
+    JascPal,
+    /// Adobe Photoshop color swatches
+    Aco,
+    /// One RRGGBB color per line
+    Hex,
+}
+
+impl PaletteFormat {
+    pub const ALL: [PaletteFormat; 4] = [PaletteFormat::Gpl, PaletteFormat::JascPal, PaletteFormat::Aco, PaletteFormat::Hex];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            PaletteFormat::Gpl => "gpl",
+            PaletteFormat::JascPal => "pal",
+            PaletteFormat::Aco => "aco",
+            PaletteFormat::Hex => "hex",
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<PaletteFormat> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        PaletteFormat::ALL.into_iter().find(|format| format.extension() == extension)
+    }
+}
+
+#[derive(Debug)]
+pub enum PaletteError {
+    /// Line number starting at 1, of a text format
+    Line(usize, String),
+    /// Byte offset, of a binary format
+    Offset(usize, String),
+    UnknownFormat,
+    Io(std::io::Error),
+}
+
+impl Display for PaletteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PaletteError::Line(line, message) => write!(f, "line {}: {}", line, message),
+            PaletteError::Offset(offset, message) => write!(f, "byte {}: {}", offset, message),
+            PaletteError::UnknownFormat => write!(f, "unknown palette format"),
+            PaletteError::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for PaletteError {}
+
+impl From<std::io::Error> for PaletteError {
+    fn from(error: std::io::Error) -> Self {
+        PaletteError::Io(error)
+    }
+}
+
+pub fn parse(format: PaletteFormat, data: &[u8]) -> Result<Vec<Color>, PaletteError> {
+    match format {
+        PaletteFormat::Aco => parse_aco(data),
+        _ => {
+            let text = std::str::from_utf8(data)
+                .map_err(|error| PaletteError::Offset(error.valid_up_to(), "invalid UTF-8".to_string()))?;
+            match format {
+                PaletteFormat::Gpl => parse_gpl(text),
+                PaletteFormat::JascPal => parse_jasc_pal(text),
+                _ => parse_hex_list(text),
+            }
+        }
+    }
+}
+
+pub fn serialize(format: PaletteFormat, colors: &[Color]) -> Vec<u8> {
+    match format {
+        PaletteFormat::Gpl => {
+            let mut text = String::from("GIMP Palette\nName: PaintIt\nColumns: 14\n#\n");
+            for color in colors {
+                text += &format!("{:3} {:3} {:3}\t{}\n", color.r, color.g, color.b, to_hex(*color));
+            }
+            text.into_bytes()
+        }
+        PaletteFormat::JascPal => {
+            let mut text = format!("JASC-PAL\r\n0100\r\n{}\r\n", colors.len());
+            for color in colors {
+                text += &format!("{} {} {}\r\n", color.r, color.g, color.b);
+            }
+            text.into_bytes()
+        }
+        PaletteFormat::Aco => serialize_aco(colors),
+        PaletteFormat::Hex => {
+            colors.iter()
+                .map(|color| to_hex(Color { a: 255, ..*color })[1..].to_ascii_lowercase() + "\n")
+                .collect::<String>()
+                .into_bytes()
+        }
+    }
+}
+
+/// Load a palette, the format comes from the file extension
+pub fn load(path: &Path) -> Result<Vec<Color>, PaletteError> {
+    let format = PaletteFormat::from_path(path).ok_or(PaletteError::UnknownFormat)?;
+    parse(format, &std::fs::read(path)?)
+}
+
+/// Save a palette, the format comes from the file extension
+pub fn save(path: &Path, colors: &[Color]) -> Result<(), PaletteError> {
+    let format = PaletteFormat::from_path(path).ok_or(PaletteError::UnknownFormat)?;
+    std::fs::write(path, serialize(format, colors))?;
+    Ok(())
+}
+
+/// Parse "r g b" at the start of the line, anything after the third number is ignored
+fn parse_rgb(line_number: usize, line: &str) -> Result<Color, PaletteError> {
+    let mut channels = [0u8; 3];
+    let mut words = line.split_whitespace();
+    for channel in channels.iter_mut() {
+        let word = words.next()
+            .ok_or_else(|| PaletteError::Line(line_number, "expected three color components".to_string()))?;
+        *channel = word.parse()
+            .map_err(|_| PaletteError::Line(line_number, format!("'{}' is not a number between 0 and 255", word)))?;
+    }
+    Ok(Color::new(channels[0], channels[1], channels[2], 255))
+}
+
+fn parse_gpl(text: &str) -> Result<Vec<Color>, PaletteError> {
+    let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line.trim()));
+
+    if lines.next().map(|(_, line)| line) != Some("GIMP Palette") {
+        return Err(PaletteError::Line(1, "missing 'GIMP Palette' header".to_string()));
+    }
+
+    let mut colors = Vec::new();
+    for (line_number, line) in lines {
+        let header = ["Name:", "Columns:"].iter().any(|key| line.starts_with(key));
+        if line.is_empty() || line.starts_with('#') || header {
+            continue;
+        }
+        colors.push(parse_rgb(line_number, line)?);
+    }
+    Ok(colors)
+}
+
+fn parse_jasc_pal(text: &str) -> Result<Vec<Color>, PaletteError> {
+    let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line.trim()));
+
+    if lines.next().map(|(_, line)| line) != Some("JASC-PAL") {
+        return Err(PaletteError::Line(1, "missing 'JASC-PAL' header".to_string()));
+    }
+    if lines.next().map(|(_, line)| line) != Some("0100") {
+        return Err(PaletteError::Line(2, "unsupported version, expected '0100'".to_string()));
+    }
+    let count: usize = match lines.next() {
+        Some((_, line)) => line.parse()
+            .map_err(|_| PaletteError::Line(3, format!("'{}' is not a color count", line)))?,
+        None => return Err(PaletteError::Line(3, "missing color count".to_string())),
+    };
+
+    let mut colors = Vec::with_capacity(count);
+    let mut last_line = 3;
+    for (line_number, line) in lines.filter(|(_, line)| !line.is_empty()) {
+        if colors.len() == count {
+            return Err(PaletteError::Line(line_number, format!("more than {} colors", count)));
+        }
+        colors.push(parse_rgb(line_number, line)?);
+        last_line = line_number;
+    }
+
+    if colors.len() < count {
+        return Err(PaletteError::Line(last_line, format!("expected {} colors, found {}", count, colors.len())));
+    }
+    Ok(colors)
+}
+
+fn parse_hex_list(text: &str) -> Result<Vec<Color>, PaletteError> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(line_number, line)| {
+            parse_hex(line).ok_or_else(|| PaletteError::Line(line_number, format!("'{}' is not a RRGGBB color", line)))
+        })
+        .collect()
+}
+
+/// Reads big endian words, reporting the offset of data that runs out
+struct AcoReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl AcoReader<'_> {
+    fn u16(&mut self) -> Result<u16, PaletteError> {
+        let bytes = self.data.get(self.offset..self.offset + 2)
+            .ok_or_else(|| PaletteError::Offset(self.offset, "unexpected end of file".to_string()))?;
+        self.offset += 2;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, PaletteError> {
+        Ok(((self.u16()? as u32) << 16) | self.u16()? as u32)
+    }
+}
+
+fn parse_aco(data: &[u8]) -> Result<Vec<Color>, PaletteError> {
+    let mut reader = AcoReader { data, offset: 0 };
+
+    // Version 1 is usually followed by a version 2 section with the same colors and their names
+    let version = reader.u16()?;
+    if version != 1 && version != 2 {
+        return Err(PaletteError::Offset(0, format!("unsupported version {}", version)));
+    }
+
+    let count = reader.u16()?;
+    let mut colors = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let offset = reader.offset;
+        let space = reader.u16()?;
+        let components = [reader.u16()?, reader.u16()?, reader.u16()?, reader.u16()?];
+        let color = match space {
+            0 => Color::new(
+                (components[0] / 257) as u8,
+                (components[1] / 257) as u8,
+                (components[2] / 257) as u8,
+                255,
+            ),
+            1 => Hsv {
+                hue: components[0] as f32 / 65535f32 * 360f32,
+                saturation: components[1] as f32 / 65535f32,
+                value: components[2] as f32 / 65535f32,
+            }.to_color(255),
+            8 => {
+                let gray = 255 - (components[0] as u32 * 255 / 10000).min(255) as u8;
+                Color::new(gray, gray, gray, 255)
+            }
+            _ => return Err(PaletteError::Offset(offset, format!("unsupported color space {}", space))),
+        };
+        colors.push(color);
+
+        if version == 2 {
+            // Name length in UTF-16 code units, including the terminator
+            let length = reader.u32()?;
+            for _ in 0..length {
+                reader.u16()?;
+            }
+        }
+    }
+    Ok(colors)
+}
+
+/// Version 1 followed by version 2 with empty names, as Photoshop writes them
+fn serialize_aco(colors: &[Color]) -> Vec<u8> {
+    let mut data = Vec::new();
+    for version in [1u16, 2u16] {
+        data.extend(version.to_be_bytes());
+        data.extend((colors.len() as u16).to_be_bytes());
+        for color in colors {
+            let components = [0, color.r as u16 * 257, color.g as u16 * 257, color.b as u16 * 257, 0];
+            for component in components {
+                data.extend(component.to_be_bytes());
+            }
+            if version == 2 {
+                data.extend(1u32.to_be_bytes());
+                data.extend(0u16.to_be_bytes());
+            }
+        }
+    }
+    data
+}
@@ -0,0 +1,563 @@
+use std::sync::Arc;
+use raylib::color::Color;
+use raylib::math::{Rectangle, Vector2};
+
+/// Width and height of the tiles, smaller at the right and bottom edges
+pub const TILE_SIZE: i32 = 256;
+/// Largest width or height an image can be given or resized to
+pub const MAX_IMAGE_SIZE: i32 = 16384;
+
+#[derive(Debug, Clone)]
+enum Tile {
+    /// Every pixel has the color, until one is drawn on
+    Solid(Color),
+    /// Rows of pixels, top to bottom
+    Pixels(Vec<Color>),
+}
+
+impl Tile {
+    fn get(&self, offset: usize) -> Color {
+        match self {
+            Tile::Solid(color) => *color,
+            Tile::Pixels(pixels) => pixels[offset],
+        }
+    }
+}
+
+/// One bit per pixel, row after row, for the pixels a flood fill reached
+struct PixelMask {
+    width: usize,
+    bits: Vec<u64>,
+}
+
+impl PixelMask {
+    fn new(width: i32, height: i32) -> Self {
+        PixelMask { width: width as usize, bits: vec![0; (width as usize * height as usize).div_ceil(64)] }
+    }
+
+    /// Words holding the bits from `start` to before `end`, with the mask of those bits in each
+    fn words(start: usize, end: usize) -> impl Iterator<Item = (usize, u64)> {
+        (start / 64..end.div_ceil(64)).map(move |word| {
+            let from = start.max(word * 64) - word * 64;
+            let to = end.min(word * 64 + 64) - word * 64;
+            let mask = if to - from == 64 { u64::MAX } else { ((1u64 << (to - from)) - 1) << from };
+            (word, mask)
+        })
+    }
+
+    fn bit(&self, x: i32, y: i32) -> usize {
+        y as usize * self.width + x as usize
+    }
+
+    fn get(&self, x: i32, y: i32) -> bool {
+        let bit = self.bit(x, y);
+        self.bits[bit / 64] & (1 << (bit % 64)) != 0
+    }
+
+    /// Set the pixels of the row from `x0` to `x1`, both included
+    fn set_span(&mut self, y: i32, x0: i32, x1: i32) {
+        for (word, mask) in PixelMask::words(self.bit(x0, y), self.bit(x1, y) + 1) {
+            self.bits[word] |= mask;
+        }
+    }
+
+    /// Number of pixels set in the row from `x0` to `x1`, both included
+    fn span_count(&self, y: i32, x0: i32, x1: i32) -> usize {
+        PixelMask::words(self.bit(x0, y), self.bit(x1, y) + 1)
+            .map(|(word, mask)| (self.bits[word] & mask).count_ones() as usize)
+            .sum()
+    }
+}
+
+/// RGBA pixels kept in memory, so tools can draw without a window or GPU.
+/// The drawing functions follow raylib's Image ones: pixels are replaced, not blended.
+///
+/// Pixels are stored in tiles, so images larger than a texture can be edited and shown a tile at
+/// a time. Tiles are shared between clones until drawn on, and blank ones take no memory.
+#[derive(Clone, Default)]
+pub struct PixelBuffer {
+    width: i32,
+    height: i32,
+    /// Rows of tiles, top to bottom
+    tiles: Vec<Arc<Tile>>,
+    /// Area drawn on since the last `take_damage`, so a texture can be updated with only that part
+    damage: Option<Rectangle>,
+}
+
+/// Only the size, a dump of the pixels would flood any log the tools are printed to
+impl std::fmt::Debug for PixelBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "PixelBuffer({}x{})", self.width, self.height)
+    }
+}
+
+/// Buffers are equal when their pixels are, whatever was drawn on them
+impl PartialEq for PixelBuffer {
+    fn eq(&self, other: &Self) -> bool {
+        if self.width != other.width || self.height != other.height {
+            return false;
+        }
+        (0..self.tiles.len()).all(|index| {
+            let (a, b) = (&self.tiles[index], &other.tiles[index]);
+            let area = self.tile_area(index);
+            Arc::ptr_eq(a, b) || (0..(area.width * area.height) as usize).all(|offset| a.get(offset) == b.get(offset))
+        })
+    }
+}
+
+impl PixelBuffer {
+    pub fn new(width: i32, height: i32, color: Color) -> Self {
+        let width = width.max(0);
+        let height = height.max(0);
+        let tiles = ((width + TILE_SIZE - 1) / TILE_SIZE * ((height + TILE_SIZE - 1) / TILE_SIZE)) as usize;
+        PixelBuffer {
+            width,
+            height,
+            tiles: (0..tiles).map(|_| Arc::new(Tile::Solid(color))).collect(),
+            damage: None,
+        }
+    }
+
+    /// Build with the color of every pixel given by its position
+    fn from_fn(width: i32, height: i32, color: impl Fn(i32, i32) -> Color) -> Self {
+        let mut buffer = PixelBuffer::new(width, height, Color::new(0, 0, 0, 0));
+        for index in 0..buffer.tiles.len() {
+            let area = buffer.tile_area(index);
+            let (left, top) = (area.x as i32, area.y as i32);
+            let pixels = (top..top + area.height as i32)
+                .flat_map(|y| (left..left + area.width as i32).map(move |x| (x, y)))
+                .map(|(x, y)| color(x, y))
+                .collect();
+            buffer.tiles[index] = Arc::new(Tile::Pixels(pixels));
+        }
+        buffer
+    }
+
+    /// Build from rows of pixels, top to bottom
+    pub fn from_pixels(width: i32, height: i32, pixels: Vec<Color>) -> Option<Self> {
+        if width < 0 || height < 0 || pixels.len() as u64 != width as u64 * height as u64 {
+            return None;
+        }
+        Some(PixelBuffer::from_fn(width, height, |x, y| pixels[y as usize * width as usize + x as usize]))
+    }
+
+    /// Build from tightly packed RGBA bytes, top to bottom
+    pub fn from_rgba(width: i32, height: i32, bytes: &[u8]) -> Option<Self> {
+        let pixels = bytes.chunks_exact(4)
+            .map(|rgba| Color::new(rgba[0], rgba[1], rgba[2], rgba[3]))
+            .collect();
+        PixelBuffer::from_pixels(width, height, pixels)
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// Every pixel, row after row, top to bottom
+    pub fn pixels(&self) -> impl Iterator<Item = Color> + '_ {
+        (0..self.height).flat_map(move |y| (0..self.width).map(move |x| self.get_color(x, y)))
+    }
+
+    pub fn to_rgba(&self) -> Vec<u8> {
+        self.area_to_rgba(Rectangle::new(0f32, 0f32, self.width as f32, self.height as f32))
+    }
+
+    /// RGBA bytes of the area, row after row, copied from the tiles one run of a row at a time.
+    /// The area must be inside the buffer.
+    pub fn area_to_rgba(&self, area: Rectangle) -> Vec<u8> {
+        let (left, top) = (area.x as i32, area.y as i32);
+        let (right, bottom) = (left + area.width as i32, top + area.height as i32);
+        assert!(0 <= left && 0 <= top && right <= self.width && bottom <= self.height,
+                "{:?} is not inside a {}x{} buffer", area, self.width, self.height);
+
+        let rgba = |color: &Color| [color.r, color.g, color.b, color.a];
+        let mut bytes = Vec::with_capacity((right - left).max(0) as usize * (bottom - top).max(0) as usize * 4);
+        for y in top..bottom {
+            let mut x = left;
+            while x < right {
+                let end = right.min((x / TILE_SIZE + 1) * TILE_SIZE);
+                let (index, offset) = self.locate(x, y);
+                let count = (end - x) as usize;
+                match &*self.tiles[index] {
+                    Tile::Solid(color) => bytes.extend((0..count).flat_map(|_| rgba(color))),
+                    Tile::Pixels(pixels) => bytes.extend(pixels[offset..offset + count].iter().flat_map(rgba)),
+                }
+                x = end;
+            }
+        }
+        bytes
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        0 <= x && x < self.width && 0 <= y && y < self.height
+    }
+
+    fn tile_columns(&self) -> i32 {
+        (self.width + TILE_SIZE - 1) / TILE_SIZE
+    }
+
+    pub fn tile_count(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// Pixels covered by the tile, in buffer coordinates
+    pub fn tile_area(&self, index: usize) -> Rectangle {
+        let columns = self.tile_columns().max(1);
+        let (x, y) = (index as i32 % columns * TILE_SIZE, index as i32 / columns * TILE_SIZE);
+        Rectangle::new(x as f32, y as f32, (self.width - x).min(TILE_SIZE) as f32,
+                       (self.height - y).min(TILE_SIZE) as f32)
+    }
+
+    /// Indices of the tiles overlapping the area
+    pub fn tiles_in(&self, area: Rectangle) -> Vec<usize> {
+        if area.width <= 0f32 || area.height <= 0f32 || area.x + area.width <= 0f32 || area.y + area.height <= 0f32 {
+            return Vec::new();
+        }
+
+        let columns = self.tile_columns();
+        let rows = (self.height + TILE_SIZE - 1) / TILE_SIZE;
+        let first_column = (area.x as i32).max(0) / TILE_SIZE;
+        let first_row = (area.y as i32).max(0) / TILE_SIZE;
+        let last_column = (((area.x + area.width).ceil() as i32 - 1) / TILE_SIZE).min(columns - 1);
+        let last_row = (((area.y + area.height).ceil() as i32 - 1) / TILE_SIZE).min(rows - 1);
+
+        (first_row..=last_row)
+            .flat_map(|row| (first_column..=last_column).map(move |column| (row * columns + column) as usize))
+            .collect()
+    }
+
+    /// Tile of the pixel, and the pixel's offset inside it
+    fn locate(&self, x: i32, y: i32) -> (usize, usize) {
+        let column = x / TILE_SIZE;
+        let tile_width = (self.width - column * TILE_SIZE).min(TILE_SIZE);
+        let index = (y / TILE_SIZE * self.tile_columns() + column) as usize;
+        (index, (y % TILE_SIZE * tile_width + x % TILE_SIZE) as usize)
+    }
+
+    /// Pixels of the tile, copied first if the tile is shared or solid
+    fn tile_pixels_mut(&mut self, index: usize) -> &mut Vec<Color> {
+        let area = self.tile_area(index);
+        let tile = Arc::make_mut(&mut self.tiles[index]);
+        if let Tile::Solid(color) = *tile {
+            *tile = Tile::Pixels(vec![color; (area.width * area.height) as usize]);
+        }
+        match tile {
+            Tile::Pixels(pixels) => pixels,
+            Tile::Solid(_) => unreachable!(),
+        }
+    }
+
+    /// Grow the damaged area to cover the rectangle, which is inside the buffer
+    fn add_damage(&mut self, x: i32, y: i32, width: i32, height: i32) {
+        let (x, y, width, height) = (x as f32, y as f32, width as f32, height as f32);
+        self.damage = Some(match self.damage {
+            None => Rectangle::new(x, y, width, height),
+            Some(damage) => {
+                let left = damage.x.min(x);
+                let top = damage.y.min(y);
+                let right = (damage.x + damage.width).max(x + width);
+                let bottom = (damage.y + damage.height).max(y + height);
+                Rectangle::new(left, top, right - left, bottom - top)
+            }
+        });
+    }
+
+    /// Area drawn on since the last call, in whole pixels, then start tracking anew
+    pub fn take_damage(&mut self) -> Option<Rectangle> {
+        self.damage.take()
+    }
+
+    /// Transparent outside the buffer
+    pub fn get_color(&self, x: i32, y: i32) -> Color {
+        if !self.contains(x, y) {
+            return Color::new(0, 0, 0, 0);
+        }
+        let (index, offset) = self.locate(x, y);
+        self.tiles[index].get(offset)
+    }
+
+    pub fn draw_pixel(&mut self, x: i32, y: i32, color: Color) {
+        if self.contains(x, y) {
+            let (index, offset) = self.locate(x, y);
+            self.tile_pixels_mut(index)[offset] = color;
+            self.add_damage(x, y, 1, 1);
+        }
+    }
+
+    pub fn draw_pixel_v(&mut self, position: Vector2, color: Color) {
+        self.draw_pixel(position.x as i32, position.y as i32, color);
+    }
+
+    /// Fill the rectangle, clipped to the buffer
+    pub fn draw_rectangle(&mut self, x: i32, y: i32, width: i32, height: i32, color: Color) {
+        let x_start = x.max(0);
+        let y_start = y.max(0);
+        let x_end = (x + width).min(self.width);
+        let y_end = (y + height).min(self.height);
+        if x_end <= x_start || y_end <= y_start {
+            return;
+        }
+
+        let area = Rectangle::new(x_start as f32, y_start as f32, (x_end - x_start) as f32, (y_end - y_start) as f32);
+        for index in self.tiles_in(area) {
+            let tile = self.tile_area(index);
+            let (left, top) = (tile.x as i32, tile.y as i32);
+            let (right, bottom) = (left + tile.width as i32, top + tile.height as i32);
+            if x_start <= left && right <= x_end && y_start <= top && bottom <= y_end {
+                self.tiles[index] = Arc::new(Tile::Solid(color));
+                continue;
+            }
+
+            let tile_width = tile.width as i32;
+            let (from, to) = (x_start.max(left) - left, x_end.min(right) - left);
+            let pixels = self.tile_pixels_mut(index);
+            for row in y_start.max(top) - top..y_end.min(bottom) - top {
+                let start = (row * tile_width) as usize;
+                pixels[start + from as usize..start + to as usize].fill(color);
+            }
+        }
+        self.add_damage(x_start, y_start, x_end - x_start, y_end - y_start);
+    }
+
+    /// Outline drawn inside the rectangle, `thick` pixels wide
+    pub fn draw_rectangle_lines(&mut self, rectangle: Rectangle, thick: i32, color: Color) {
+        let (x, y) = (rectangle.x as i32, rectangle.y as i32);
+        let (width, height) = (rectangle.width as i32, rectangle.height as i32);
+
+        self.draw_rectangle(x, y, width, thick, color);
+        self.draw_rectangle(x, y + thick, thick, height - thick * 2, color);
+        self.draw_rectangle(x + width - thick, y + thick, thick, height - thick * 2, color);
+        self.draw_rectangle(x, y + height - thick, width, thick, color);
+    }
+
+    /// Bresenham line, including both ends
+    pub fn draw_line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: Color) {
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let sx = if x0 < x1 { 1 } else { -1 };
+        let sy = if y0 < y1 { 1 } else { -1 };
+        let mut error = dx + dy;
+        let (mut x, mut y) = (x0, y0);
+
+        loop {
+            self.draw_pixel(x, y, color);
+            if x == x1 && y == y1 {
+                break;
+            }
+            let e2 = 2 * error;
+            if e2 >= dy {
+                error += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                error += dx;
+                y += sy;
+            }
+        }
+    }
+
+    pub fn draw_line_v(&mut self, start: Vector2, end: Vector2, color: Color) {
+        self.draw_line(start.x as i32, start.y as i32, end.x as i32, end.y as i32, color);
+    }
+
+    /// Filled circle, with the same midpoint algorithm as raylib's ImageDrawCircle
+    pub fn draw_circle(&mut self, center_x: i32, center_y: i32, radius: i32, color: Color) {
+        let mut x = 0;
+        let mut y = radius;
+        let mut decision = 3 - 2 * radius;
+
+        while y >= x {
+            self.draw_rectangle(center_x - x, center_y + y, x * 2, 1, color);
+            self.draw_rectangle(center_x - x, center_y - y, x * 2, 1, color);
+            self.draw_rectangle(center_x - y, center_y + x, y * 2, 1, color);
+            self.draw_rectangle(center_x - y, center_y - x, y * 2, 1, color);
+            x += 1;
+
+            if decision > 0 {
+                y -= 1;
+                decision += 4 * (x - y) + 10;
+            } else {
+                decision += 4 * x + 6;
+            }
+        }
+    }
+
+    /// Last pixel from `x` to the right of the row that starts a run of `target` pixels at `x`,
+    /// skipping over solid tiles at once
+    fn run_end(&self, x: i32, y: i32, target: Color) -> i32 {
+        let mut x = x;
+        while x + 1 < self.width {
+            let (index, offset) = self.locate(x + 1, y);
+            match &*self.tiles[index] {
+                Tile::Solid(color) if *color == target => {
+                    x = ((x + 1) / TILE_SIZE * TILE_SIZE + TILE_SIZE - 1).min(self.width - 1);
+                },
+                Tile::Pixels(pixels) if pixels[offset] == target => x += 1,
+                _ => break,
+            }
+        }
+        x
+    }
+
+    /// First pixel of the run of `target` pixels that reaches `x`, skipping over solid tiles at once
+    fn run_start(&self, x: i32, y: i32, target: Color) -> i32 {
+        let mut x = x;
+        while x > 0 {
+            let (index, offset) = self.locate(x - 1, y);
+            match &*self.tiles[index] {
+                Tile::Solid(color) if *color == target => x = (x - 1) / TILE_SIZE * TILE_SIZE,
+                Tile::Pixels(pixels) if pixels[offset] == target => x -= 1,
+                _ => break,
+            }
+        }
+        x
+    }
+
+    /// Replace the area of same colored pixels around the point, joined by their sides. Tiles the
+    /// area covers completely become solid, so filling a blank image takes no pixel memory.
+    pub fn flood_fill(&mut self, x: i32, y: i32, color: Color) -> bool {
+        if !self.contains(x, y) {
+            return false;
+        }
+        let target = self.get_color(x, y);
+        if target == color {
+            return false;
+        }
+
+        // Scanline fill: each seed grows into its whole run in the row, then seeds the runs it
+        // touches in the rows above and below
+        let mut reached = PixelMask::new(self.width, self.height);
+        let (mut left, mut top, mut right, mut bottom) = (x, y, x, y);
+        let mut seeds = vec![(x, y)];
+        while let Some((x, y)) = seeds.pop() {
+            if reached.get(x, y) {
+                continue;
+            }
+            let (start, end) = (self.run_start(x, y, target), self.run_end(x, y, target));
+            reached.set_span(y, start, end);
+            (left, right, top, bottom) = (left.min(start), right.max(end), top.min(y), bottom.max(y));
+
+            for row in [y - 1, y + 1] {
+                if row < 0 || row >= self.height {
+                    continue;
+                }
+                let mut column = start;
+                while column <= end {
+                    if self.get_color(column, row) != target {
+                        column += 1;
+                        continue;
+                    }
+                    // A run is reached as a whole, so its first pixel tells
+                    if !reached.get(column, row) {
+                        seeds.push((column, row));
+                    }
+                    column = self.run_end(column, row, target) + 1;
+                }
+            }
+        }
+
+        let area = Rectangle::new(left as f32, top as f32, (right - left + 1) as f32, (bottom - top + 1) as f32);
+        for index in self.tiles_in(area) {
+            let tile = self.tile_area(index);
+            let (tile_left, tile_top) = (tile.x as i32, tile.y as i32);
+            let (tile_width, tile_height) = (tile.width as i32, tile.height as i32);
+            let tile_right = tile_left + tile_width - 1;
+            let counts: Vec<usize> = (tile_top..tile_top + tile_height)
+                .map(|row| reached.span_count(row, tile_left, tile_right))
+                .collect();
+            if counts.iter().all(|count| *count == tile_width as usize) {
+                self.tiles[index] = Arc::new(Tile::Solid(color));
+                continue;
+            }
+
+            let pixels = self.tile_pixels_mut(index);
+            for (row, count) in counts.into_iter().enumerate() {
+                if count == 0 {
+                    continue;
+                }
+                for column in 0..tile_width {
+                    if reached.get(tile_left + column, tile_top + row as i32) {
+                        pixels[row * tile_width as usize + column as usize] = color;
+                    }
+                }
+            }
+        }
+        self.add_damage(left, top, right - left + 1, bottom - top + 1);
+        true
+    }
+
+    /// Copy of the area inside the buffer, transparent where the area goes outside
+    pub fn crop(&self, area: Rectangle) -> PixelBuffer {
+        let (x, y) = (area.x as i32, area.y as i32);
+        let mut cropped = PixelBuffer::new(area.width as i32, area.height as i32, Color::new(0, 0, 0, 0));
+        for row in 0..cropped.height {
+            for column in 0..cropped.width {
+                cropped.draw_pixel(column, row, self.get_color(x + column, y + row));
+            }
+        }
+        cropped
+    }
+
+    /// Mirror left to right
+    pub fn flip_horizontal(&mut self) {
+        let (width, height) = (self.width, self.height);
+        *self = PixelBuffer::from_fn(width, height, |x, y| self.get_color(width - 1 - x, y));
+        self.add_damage(0, 0, width, height);
+    }
+
+    /// Mirror top to bottom
+    pub fn flip_vertical(&mut self) {
+        let (width, height) = (self.width, self.height);
+        *self = PixelBuffer::from_fn(width, height, |x, y| self.get_color(x, height - 1 - y));
+        self.add_damage(0, 0, width, height);
+    }
+
+    /// Copy stretched to the size, taking the nearest pixel
+    pub fn resized(&self, width: i32, height: i32) -> PixelBuffer {
+        PixelBuffer::from_fn(width, height, |x, y| {
+            let x = x as i64 * self.width as i64 / width as i64;
+            let y = y as i64 * self.height as i64 / height as i64;
+            self.get_color(x as i32, y as i32)
+        })
+    }
+
+    /// Draw another buffer with its top left corner at the position, alpha blended over these pixels
+    pub fn draw_buffer(&mut self, source: &PixelBuffer, x: i32, y: i32) {
+        for row in 0..source.height {
+            for column in 0..source.width {
+                let (target_x, target_y) = (x + column, y + row);
+                if !self.contains(target_x, target_y) {
+                    continue;
+                }
+
+                let src = source.get_color(column, row);
+                let dst = self.get_color(target_x, target_y);
+                self.draw_pixel(target_x, target_y, blend(src, dst));
+            }
+        }
+    }
+}
+
+/// Straight alpha "over" operator
+pub fn blend(src: Color, dst: Color) -> Color {
+    let src_alpha = src.a as f32 / 255f32;
+    let dst_alpha = dst.a as f32 / 255f32;
+    let alpha = src_alpha + dst_alpha * (1f32 - src_alpha);
+    if alpha == 0f32 {
+        return Color::new(0, 0, 0, 0);
+    }
+
+    let channel = |s: u8, d: u8| {
+        ((s as f32 * src_alpha + d as f32 * dst_alpha * (1f32 - src_alpha)) / alpha).round() as u8
+    };
+    Color::new(
+        channel(src.r, dst.r),
+        channel(src.g, dst.g),
+        channel(src.b, dst.b),
+        (alpha * 255f32).round() as u8,
+    )
+}
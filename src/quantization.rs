@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use raylib::color::Color;
+use crate::pixel_buffer::PixelBuffer;
+
+/// Distinct opaque color and how many pixels have it
+#[derive(Debug, Copy, Clone)]
+struct Entry {
+    rgb: [u8; 3],
+    count: u64,
+}
+
+/// Count the colors, transparent pixels are not part of the picture
+fn histogram(pixels: &[Color]) -> Vec<Entry> {
+    let mut counts: HashMap<[u8; 3], u64> = HashMap::new();
+    for pixel in pixels.iter().filter(|pixel| pixel.a != 0) {
+        *counts.entry([pixel.r, pixel.g, pixel.b]).or_default() += 1;
+    }
+
+    let mut entries: Vec<Entry> = counts.into_iter().map(|(rgb, count)| Entry { rgb, count }).collect();
+    // HashMap order is random, keep the result reproducible
+    entries.sort_by_key(|entry| entry.rgb);
+    entries
+}
+
+fn add_weighted(sum: &mut [f64; 3], entry: &Entry) {
+    for (channel, value) in sum.iter_mut().zip(entry.rgb) {
+        *channel += value as f64 * entry.count as f64;
+    }
+}
+
+fn average(entries: &[Entry]) -> [f64; 3] {
+    let total: u64 = entries.iter().map(|entry| entry.count).sum();
+    let mut sum = [0f64; 3];
+    for entry in entries {
+        add_weighted(&mut sum, entry);
+    }
+    sum.map(|channel| channel / total as f64)
+}
+
+fn to_color(rgb: [f64; 3]) -> Color {
+    Color::new(rgb[0].round() as u8, rgb[1].round() as u8, rgb[2].round() as u8, 255)
+}
+
+/// Channel with the widest range in the box, and that range
+fn widest_channel(entries: &[Entry]) -> (usize, u8) {
+    (0..3)
+        .map(|channel| {
+            let min = entries.iter().map(|entry| entry.rgb[channel]).min().unwrap_or(0);
+            let max = entries.iter().map(|entry| entry.rgb[channel]).max().unwrap_or(0);
+            (channel, max - min)
+        })
+        .max_by_key(|(_, range)| *range)
+        .unwrap()
+}
+
+fn median_cut_boxes(entries: Vec<Entry>, size: usize) -> Vec<Vec<Entry>> {
+    let mut boxes = vec![entries];
+
+    while boxes.len() < size {
+        // Split the box whose colors are furthest apart
+        let Some((index, (channel, _))) = boxes.iter()
+            .enumerate()
+            .map(|(i, entries)| (i, widest_channel(entries)))
+            .filter(|(_, (_, range))| *range > 0)
+            .max_by_key(|(_, (_, range))| *range) else {
+            break;
+        };
+
+        let mut entries = boxes.swap_remove(index);
+        entries.sort_by_key(|entry| entry.rgb[channel]);
+
+        // Split at the median pixel, leaving at least one color on each side
+        let half: u64 = entries.iter().map(|entry| entry.count).sum::<u64>() / 2;
+        let mut accumulated = 0;
+        let mut split = entries.len() - 1;
+        for (i, entry) in entries.iter().enumerate() {
+            accumulated += entry.count;
+            if accumulated >= half {
+                split = i + 1;
+                break;
+            }
+        }
+        let split = split.clamp(1, entries.len() - 1);
+
+        let upper = entries.split_off(split);
+        boxes.push(entries);
+        boxes.push(upper);
+    }
+
+    boxes
+}
+
+/// At most about `limit` pixels of the picture, on an even grid, so large pictures quantize quickly
+pub fn sample(image: &PixelBuffer, limit: usize) -> Vec<Color> {
+    let area = image.width() as usize * image.height() as usize;
+    let step = (area as f64 / limit.max(1) as f64).sqrt().ceil().max(1f64) as usize;
+    (0..image.height()).step_by(step)
+        .flat_map(|y| (0..image.width()).step_by(step).map(move |x| image.get_color(x, y)))
+        .collect()
+}
+
+/// Sort dark to light, so extracted palettes read in a stable order
+fn sort_by_luminance(colors: &mut [Color]) {
+    colors.sort_by_key(|color| 299 * color.r as u32 + 587 * color.g as u32 + 114 * color.b as u32);
+}
+
+/// Reduce the picture to at most `size` colors by repeatedly splitting the color box with the widest range at its median
+pub fn median_cut(pixels: &[Color], size: usize) -> Vec<Color> {
+    let entries = histogram(pixels);
+    if entries.is_empty() || size == 0 {
+        return Vec::new();
+    }
+
+    let mut colors: Vec<Color> = median_cut_boxes(entries, size)
+        .iter()
+        .map(|entries| to_color(average(entries)))
+        .collect();
+    sort_by_luminance(&mut colors);
+    colors
+}
+
+/// Reduce the picture to at most `size` colors with k-means clustering, starting from the median cut
+pub fn k_means(pixels: &[Color], size: usize, iterations: usize) -> Vec<Color> {
+    let entries = histogram(pixels);
+    if entries.is_empty() || size == 0 {
+        return Vec::new();
+    }
+
+    let mut centroids: Vec<[f64; 3]> = median_cut_boxes(entries.clone(), size)
+        .iter()
+        .map(|entries| average(entries))
+        .collect();
+
+    let distance = |a: [f64; 3], b: [u8; 3]| {
+        (0..3).map(|channel| (a[channel] - b[channel] as f64).powi(2)).sum::<f64>()
+    };
+
+    for _ in 0..iterations {
+        let mut sums = vec![[0f64; 3]; centroids.len()];
+        let mut counts = vec![0u64; centroids.len()];
+
+        for entry in &entries {
+            let nearest = (0..centroids.len())
+                .min_by(|a, b| distance(centroids[*a], entry.rgb).total_cmp(&distance(centroids[*b], entry.rgb)))
+                .unwrap();
+            add_weighted(&mut sums[nearest], entry);
+            counts[nearest] += entry.count;
+        }
+
+        let updated: Vec<[f64; 3]> = sums.iter()
+            .zip(&counts)
+            .filter(|(_, count)| **count > 0)
+            .map(|(sum, count)| sum.map(|channel| channel / *count as f64))
+            .collect();
+
+        let converged = updated == centroids;
+        centroids = updated;
+        if converged {
+            break;
+        }
+    }
+
+    let mut colors: Vec<Color> = centroids.into_iter().map(to_color).collect();
+    sort_by_luminance(&mut colors);
+    colors.dedup();
+    colors
+}
@@ -0,0 +1,215 @@
+/// Horizontal run of pixels, both ends inclusive
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Span {
+    pub y: i32,
+    pub x_start: i32,
+    pub x_end: i32,
+}
+
+/// Outline of the ellipse inscribed in the rectangle with corners (x0, y0) and (x1, y1), given in
+/// any order, both corners inclusive. Uses the midpoint algorithm in its integer error form
+/// (Zingl, "A Rasterizing Algorithm for Drawing Curves"), so every pixel is set exactly once and
+/// even sized boxes are handled without offsetting the center.
+pub fn ellipse(x0: i32, y0: i32, x1: i32, y1: i32) -> Vec<(i32, i32)> {
+    let mut points = Vec::new();
+
+    let (mut left, mut right) = (x0.min(x1), x0.max(x1));
+    let (top, bottom) = (y0.min(y1), y0.max(y1));
+
+    let a = (right - left) as i64;
+    let b = (bottom - top) as i64;
+    let b_odd = b & 1;
+
+    // Error increments
+    let mut dx = 4 * (1 - a) * b * b;
+    let mut dy = 4 * (b_odd + 1) * a * a;
+    let mut err = dx + dy + b_odd * a * a;
+
+    // Start at the middle row(s) and walk outwards
+    let mut lower = top + ((b + 1) / 2) as i32;
+    let mut upper = lower - b_odd as i32;
+
+    let a8 = 8 * a * a;
+    let b8 = 8 * b * b;
+
+    loop {
+        points.push((right, lower));
+        points.push((left, lower));
+        points.push((left, upper));
+        points.push((right, upper));
+
+        let e2 = 2 * err;
+        if e2 <= dy {
+            lower += 1;
+            upper -= 1;
+            dy += a8;
+            err += dy;
+        }
+        if e2 >= dx || 2 * err > dy {
+            left += 1;
+            right -= 1;
+            dx += b8;
+            err += dx;
+        }
+
+        if left > right {
+            break;
+        }
+    }
+
+    // Flat ellipses stop too early, finish the tips
+    while ((lower - upper) as i64) <= b {
+        points.push((left - 1, lower));
+        points.push((right + 1, lower));
+        lower += 1;
+        points.push((left - 1, upper));
+        points.push((right + 1, upper));
+        upper -= 1;
+    }
+
+    points.sort_by_key(|&(x, y)| (y, x));
+    points.dedup();
+    points
+}
+
+/// Filled ellipse inscribed in the rectangle with corners (x0, y0) and (x1, y1), one span per row,
+/// sorted from top to bottom
+pub fn ellipse_spans(x0: i32, y0: i32, x1: i32, y1: i32) -> Vec<Span> {
+    outline_spans(ellipse(x0, y0, x1, y1))
+}
+
+/// Spans between the leftmost and rightmost pixels of each row of the outline of a convex shape,
+/// the outline must be sorted by row
+fn outline_spans(outline: Vec<(i32, i32)>) -> Vec<Span> {
+    let mut spans: Vec<Span> = Vec::new();
+
+    for (x, y) in outline {
+        match spans.last_mut() {
+            Some(span) if span.y == y => {
+                span.x_start = span.x_start.min(x);
+                span.x_end = span.x_end.max(x);
+            }
+            _ => spans.push(Span { y, x_start: x, x_end: x }),
+        }
+    }
+
+    spans
+}
+
+/// Parts of the spans that are not covered by the hole spans, at most two per row. Both must be
+/// sorted by row, one span per row.
+fn subtract_spans(spans: Vec<Span>, hole: Vec<Span>) -> Vec<Span> {
+    let mut hole = hole.into_iter().peekable();
+    let mut remaining = Vec::new();
+    for span in spans {
+        while hole.next_if(|inner| inner.y < span.y).is_some() {}
+        let Some(inner) = hole.next_if(|inner| inner.y == span.y) else {
+            remaining.push(span);
+            continue;
+        };
+
+        if inner.x_start > span.x_start {
+            remaining.push(Span { x_end: span.x_end.min(inner.x_start - 1), ..span });
+        }
+        if inner.x_end < span.x_end {
+            remaining.push(Span { x_start: span.x_start.max(inner.x_end + 1), ..span });
+        }
+    }
+    remaining
+}
+
+/// Every pixel of the spans, in order
+fn span_pixels(spans: Vec<Span>) -> Vec<(i32, i32)> {
+    spans.into_iter()
+        .flat_map(|span| (span.x_start..=span.x_end).map(move |x| (x, span.y)))
+        .collect()
+}
+
+/// Outline of the ellipse inscribed in the rectangle with corners (x0, y0) and (x1, y1), grown
+/// inwards to be `width` pixels thick
+pub fn ellipse_stroke(x0: i32, y0: i32, x1: i32, y1: i32, width: i32) -> Vec<(i32, i32)> {
+    if width <= 1 {
+        return ellipse(x0, y0, x1, y1);
+    }
+
+    let (left, right) = (x0.min(x1), x0.max(x1));
+    let (top, bottom) = (y0.min(y1), y0.max(y1));
+
+    let inner = if right - left >= 2 * width && bottom - top >= 2 * width {
+        ellipse_spans(left + width, top + width, right - width, bottom - width)
+    } else {
+        // Too thick for a hole
+        Vec::new()
+    };
+
+    span_pixels(subtract_spans(ellipse_spans(left, top, right, bottom), inner))
+}
+
+/// Outline of the circle centered on (center_x, center_y)
+pub fn circle(center_x: i32, center_y: i32, radius: i32) -> Vec<(i32, i32)> {
+    ellipse(center_x - radius, center_y - radius, center_x + radius, center_y + radius)
+}
+
+/// Filled circle centered on (center_x, center_y)
+pub fn circle_spans(center_x: i32, center_y: i32, radius: i32) -> Vec<Span> {
+    ellipse_spans(center_x - radius, center_y - radius, center_x + radius, center_y + radius)
+}
+
+/// Outline of the rectangle with corners (x0, y0) and (x1, y1), given in any order, with its
+/// corners rounded to quarter circles of `radius`. The corners are the quadrants of a midpoint
+/// circle, so they match `circle` exactly.
+pub fn rounded_rectangle(x0: i32, y0: i32, x1: i32, y1: i32, radius: i32) -> Vec<(i32, i32)> {
+    let (left, right) = (x0.min(x1), x0.max(x1));
+    let (top, bottom) = (y0.min(y1), y0.max(y1));
+    let radius = radius.clamp(0, ((right - left) / 2).min((bottom - top) / 2));
+    let diameter = 2 * radius;
+
+    let mut points = Vec::new();
+
+    // Pull the quadrants of the circle apart, into the corners
+    for (x, y) in ellipse(0, 0, diameter, diameter) {
+        let x = if x <= radius { left + x } else { right - (diameter - x) };
+        let y = if y <= radius { top + y } else { bottom - (diameter - y) };
+        points.push((x, y));
+    }
+
+    for x in (left + radius)..=(right - radius) {
+        points.push((x, top));
+        points.push((x, bottom));
+    }
+    for y in (top + radius)..=(bottom - radius) {
+        points.push((left, y));
+        points.push((right, y));
+    }
+
+    points.sort_by_key(|&(x, y)| (y, x));
+    points.dedup();
+    points
+}
+
+/// Filled rounded rectangle, one span per row, sorted from top to bottom
+pub fn rounded_rectangle_spans(x0: i32, y0: i32, x1: i32, y1: i32, radius: i32) -> Vec<Span> {
+    outline_spans(rounded_rectangle(x0, y0, x1, y1, radius))
+}
+
+/// Outline of the rounded rectangle grown inwards to be `width` pixels thick, the inner edge
+/// follows corners of `radius - width`
+pub fn rounded_rectangle_stroke(x0: i32, y0: i32, x1: i32, y1: i32, radius: i32,
+                                width: i32) -> Vec<(i32, i32)> {
+    if width <= 1 {
+        return rounded_rectangle(x0, y0, x1, y1, radius);
+    }
+
+    let (left, right) = (x0.min(x1), x0.max(x1));
+    let (top, bottom) = (y0.min(y1), y0.max(y1));
+
+    let inner = if right - left >= 2 * width && bottom - top >= 2 * width {
+        rounded_rectangle_spans(left + width, top + width, right - width, bottom - width,
+                                (radius - width).max(0))
+    } else {
+        // Too thick for a hole
+        Vec::new()
+    };
+
+    span_pixels(subtract_spans(rounded_rectangle_spans(left, top, right, bottom, radius), inner))
+}
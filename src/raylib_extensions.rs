@@ -1,4 +1,7 @@
+use raylib::color::Color;
 use raylib::math::{Rectangle, Vector2};
+use raylib::texture::{Image, Texture2D};
+use crate::pixel_buffer::PixelBuffer;
 
 
 
@@ -15,4 +18,49 @@ pub fn generate_rectangle(p0: Vector2, p1: Vector2) -> Rectangle {
         width: size.x.abs(),
         height: size.y.abs(),
     }
-}
\ No newline at end of file
+}
+
+/// Copy a pixel buffer into an image, to upload it as a texture or save it
+pub fn image_from_buffer(buffer: &PixelBuffer) -> Image {
+    image_from_rgba(buffer.width(), buffer.height(), &buffer.to_rgba())
+}
+
+/// Image of RGBA bytes, row after row
+pub fn image_from_rgba(width: i32, height: i32, bytes: &[u8]) -> Image {
+    assert_eq!(bytes.len(), width as usize * height as usize * 4, "not the bytes of a {}x{} image", width, height);
+    let image = Image::gen_image_color(width, height, Color::new(0, 0, 0, 0));
+    // Generated images are always R8G8B8A8, the layout of the bytes
+    unsafe {
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), image.data() as *mut u8, bytes.len());
+    }
+    image
+}
+
+/// Copy the pixels of an image, in any format, into a pixel buffer
+pub fn buffer_from_image(image: &Image) -> PixelBuffer {
+    PixelBuffer::from_pixels(image.width, image.height, image.get_image_data().to_vec())
+        .unwrap()
+}
+
+/// Scale everything drawn after this call in the current frame, from UI pixels to window pixels
+pub fn scale_drawing(scale: f32) {
+    // The same matrix change BeginMode2D makes, without switching to another draw handle
+    unsafe {
+        raylib::ffi::rlScalef(scale, scale, 1f32);
+    }
+}
+
+/// Upload RGBA bytes, row after row, into the area of the texture
+pub fn update_texture_rec(texture: &mut Texture2D, area: Rectangle, bytes: &[u8]) {
+    let raw: &raylib::ffi::Texture2D = texture.as_ref();
+    let (x, y, width, height) = (area.x as i32, area.y as i32, area.width as i32, area.height as i32);
+    assert!(x >= 0 && y >= 0 && x + width <= raw.width && y + height <= raw.height,
+            "{}x{} pixels at {}, {} do not fit in a {}x{} texture", width, height, x, y, raw.width, raw.height);
+    assert_eq!(bytes.len(), width as usize * height as usize * 4, "not the bytes of {}x{} pixels", width, height);
+
+    // Textures loaded from buffers are R8G8B8A8, the layout of the bytes, and the area is the
+    // size of the bytes, so the upload reads exactly the bytes there are
+    unsafe {
+        raylib::ffi::UpdateTextureRec(*raw, area.into(), bytes.as_ptr() as *const std::os::raw::c_void);
+    }
+}
@@ -0,0 +1,184 @@
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use raylib::color::Color;
+use raylib::math::Vector2;
+use crate::actions::fill_mode::FillMode;
+use crate::color_space::parse_hex;
+use crate::operation::Operation;
+use crate::pixel_buffer::{PixelBuffer, MAX_IMAGE_SIZE};
+use crate::user_state::UserState;
+
+/// One line of a drawing script: either an option for the following operations, or an operation
+///
+/// ```text
+/// # Comments start with '#'
+/// color #FF0000            primary color, RRGGBB or RRGGBBAA
+/// secondary #FFFFFF        fill color of the shapes
+/// line-width 3
+/// fill-mode outline-fill   outline, outline-fill or fill
+/// antialiasing on          on or off
+/// line 0 0 40 20
+/// rectangle 4 4 40 20
+/// ellipse 4 4 40 20
+/// polygon 4 20 24 2 44 20
+/// fill 10 10
+/// text 4 4 20 "Hello"      position, size, then the rest of the line
+/// clear                    fill the image with the secondary color
+/// flip horizontal          horizontal or vertical
+/// resize 64 48
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub enum Command {
+    Color(Color),
+    SecondaryColor(Color),
+    LineWidth(i32),
+    FillMode(FillMode),
+    Antialiasing(bool),
+    Operation(Operation),
+}
+
+#[derive(Debug)]
+pub enum ScriptError {
+    /// Line number starting at 1
+    Line(usize, String),
+    Io(std::io::Error),
+}
+
+impl Display for ScriptError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScriptError::Line(line, message) => write!(f, "line {}: {}", line, message),
+            ScriptError::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+impl From<std::io::Error> for ScriptError {
+    fn from(error: std::io::Error) -> Self {
+        ScriptError::Io(error)
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(line_number: usize, word: Option<&str>) -> Result<T, ScriptError> {
+    let word = word.ok_or_else(|| ScriptError::Line(line_number, "missing value".to_string()))?;
+    word.parse().map_err(|_| ScriptError::Line(line_number, format!("'{}' is not a valid number", word)))
+}
+
+fn parse_point<'a>(line_number: usize, words: &mut impl Iterator<Item = &'a str>) -> Result<Vector2, ScriptError> {
+    Ok(Vector2::new(parse_number(line_number, words.next())?, parse_number(line_number, words.next())?))
+}
+
+fn parse_color(line_number: usize, word: Option<&str>) -> Result<Color, ScriptError> {
+    let word = word.ok_or_else(|| ScriptError::Line(line_number, "missing color".to_string()))?;
+    parse_hex(word).ok_or_else(|| ScriptError::Line(line_number, format!("'{}' is not a RRGGBB color", word)))
+}
+
+fn parse_choice<T: Copy>(line_number: usize, word: Option<&str>, choices: &[(&str, T)]) -> Result<T, ScriptError> {
+    let names: Vec<&str> = choices.iter().map(|(name, _)| *name).collect();
+    let word = word.ok_or_else(|| ScriptError::Line(line_number, format!("expected one of {}", names.join(", "))))?;
+    choices.iter()
+        .find(|(name, _)| *name == word)
+        .map(|(_, value)| *value)
+        .ok_or_else(|| ScriptError::Line(line_number, format!("'{}' is not one of {}", word, names.join(", "))))
+}
+
+/// Text after the position and size, quotes around it are optional
+fn parse_text(line_number: usize, rest: &str) -> Result<(Vector2, i32, String), ScriptError> {
+    let mut words = rest.splitn(4, char::is_whitespace);
+    let position = parse_point(line_number, &mut words)?;
+    let size = parse_number(line_number, words.next())?;
+    let text = words.next().unwrap_or("").trim();
+    let text = text.strip_prefix('"').and_then(|t| t.strip_suffix('"')).unwrap_or(text);
+    Ok((position, size, text.replace("\\n", "\n")))
+}
+
+fn parse_command(line_number: usize, line: &str) -> Result<Command, ScriptError> {
+    let (name, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let mut words = rest.split_whitespace();
+    let command = match name {
+        "color" => Command::Color(parse_color(line_number, words.next())?),
+        "secondary" => Command::SecondaryColor(parse_color(line_number, words.next())?),
+        "line-width" => Command::LineWidth(parse_number(line_number, words.next())?),
+        "fill-mode" => Command::FillMode(parse_choice(line_number, words.next(), &[
+            ("outline", FillMode::Outline),
+            ("outline-fill", FillMode::OutlineFill),
+            ("fill", FillMode::Fill),
+        ])?),
+        "antialiasing" => Command::Antialiasing(parse_choice(line_number, words.next(), &[("on", true), ("off", false)])?),
+        "line" => Command::Operation(Operation::Line {
+            from: parse_point(line_number, &mut words)?,
+            to: parse_point(line_number, &mut words)?,
+        }),
+        "rectangle" => Command::Operation(Operation::Rectangle {
+            from: parse_point(line_number, &mut words)?,
+            to: parse_point(line_number, &mut words)?,
+        }),
+        "ellipse" => Command::Operation(Operation::Ellipse {
+            from: parse_point(line_number, &mut words)?,
+            to: parse_point(line_number, &mut words)?,
+        }),
+        "polygon" => {
+            let mut points = Vec::new();
+            while words.clone().next().is_some() {
+                points.push(parse_point(line_number, &mut words)?);
+            }
+            if points.len() < 3 {
+                return Err(ScriptError::Line(line_number, "a polygon needs at least 3 points".to_string()));
+            }
+            Command::Operation(Operation::Polygon(points))
+        },
+        "fill" => Command::Operation(Operation::Fill(parse_point(line_number, &mut words)?)),
+        "text" => {
+            let (position, size, text) = parse_text(line_number, rest)?;
+            return Ok(Command::Operation(Operation::Text { position, text, size }));
+        },
+        "clear" => Command::Operation(Operation::Clear),
+        "flip" => {
+            let horizontal = parse_choice(line_number, words.next(), &[("horizontal", true), ("vertical", false)])?;
+            Command::Operation(if horizontal { Operation::FlipHorizontal } else { Operation::FlipVertical })
+        },
+        "resize" => {
+            let width = parse_number(line_number, words.next())?;
+            let height = parse_number(line_number, words.next())?;
+            if !(1..=MAX_IMAGE_SIZE).contains(&width) || !(1..=MAX_IMAGE_SIZE).contains(&height) {
+                return Err(ScriptError::Line(line_number, format!("{}x{} is not a valid size", width, height)));
+            }
+            Command::Operation(Operation::Resize { width, height })
+        },
+        _ => return Err(ScriptError::Line(line_number, format!("unknown command '{}'", name))),
+    };
+
+    match words.next() {
+        Some(word) => Err(ScriptError::Line(line_number, format!("unexpected '{}'", word))),
+        None => Ok(command),
+    }
+}
+
+pub fn parse(text: &str) -> Result<Vec<Command>, ScriptError> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(line_number, line)| parse_command(line_number, line))
+        .collect()
+}
+
+pub fn load(path: &Path) -> Result<Vec<Command>, ScriptError> {
+    parse(&std::fs::read_to_string(path)?)
+}
+
+/// Run the commands in order, the options they set stay in the user state
+pub fn run(commands: &[Command], user_state: &mut UserState, canvas: &mut PixelBuffer) {
+    for command in commands {
+        match command {
+            Command::Color(color) => user_state.current_colors[0] = *color,
+            Command::SecondaryColor(color) => user_state.current_colors[1] = *color,
+            Command::LineWidth(width) => user_state.line_width = *width,
+            Command::FillMode(fill_mode) => user_state.fill_mode = *fill_mode,
+            Command::Antialiasing(antialiasing) => user_state.antialiasing = *antialiasing,
+            Command::Operation(operation) => operation.apply(user_state, canvas),
+        }
+    }
+}
@@ -0,0 +1,316 @@
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use raylib::prelude::*;
+use crate::actions::input::Modifiers;
+
+const HEADER: &str = "PaintIt session 2";
+
+/// Mouse buttons the window reacts to, in the order of the bits in `FrameInput`
+const BUTTONS: [MouseButton; 3] = [
+    MouseButton::MOUSE_BUTTON_LEFT,
+    MouseButton::MOUSE_BUTTON_RIGHT,
+    MouseButton::MOUSE_BUTTON_MIDDLE,
+];
+
+/// Everything the window reads from the user during one frame, live or from a recorded session
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct FrameInput {
+    /// Seconds since the window opened
+    pub time: f64,
+    /// Seconds since the previous frame
+    pub frame_time: f32,
+    pub mouse_position: Vector2,
+    /// One bit per button, in the order of `BUTTONS`
+    pub buttons_down: u8,
+    pub buttons_pressed: u8,
+    pub buttons_released: u8,
+    pub wheel: f32,
+    pub modifiers: Modifiers,
+    /// In the order they were pressed
+    pub keys_pressed: Vec<KeyboardKey>,
+    /// Characters typed, in order
+    pub chars: Vec<char>,
+    pub dropped_files: Vec<PathBuf>,
+    /// New size of the window in UI pixels, on the frames it was resized
+    pub window_size: Option<Vector2>,
+}
+
+fn button_bit(button: MouseButton) -> u8 {
+    BUTTONS.iter().position(|b| *b == button).map_or(0, |i| 1 << i)
+}
+
+impl FrameInput {
+    pub fn capture(rl: &mut RaylibHandle) -> Self {
+        let mut input = FrameInput {
+            time: rl.get_time(),
+            frame_time: rl.get_frame_time(),
+            mouse_position: rl.get_mouse_position(),
+            wheel: rl.get_mouse_wheel_move(),
+            modifiers: Modifiers {
+                control: rl.is_key_down(KeyboardKey::KEY_LEFT_CONTROL)
+                    || rl.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL),
+                shift: rl.is_key_down(KeyboardKey::KEY_LEFT_SHIFT)
+                    || rl.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT),
+                alt: rl.is_key_down(KeyboardKey::KEY_LEFT_ALT)
+                    || rl.is_key_down(KeyboardKey::KEY_RIGHT_ALT),
+            },
+            ..Default::default()
+        };
+
+        for button in BUTTONS {
+            if rl.is_mouse_button_down(button) {
+                input.buttons_down |= button_bit(button);
+            }
+            if rl.is_mouse_button_pressed(button) {
+                input.buttons_pressed |= button_bit(button);
+            }
+            if rl.is_mouse_button_released(button) {
+                input.buttons_released |= button_bit(button);
+            }
+        }
+        while let Some(key) = rl.get_key_pressed() {
+            input.keys_pressed.push(key);
+        }
+        while let Some(c) = rl.get_char_pressed() {
+            input.chars.push(c);
+        }
+        if rl.is_file_dropped() {
+            input.dropped_files = rl.load_dropped_files().paths().into_iter().map(PathBuf::from).collect();
+        }
+        input
+    }
+
+    pub fn is_mouse_button_down(&self, button: MouseButton) -> bool {
+        self.buttons_down & button_bit(button) != 0
+    }
+
+    pub fn is_mouse_button_pressed(&self, button: MouseButton) -> bool {
+        self.buttons_pressed & button_bit(button) != 0
+    }
+
+    pub fn is_mouse_button_released(&self, button: MouseButton) -> bool {
+        self.buttons_released & button_bit(button) != 0
+    }
+
+    pub fn is_key_pressed(&self, key: KeyboardKey) -> bool {
+        self.keys_pressed.contains(&key)
+    }
+}
+
+/// Input of one frame, and the tools it switched to, as written to a session log
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct RecordedFrame {
+    pub input: FrameInput,
+    /// Indices in the tool registry
+    pub tool_switches: Vec<usize>,
+}
+
+/// A whole session log
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Session {
+    /// Seed the tools drawing at random were made from
+    pub seed: u64,
+    /// Size of the window in UI pixels when the recording started
+    pub window_size: Vector2,
+    pub frames: Vec<RecordedFrame>,
+}
+
+#[derive(Debug)]
+pub enum SessionError {
+    /// Line number starting at 1
+    Line(usize, String),
+    Io(std::io::Error),
+}
+
+impl Display for SessionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionError::Line(line, message) => write!(f, "line {}: {}", line, message),
+            SessionError::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+impl From<std::io::Error> for SessionError {
+    fn from(error: std::io::Error) -> Self {
+        SessionError::Io(error)
+    }
+}
+
+/// Lines of one frame: the dropped files, the input, then the tool switches
+///
+/// `f time frame_time x y down pressed released [w<wheel>] [m<csa>] [k<key,...>] [c<char,...>] [s<width>x<height>]`
+pub fn serialize_frame(frame: &RecordedFrame) -> String {
+    let input = &frame.input;
+    let mut text = String::new();
+    for path in &input.dropped_files {
+        text += &format!("d {}\n", path.display());
+    }
+
+    text += &format!("f {} {} {} {} {} {} {}", input.time, input.frame_time,
+                     input.mouse_position.x, input.mouse_position.y,
+                     input.buttons_down, input.buttons_pressed, input.buttons_released);
+    if input.wheel != 0f32 {
+        text += &format!(" w{}", input.wheel);
+    }
+    if input.modifiers != Modifiers::default() {
+        text += " m";
+        for (held, letter) in [(input.modifiers.control, 'c'), (input.modifiers.shift, 's'), (input.modifiers.alt, 'a')] {
+            if held {
+                text.push(letter);
+            }
+        }
+    }
+    if !input.keys_pressed.is_empty() {
+        let keys: Vec<String> = input.keys_pressed.iter().map(|key| (*key as i32).to_string()).collect();
+        text += &format!(" k{}", keys.join(","));
+    }
+    if !input.chars.is_empty() {
+        let chars: Vec<String> = input.chars.iter().map(|c| (*c as u32).to_string()).collect();
+        text += &format!(" c{}", chars.join(","));
+    }
+    if let Some(size) = input.window_size {
+        text += &format!(" s{}", format_size(size));
+    }
+    text.push('\n');
+
+    for index in &frame.tool_switches {
+        text += &format!("t {}\n", index);
+    }
+    text
+}
+
+fn format_size(size: Vector2) -> String {
+    format!("{}x{}", size.x, size.y)
+}
+
+fn parse_size(line_number: usize, text: &str) -> Result<Vector2, SessionError> {
+    let (width, height) = text.split_once('x')
+        .ok_or_else(|| SessionError::Line(line_number, format!("'{}' is not a size", text)))?;
+    Ok(Vector2::new(parse_number(line_number, Some(width))?, parse_number(line_number, Some(height))?))
+}
+
+fn parse_number<T: std::str::FromStr>(line_number: usize, word: Option<&str>) -> Result<T, SessionError> {
+    let word = word.ok_or_else(|| SessionError::Line(line_number, "missing value".to_string()))?;
+    word.parse().map_err(|_| SessionError::Line(line_number, format!("'{}' is not a valid number", word)))
+}
+
+fn parse_list<T>(line_number: usize, list: &str, convert: impl Fn(u32) -> Option<T>) -> Result<Vec<T>, SessionError> {
+    list.split(',')
+        .map(|word| {
+            parse_number(line_number, Some(word))
+                .and_then(|code| convert(code).ok_or_else(|| SessionError::Line(line_number, format!("unknown code {}", code))))
+        })
+        .collect()
+}
+
+fn parse_input(line_number: usize, text: &str) -> Result<FrameInput, SessionError> {
+    let mut words = text.split_whitespace();
+    let mut input = FrameInput {
+        time: parse_number(line_number, words.next())?,
+        frame_time: parse_number(line_number, words.next())?,
+        mouse_position: Vector2::new(parse_number(line_number, words.next())?, parse_number(line_number, words.next())?),
+        buttons_down: parse_number(line_number, words.next())?,
+        buttons_pressed: parse_number(line_number, words.next())?,
+        buttons_released: parse_number(line_number, words.next())?,
+        ..Default::default()
+    };
+
+    for word in words {
+        let mut chars = word.chars();
+        let tag = chars.next();
+        let value = chars.as_str();
+        match tag {
+            Some('w') => input.wheel = parse_number(line_number, Some(value))?,
+            Some('m') => {
+                input.modifiers = Modifiers {
+                    control: value.contains('c'),
+                    shift: value.contains('s'),
+                    alt: value.contains('a'),
+                }
+            }
+            Some('k') => input.keys_pressed = parse_list(line_number, value, |code| key_from_i32(code as i32))?,
+            Some('c') => input.chars = parse_list(line_number, value, char::from_u32)?,
+            Some('s') => input.window_size = Some(parse_size(line_number, value)?),
+            _ => return Err(SessionError::Line(line_number, format!("unknown field '{}'", word))),
+        }
+    }
+    Ok(input)
+}
+
+/// The header line, then `seed <seed>` and `window <width>x<height>`, then the frames
+pub fn parse(text: &str) -> Result<Session, SessionError> {
+    let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line.trim_end()));
+    if lines.next().map(|(_, line)| line) != Some(HEADER) {
+        return Err(SessionError::Line(1, "not a PaintIt session".to_string()));
+    }
+
+    let mut session = Session::default();
+    let mut dropped_files = Vec::new();
+    for (line_number, line) in lines {
+        if line.is_empty() {
+            continue;
+        }
+
+        let (tag, rest) = line.split_once(' ').unwrap_or((line, ""));
+        match tag {
+            "seed" => session.seed = parse_number(line_number, Some(rest))?,
+            "window" => session.window_size = parse_size(line_number, rest)?,
+            "d" => dropped_files.push(PathBuf::from(rest)),
+            "f" => {
+                let mut input = parse_input(line_number, rest)?;
+                input.dropped_files = std::mem::take(&mut dropped_files);
+                session.frames.push(RecordedFrame { input, tool_switches: Vec::new() });
+            }
+            "t" => {
+                let index = parse_number(line_number, Some(rest))?;
+                session.frames.last_mut()
+                    .ok_or_else(|| SessionError::Line(line_number, "tool switch before the first frame".to_string()))?
+                    .tool_switches.push(index);
+            }
+            _ => return Err(SessionError::Line(line_number, format!("unknown entry '{}'", tag))),
+        }
+    }
+    Ok(session)
+}
+
+pub fn load(path: &Path) -> Result<Session, SessionError> {
+    parse(&std::fs::read_to_string(path)?)
+}
+
+/// Appends every frame to a session log as soon as it is over. The writes are buffered, and
+/// flushed when the recording stops or the recorder is dropped.
+pub struct SessionRecorder {
+    file: BufWriter<File>,
+}
+
+impl SessionRecorder {
+    pub fn create(path: &Path, seed: u64, window_size: Vector2) -> std::io::Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "{}", HEADER)?;
+        writeln!(file, "seed {}", seed)?;
+        writeln!(file, "window {}", format_size(window_size))?;
+        Ok(SessionRecorder { file })
+    }
+
+    pub fn record(&mut self, frame: &RecordedFrame) -> std::io::Result<()> {
+        self.file.write_all(serialize_frame(frame).as_bytes())
+    }
+
+    /// Stop recording, with the frames still in the buffer written out
+    pub fn finish(mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Drop for SessionRecorder {
+    fn drop(&mut self) {
+        if let Err(error) = self.file.flush() {
+            eprintln!("Could not write the end of the session log: {}", error);
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use raylib::color::Color;
+use crate::actions::color_picker::SampleSize;
+use crate::actions::fill_mode::FillMode;
+use crate::actions::rounded_rectangle::CORNER_RADIUS_MAX;
+use crate::actions::spray::{SpraySize, SPRAY_DENSITY_MAX, SPRAY_DENSITY_MIN};
+use crate::color_space::{parse_hex, to_hex};
+use crate::palette::DEFAULT_PALETTE;
+use crate::user_state::{UserState, LINE_WIDTHS};
+
+const FILE_NAME: &str = "settings";
+
+/// Size of the window on the first launch, in UI pixels
+pub const DEFAULT_WINDOW_SIZE: (i32, i32) = (1020, 510);
+
+/// Largest side of the window, in UI pixels, well past any screen
+pub const MAX_WINDOW_SIZE: i32 = 16384;
+
+/// Images kept in the recent files
+pub const RECENT_FILES_LIMIT: usize = 10;
+
+/// Colors kept in the recent colors
+pub const RECENT_COLORS_LIMIT: usize = 16;
+
+const HEADER: &str = "\
+# PaintIt settings, written when the window closes. Lines that cannot be read are skipped.
+";
+
+const SPRAY_SIZES: [(&str, SpraySize); 3] = [
+    ("small", SpraySize::SizeOne),
+    ("medium", SpraySize::SizeTwo),
+    ("large", SpraySize::SizeThree),
+];
+const SAMPLE_SIZES: [(&str, SampleSize); 3] = [("1", SampleSize::One), ("3", SampleSize::Three), ("5", SampleSize::Five)];
+const FILL_MODES: [(&str, FillMode); 3] = [
+    ("outline", FillMode::Outline),
+    ("outline-fill", FillMode::OutlineFill),
+    ("fill", FillMode::Fill),
+];
+const SWITCHES: [(&str, bool); 2] = [("on", true), ("off", false)];
+
+#[derive(Debug)]
+pub enum SettingsError {
+    /// Line number starting at 1
+    Line(usize, String),
+    Io(std::io::Error),
+}
+
+impl Display for SettingsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SettingsError::Line(line, message) => write!(f, "line {}: {}", line, message),
+            SettingsError::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
+
+impl From<std::io::Error> for SettingsError {
+    fn from(error: std::io::Error) -> Self {
+        SettingsError::Io(error)
+    }
+}
+
+/// $XDG_CONFIG_HOME/PaintIt, falling back to ~/.config/PaintIt
+pub fn config_directory() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CONFIG_HOME").filter(|path| !path.is_empty()) {
+        Some(path) => PathBuf::from(path),
+        None => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("PaintIt"))
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    config_directory().map(|directory| directory.join(FILE_NAME))
+}
+
+fn name_of<T: PartialEq>(choices: &[(&'static str, T)], value: &T) -> &'static str {
+    choices.iter().find(|(_, choice)| choice == value).map_or("", |(name, _)| *name)
+}
+
+fn parse_choice<T: Copy>(choices: &[(&str, T)], value: &str) -> Result<T, String> {
+    choices.iter()
+        .find(|(name, _)| *name == value)
+        .map(|(_, choice)| *choice)
+        .ok_or_else(|| {
+            let names: Vec<&str> = choices.iter().map(|(name, _)| *name).collect();
+            format!("'{}' is not one of {}", value, names.join(", "))
+        })
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("'{}' is not a valid number", value))
+}
+
+fn parse_between<T: std::str::FromStr + PartialOrd + Display>(value: &str, min: T, max: T) -> Result<T, String> {
+    let number = parse_number(value)?;
+    if number < min || number > max {
+        return Err(format!("{} is not between {} and {}", number, min, max));
+    }
+    Ok(number)
+}
+
+fn parse_color(value: &str) -> Result<Color, String> {
+    parse_hex(value).ok_or_else(|| format!("'{}' is not a RRGGBB color", value))
+}
+
+/// What the window keeps from one launch to the next
+#[derive(Debug, PartialEq, Clone)]
+pub struct Settings {
+    /// Colors and tool options, the positions are ignored
+    pub options: UserState,
+    pub palette: Vec<Color>,
+    /// Name of the selected tool in the registry
+    pub tool: Option<String>,
+    /// Images opened, most recent first
+    pub recent_files: Vec<PathBuf>,
+    /// Colors put onto the canvas, most recent first
+    pub recent_colors: Vec<Color>,
+    /// In UI pixels, which are larger than the screen's on HiDPI displays
+    pub window_size: (i32, i32),
+    /// The palette and the recent colors under the canvas
+    pub show_color_box: bool,
+    pub show_status_bar: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            options: UserState::default(),
+            palette: DEFAULT_PALETTE.to_vec(),
+            tool: None,
+            recent_files: Vec::new(),
+            recent_colors: Vec::new(),
+            window_size: DEFAULT_WINDOW_SIZE,
+            show_color_box: true,
+            show_status_bar: true,
+        }
+    }
+}
+
+impl Settings {
+    fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let options = &mut self.options;
+        match name {
+            "primary-color" => options.current_colors[0] = parse_color(value)?,
+            "secondary-color" => options.current_colors[1] = parse_color(value)?,
+            "palette" => {
+                let palette = value.split_whitespace().map(parse_color).collect::<Result<Vec<_>, _>>()?;
+                if palette.is_empty() {
+                    return Err("the palette has no colors".to_string());
+                }
+                self.palette = palette;
+            },
+            "spray-size" => options.spray_size = parse_choice(&SPRAY_SIZES, value)?,
+            "spray-density" => options.spray_density = parse_between(value, SPRAY_DENSITY_MIN, SPRAY_DENSITY_MAX)?,
+            "antialiasing" => options.antialiasing = parse_choice(&SWITCHES, value)?,
+            "line-width" => {
+                let width = parse_number(value)?;
+                if !LINE_WIDTHS.contains(&width) {
+                    let widths: Vec<String> = LINE_WIDTHS.iter().map(|width| width.to_string()).collect();
+                    return Err(format!("{} is not one of {}", width, widths.join(", ")));
+                }
+                options.line_width = width;
+            },
+            "fill-mode" => options.fill_mode = parse_choice(&FILL_MODES, value)?,
+            "corner-radius" => options.corner_radius = parse_between(value, 0, CORNER_RADIUS_MAX)?,
+            "sample-size" => options.sample_size = parse_choice(&SAMPLE_SIZES, value)?,
+            "tool" => self.tool = Some(value.to_string()),
+            // One line per file, oldest last
+            "recent-file" => {
+                if self.recent_files.len() < RECENT_FILES_LIMIT {
+                    self.recent_files.push(PathBuf::from(value));
+                }
+            },
+            "recent-colors" => {
+                let colors = value.split_whitespace().map(parse_color).collect::<Result<Vec<_>, _>>()?;
+                self.recent_colors = colors.into_iter().take(RECENT_COLORS_LIMIT).collect();
+            },
+            "window-size" => {
+                let (width, height) = value.split_once('x')
+                    .ok_or_else(|| format!("'{}' is not a <width>x<height> size", value))?;
+                self.window_size = (parse_between(width, 1, MAX_WINDOW_SIZE)?,
+                                    parse_between(height, 1, MAX_WINDOW_SIZE)?);
+            },
+            "color-box" => self.show_color_box = parse_choice(&SWITCHES, value)?,
+            "status-bar" => self.show_status_bar = parse_choice(&SWITCHES, value)?,
+            _ => return Err(format!("unknown setting '{}'", name)),
+        }
+        Ok(())
+    }
+
+    /// Settings read from the lines that make sense, the defaults for the rest, and an error
+    /// for every line that was skipped
+    pub fn parse(text: &str) -> (Settings, Vec<SettingsError>) {
+        let mut settings = Settings::default();
+        let mut errors = Vec::new();
+        for (line_number, line) in text.lines().enumerate().map(|(i, line)| (i + 1, line.trim())) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let result = line.split_once('=')
+                .ok_or_else(|| "expected <setting> = <value>".to_string())
+                .and_then(|(name, value)| settings.set(name.trim(), value.trim()));
+            if let Err(message) = result {
+                errors.push(SettingsError::Line(line_number, message));
+            }
+        }
+        (settings, errors)
+    }
+
+    /// The defaults when there is no file yet, and what could be read of a damaged one
+    pub fn load(path: &Path) -> Settings {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Settings::default(),
+            Err(error) => {
+                eprintln!("Could not read settings {}, using the defaults: {}", path.display(), error);
+                return Settings::default();
+            },
+        };
+
+        let (settings, errors) = Settings::parse(&text);
+        for error in errors {
+            eprintln!("Skipped a setting in {}: {}", path.display(), error);
+        }
+        settings
+    }
+
+    /// Written next to the file then moved over it, so a crash cannot leave half a file
+    pub fn save(&self, path: &Path) -> Result<(), SettingsError> {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let partial = path.with_extension("partial");
+        fs::write(&partial, self.serialize())?;
+        fs::rename(&partial, path)?;
+        Ok(())
+    }
+
+    pub fn serialize(&self) -> String {
+        let options = &self.options;
+        let palette: Vec<String> = self.palette.iter().map(|color| to_hex(*color)).collect();
+        let mut text = HEADER.to_string();
+        text += &format!("primary-color = {}\n", to_hex(options.current_colors[0]));
+        text += &format!("secondary-color = {}\n", to_hex(options.current_colors[1]));
+        text += &format!("palette = {}\n", palette.join(" "));
+        text += &format!("spray-size = {}\n", name_of(&SPRAY_SIZES, &options.spray_size));
+        text += &format!("spray-density = {}\n", options.spray_density);
+        text += &format!("antialiasing = {}\n", name_of(&SWITCHES, &options.antialiasing));
+        text += &format!("line-width = {}\n", options.line_width);
+        text += &format!("fill-mode = {}\n", name_of(&FILL_MODES, &options.fill_mode));
+        text += &format!("corner-radius = {}\n", options.corner_radius);
+        text += &format!("sample-size = {}\n", name_of(&SAMPLE_SIZES, &options.sample_size));
+        text += &format!("window-size = {}x{}\n", self.window_size.0, self.window_size.1);
+        text += &format!("color-box = {}\n", name_of(&SWITCHES, &self.show_color_box));
+        text += &format!("status-bar = {}\n", name_of(&SWITCHES, &self.show_status_bar));
+        if let Some(tool) = &self.tool {
+            text += &format!("tool = {}\n", tool);
+        }
+        if !self.recent_colors.is_empty() {
+            let colors: Vec<String> = self.recent_colors.iter().map(|color| to_hex(*color)).collect();
+            text += &format!("recent-colors = {}\n", colors.join(" "));
+        }
+        for path in &self.recent_files {
+            text += &format!("recent-file = {}\n", path.display());
+        }
+        text
+    }
+}
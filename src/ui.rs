@@ -1,49 +1,211 @@
+mod tool_options;
+mod palette_bar;
+mod edit_colors;
+mod recent_colors;
+mod canvas_view;
+mod menu_bar;
+pub mod widgets;
+
+use std::path::{Path, PathBuf};
 use raylib::prelude::*;
-use crate::actions::action_state::ActionState;
 use crate::actions::brush::{BrushSize, BrushType};
-use crate::specify_state;
-use crate::actions::spray::SpraySize;
-use crate::actions::update_execute_action::UpdateExecuteAction;
+use crate::palette::{PaletteFormat, DEFAULT_PALETTE};
+use crate::quantization;
+use crate::actions::input::InputEvent;
+use crate::actions::preview::ToolCursor;
+use crate::actions::tool_registry::{ToolEntry, ToolRegistry};
+use crate::actions::update_execute_action;
+use crate::raylib_extensions;
+use crate::session::{self, FrameInput, RecordedFrame, SessionError, SessionRecorder};
 use crate::user_state::{WindowVector2, UserState};
+use crate::ui::tool_options::ToolOptions;
+use crate::ui::palette_bar::{PaletteBar, PaletteEvent};
+use crate::ui::edit_colors::{DialogResult, EditColorsDialog, CUSTOM_COLORS};
+use crate::ui::recent_colors::RecentColors;
+use crate::ui::canvas_view::CanvasView;
+use crate::ui::widgets::{draw_bevel, draw_tooltip, Dialog, DialogEvent};
+use crate::ui::menu_bar::{Menu, MenuBar, MenuEntry, MenuItem, MenuResult, MENU_BAR_HEIGHT};
+use crate::document::{Document, DocumentError};
+use crate::operation::Operation;
+use crate::pixel_buffer::{PixelBuffer, MAX_IMAGE_SIZE};
+use crate::keymap::{self, Action, Keymap};
+use crate::settings::{self, Settings, RECENT_FILES_LIMIT};
 
 const TEXTURE_SIZE: usize = 16;
 const TEXTURE_NUMBER: usize = 16;
 
 const BUTTON_SIZE: usize = 32;
 
-const SCREEN_WIDTH: i32 = 1020;
-const SCREEN_HEIGHT: i32 = 510;
+/// Smallest window that fits the toolbar and the color bars, in UI pixels
+const MIN_WINDOW_WIDTH: i32 = 640;
+const MIN_WINDOW_HEIGHT: i32 = 530;
+
+const STATUS_BAR_HEIGHT: f32 = 20f32;
 
 const CANVAS_MARGIN: f32 = 4f32;
 
+/// File name Export Palette offers when the palette was not imported from a file
+const DEFAULT_PALETTE_FILE: &str = "palette.gpl";
+/// File name Save As offers when the image was not opened from a file
+const DEFAULT_IMAGE_FILE: &str = "untitled.png";
+
+const NEW_IMAGE_WIDTH: i32 = 743;
+const NEW_IMAGE_HEIGHT: i32 = 406;
+
+/// How long the mouse rests on a toolbar button before its tooltip shows
+const TOOLTIP_DELAY: f64 = 0.5;
+/// Face of the toolbar button under the mouse
+const HOVER_COLOR: Color = Color::new(225, 225, 240, 255);
+/// Face of the selected tool's button, lighter than the toolbar like a pushed-in switch
+const SELECTED_COLOR: Color = Color::new(235, 235, 235, 255);
+
+/// How long a message stays in the status bar
+const STATUS_MESSAGE_SECONDS: f64 = 4.0;
+
+/// Passes of k-means when extracting a palette from the picture
+const PALETTE_EXTRACTION_ITERATIONS: usize = 16;
+/// Pixels looked at when extracting a palette, spread over the picture
+const PALETTE_EXTRACTION_SAMPLES: usize = 65536;
+/// Most colors Extract Palette makes
+const MAX_EXTRACTED_COLORS: i32 = 256;
+
 const TRANSPARENT: Color = Color::new(0,0,0,0);
 
+/// Image that takes the place of the current one
+enum NextImage {
+    Blank { width: i32, height: i32, background: Color },
+    File(PathBuf),
+}
+
+/// What the open widget dialog was opened for, which decides what its OK button does
+enum DialogPurpose {
+    NewImage,
+    /// Asks before New Image or Open drops the changes that were not saved
+    DiscardChanges(NextImage),
+    OpenImage,
+    SaveImageAs,
+    /// Asks before Save As writes over the file
+    ReplaceImage(PathBuf),
+    ImportPalette,
+    ExportPalette,
+    /// Asks before Export Palette writes over the file
+    ReplacePalette(PathBuf),
+    ExtractPalette,
+}
+
+/// What the menu items do, besides the actions that also have keys
+#[derive(Clone)]
+enum MenuCommand {
+    Run(Action),
+    OpenRecent(PathBuf),
+    About,
+    Exit,
+}
+
 pub struct Ui {
     rl: RaylibHandle,
     thread: RaylibThread,
     textures: Texture2D,
     atlas_sources: [Rectangle; TEXTURE_NUMBER],
-    button_positions: [Vector2; TEXTURE_NUMBER],
-    button_bounds: [Rectangle; TEXTURE_NUMBER],
-    current_pressed: Option<ActionState>,
+    tools: ToolRegistry,
+    keymap: Keymap,
+    /// Size of the window in UI pixels
+    screen_size: Vector2,
+    /// Window pixels per UI pixel
+    scale: f32,
+    button_positions: Vec<Vector2>,
+    button_bounds: Vec<Rectangle>,
+    /// Index of the selected tool in the registry
+    current_tool: Option<usize>,
+    /// Toolbar button under the mouse, and when the mouse got there
+    hovered_tool: Option<(usize, f64)>,
+    /// Toolbar button the left mouse button went down on, which selects it when released on it
+    held_tool: Option<usize>,
+    /// Tool to go back to after picking a color
+    previous_tool: Option<usize>,
     current_colors: [Color; 2],
-    canvas_position: Vector2,
-    canvas_image: Image,
-    canvas_dirty: bool,
-    canvas_texture: Texture2D,
-    canvas_rectangle: Rectangle,
+    tool_options: ToolOptions,
+    palette_bar: PaletteBar,
+    recent_colors: RecentColors,
+    custom_colors: [Option<Color>; CUSTOM_COLORS],
+    /// While open, the rest of the window ignores input
+    edit_colors: Option<EditColorsDialog>,
+    /// Dialog made of widgets, for the command that opened it. While open, the rest of the
+    /// window ignores input.
+    dialog: Option<(DialogPurpose, Dialog)>,
+    /// The image, with the undo steps of the strokes made on it
+    document: Document,
+    /// File the image was opened from or saved to
+    image_path: Option<PathBuf>,
+    /// A tool changed the canvas during the current stroke, which is already an undo step
+    stroke_open: bool,
+    /// Canvas from when the mouse went down on it, the undo step once the tool draws
+    stroke_before: Option<PixelBuffer>,
+    canvas_view: CanvasView,
+    menu_bar: MenuBar,
+    show_color_box: bool,
+    show_status_bar: bool,
+    /// Shown in the status bar until the time
+    status_message: Option<(String, f64)>,
+    exit_requested: bool,
     mouse_position: WindowVector2,
     mouse_in_canvas: bool,
+    /// The current tool draws its own cursor over the canvas
+    cursor_hidden: bool,
     /// This user state is to be passed to the actions
     user_state: UserState,
+    /// Input of the current frame, live or replayed
+    input: FrameInput,
+    /// Tools selected during the current frame
+    tool_switches: Vec<usize>,
+    recorder: Option<SessionRecorder>,
+    /// Frames left to replay, live input is used once they run out
+    replay: Option<std::vec::IntoIter<RecordedFrame>>,
+    /// Frames played since the window opened
+    frame_count: usize,
+    /// Images opened, most recent first
+    recent_files: Vec<PathBuf>,
+    /// Where the settings are written when the window closes
+    settings_path: Option<PathBuf>,
+}
+
+/// Name of the tool for its button, with the key selecting it
+fn tooltip(entry: &ToolEntry, keymap: &Keymap) -> String {
+    match keymap.binding(&Action::tool(entry.name)) {
+        Some(binding) => format!("{} ({})", entry.name, binding),
+        None => entry.name.to_string(),
+    }
+}
+
+/// Window pixels per UI pixel, from the display's DPI, whole so the icons stay sharp.
+/// macOS already scales windows for Retina displays.
+fn ui_scale(rl: &RaylibHandle) -> f32 {
+    if cfg!(target_os = "macos") {
+        return 1f32;
+    }
+    rl.get_window_scale_dpi().x.round().max(1f32)
 }
 
 impl Ui {
     pub fn init() -> Self {
+        let settings_path = settings::default_path();
+        let settings = settings_path.as_deref().map(Settings::load).unwrap_or_default();
+
+        let (width, height) = settings.window_size;
         let (mut rl, thread) = raylib::init()
-            .size(SCREEN_WIDTH, SCREEN_HEIGHT)
+            .size(width.max(MIN_WINDOW_WIDTH), height.max(MIN_WINDOW_HEIGHT))
+            .resizable()
             .title("PaintIt")
             .build();
+        let scale = ui_scale(&rl);
+        if scale != 1f32 {
+            rl.set_window_size((width.max(MIN_WINDOW_WIDTH) as f32 * scale) as i32,
+                               (height.max(MIN_WINDOW_HEIGHT) as f32 * scale) as i32);
+        }
+        rl.set_window_min_size((MIN_WINDOW_WIDTH as f32 * scale) as i32, (MIN_WINDOW_HEIGHT as f32 * scale) as i32);
+
+        // Escape closes the menus and the dialogs, not the window
+        rl.set_exit_key(None);
 
         let textures = rl.load_texture(&thread, "resources/tools.png").unwrap();
         let mut atlas_sources: [Rectangle; TEXTURE_NUMBER] = Default::default();
@@ -56,194 +218,994 @@ impl Ui {
             };
         }
 
-        let mut button_positions: [Vector2; TEXTURE_NUMBER] = Default::default();
-        for i in 0..TEXTURE_NUMBER {
-            button_positions[i] = Vector2 {
-                x: ((i % 2) * BUTTON_SIZE) as f32,
-                y: ((i / 2) * BUTTON_SIZE) as f32
-            };
-        }
-
-        let mut button_bounds: [Rectangle; TEXTURE_NUMBER] = Default::default();
-        for i in 0..TEXTURE_NUMBER {
-            button_bounds[i] = Rectangle {
-                x: button_positions[i].x,
-                y: button_positions[i].y,
-                width: (TEXTURE_SIZE + 16) as f32,
-                height: (TEXTURE_SIZE + 16) as f32
-            };
-        }
+        let tools = ToolRegistry::with_default_tools();
+        let keymap = match keymap::default_path() {
+            Some(path) => Keymap::load(&tools, &path).unwrap_or_else(|error| {
+                eprintln!("Could not load keymap {}, using the default keys: {}", path.display(), error);
+                Keymap::defaults(&tools)
+            }),
+            None => Keymap::defaults(&tools),
+        };
 
-        let mut current_pressed: Option<ActionState> = None;
         let mut current_colors: [Color; 2] = [Color::BLACK, Color::WHITE];
 
         let canvas_position = Vector2 {
             x: (BUTTON_SIZE * 2) as f32 + CANVAS_MARGIN,
             y: CANVAS_MARGIN
         };
-        let mut canvas_image = Image::gen_image_color(743, 406, TRANSPARENT);
+        let document = Document::new(NEW_IMAGE_WIDTH, NEW_IMAGE_HEIGHT, TRANSPARENT);
 
-        let mut canvas_dirty = false;
-        let mut canvas_texture = rl.load_texture_from_image(&thread, &canvas_image).unwrap();
+        // Placed by layout, once the window size is known
+        let canvas_view = CanvasView::new(Rectangle::default());
 
-        let canvas_rectangle = Rectangle {
-            x: canvas_position.x,
-            y: canvas_position.y,
-            width: canvas_image.width as f32,
-            height: canvas_image.height as f32
-        };
+        let tool_options = ToolOptions::new(Vector2::zero(), (BUTTON_SIZE * 2) as f32 - 12f32);
+
+        let palette_bar = PaletteBar::new(Vector2::zero());
+        let recent_colors = RecentColors::new(Vector2::zero());
 
         let mouse_position = WindowVector2(Vector2::new(0.0, 0.0));
         let user_state = UserState {
             mouse_position,
             current_colors: current_colors,
             canvas_position: WindowVector2(canvas_position),
-            spray_size: SpraySize::SizeOne,
+            spray_size: tool_options.spray_size,
+            spray_density: tool_options.spray_density,
             brush_size: BrushSize::Two,
             brush_type: BrushType::Circle,
-            canvas_image: canvas_image.clone(),
+            antialiasing: tool_options.antialiasing,
+            line_width: tool_options.line_width,
+            fill_mode: tool_options.fill_mode,
+            corner_radius: tool_options.corner_radius,
+            sample_size: tool_options.sample_size,
         };
 
-        return Ui {
+        let mut ui = Ui {
             rl,
             thread,
             textures,
             atlas_sources,
-            button_positions,
-            button_bounds,
-            current_pressed,
+            tools,
+            keymap,
+            screen_size: Vector2::zero(),
+            scale,
+            button_positions: Vec::new(),
+            button_bounds: Vec::new(),
+            current_tool: None,
+            hovered_tool: None,
+            held_tool: None,
+            previous_tool: None,
             current_colors,
-            canvas_position,
-            canvas_image,
-            canvas_dirty,
-            canvas_texture,
-            canvas_rectangle,
+            tool_options,
+            palette_bar,
+            recent_colors,
+            custom_colors: [None; CUSTOM_COLORS],
+            edit_colors: None,
+            dialog: None,
+            document,
+            image_path: None,
+            stroke_open: false,
+            stroke_before: None,
+            canvas_view,
+            menu_bar: MenuBar::default(),
+            show_color_box: true,
+            show_status_bar: true,
+            status_message: None,
+            exit_requested: false,
             mouse_position,
             mouse_in_canvas: false,
+            cursor_hidden: false,
             user_state,
+            input: FrameInput::default(),
+            tool_switches: Vec::new(),
+            recorder: None,
+            replay: None,
+            frame_count: 0,
+            recent_files: Vec::new(),
+            settings_path,
         };
+        ui.apply_settings(&settings);
+        ui.fit_window();
+        ui
+    }
+
+    /// Lay out for the window as it is now
+    fn fit_window(&mut self) {
+        self.scale = ui_scale(&self.rl);
+        self.screen_size = Vector2::new(self.rl.get_screen_width() as f32 / self.scale,
+                                        self.rl.get_screen_height() as f32 / self.scale);
+        self.layout();
+    }
+
+    /// Make the window the size it had in a recorded session, and lay out for that size
+    /// whatever the window manager makes of it
+    fn resize_window(&mut self, size: Vector2) {
+        self.rl.set_window_size((size.x * self.scale).round() as i32, (size.y * self.scale).round() as i32);
+        self.screen_size = size;
+        self.layout();
+    }
+
+    /// Place the toolbar, the canvas, the color bars and the status bar for the size of the window
+    fn layout(&mut self) {
+        let status_bar_y = self.status_bar_y();
+
+        self.button_positions = (0..self.tools.len())
+            .map(|i| Vector2 {
+                x: ((i % 2) * BUTTON_SIZE) as f32,
+                y: MENU_BAR_HEIGHT + ((i / 2) * BUTTON_SIZE) as f32
+            })
+            .collect();
+
+        self.button_bounds = self.button_positions.iter()
+            .map(|position| Rectangle {
+                x: position.x,
+                y: position.y,
+                width: (TEXTURE_SIZE + 16) as f32,
+                height: (TEXTURE_SIZE + 16) as f32
+            })
+            .collect();
+
+        self.tool_options.set_position(Vector2 {
+            x: 6f32,
+            y: MENU_BAR_HEIGHT + (self.tools.len().div_ceil(2) * BUTTON_SIZE) as f32 + 6f32,
+        });
+
+        let canvas_x = (BUTTON_SIZE * 2) as f32 + CANVAS_MARGIN;
+        // Leave room for the recent colors on the right
+        self.palette_bar.set_max_width(self.screen_size.x - canvas_x - RecentColors::width() - CANVAS_MARGIN * 5f32);
+        let colors_y = status_bar_y - self.palette_bar.height() - CANVAS_MARGIN * 2f32;
+        self.palette_bar.set_position(Vector2::new(canvas_x, colors_y));
+        self.recent_colors.set_position(Vector2::new(canvas_x + self.palette_bar.width() + CANVAS_MARGIN * 4f32,
+                                                     colors_y));
+
+        let canvas_bottom = if self.show_color_box { colors_y - CANVAS_MARGIN } else { status_bar_y };
+        let canvas_bounds = Rectangle {
+            x: canvas_x,
+            y: MENU_BAR_HEIGHT + CANVAS_MARGIN,
+            width: (self.screen_size.x - canvas_x - CANVAS_MARGIN).max(0f32),
+            height: (canvas_bottom - MENU_BAR_HEIGHT - CANVAS_MARGIN * 2f32).max(0f32),
+        };
+        self.canvas_view.set_bounds(canvas_bounds, self.document.canvas());
+
+        if let Some(dialog) = &mut self.edit_colors {
+            dialog.resize(self.screen_size);
+        }
+        if let Some((_, dialog)) = &mut self.dialog {
+            dialog.resize(self.screen_size);
+        }
+    }
+
+    /// Top of the status bar, or the bottom of the window while it is hidden
+    fn status_bar_y(&self) -> f32 {
+        if self.show_status_bar { self.screen_size.y - STATUS_BAR_HEIGHT } else { self.screen_size.y }
+    }
+
+    /// What to restore on the next launch
+    pub fn settings(&self) -> Settings {
+        Settings {
+            options: UserState { current_colors: self.current_colors, ..self.user_state.clone() },
+            palette: self.palette_bar.colors.clone(),
+            tool: self.current_tool.and_then(|i| self.tools.get(i)).map(|entry| entry.name.to_string()),
+            recent_files: self.recent_files.clone(),
+            recent_colors: self.recent_colors.colors().to_vec(),
+            window_size: (self.screen_size.x.round() as i32, self.screen_size.y.round() as i32),
+            show_color_box: self.show_color_box,
+            show_status_bar: self.show_status_bar,
+        }
+    }
+
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        let options = &settings.options;
+        self.current_colors = options.current_colors;
+        self.tool_options.spray_size = options.spray_size;
+        self.tool_options.spray_density = options.spray_density;
+        self.tool_options.antialiasing = options.antialiasing;
+        self.tool_options.line_width = options.line_width;
+        self.tool_options.fill_mode = options.fill_mode;
+        self.tool_options.corner_radius = options.corner_radius;
+        self.tool_options.sample_size = options.sample_size;
+        self.palette_bar.colors = settings.palette.clone();
+        self.current_tool = settings.tool.as_ref()
+            .and_then(|name| self.tools.iter().position(|entry| entry.name == name && entry.is_enabled()));
+        self.previous_tool = None;
+        self.recent_files = settings.recent_files.clone();
+        self.recent_colors.set_colors(&settings.recent_colors);
+        self.show_color_box = settings.show_color_box;
+        self.show_status_bar = settings.show_status_bar;
+        // The palette and the bars shown change the layout
+        self.layout();
+    }
+
+    /// Replace the canvas with the image, scrolled back to its top left corner
+    pub fn open_image(&mut self, path: &Path) -> Result<(), DocumentError> {
+        self.document = Document::open(path)?;
+        self.canvas_view.reset();
+        self.reset_current_tool();
+        self.set_image_path(path);
+        Ok(())
+    }
+
+    /// The file the image now belongs to, first in the recent files
+    fn set_image_path(&mut self, path: &Path) {
+        let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        self.recent_files.retain(|recent| *recent != path);
+        self.recent_files.insert(0, path.clone());
+        self.recent_files.truncate(RECENT_FILES_LIMIT);
+        self.image_path = Some(path);
+    }
+
+    /// Write the input of every frame from now on to a session log. Sessions start from the
+    /// default settings and a seed of their own, so they replay the same whatever the saved
+    /// state is. What the recording changes is not saved as the settings, which keeps the saved
+    /// ones from being replaced by the defaults.
+    pub fn record(&mut self, path: &Path) -> std::io::Result<()> {
+        let seed = rand::random();
+        self.recorder = Some(SessionRecorder::create(path, seed, self.screen_size)?);
+        self.apply_settings(&Settings { recent_files: self.recent_files.clone(), ..Settings::default() });
+        self.settings_path = None;
+        self.tools.seed(seed);
+        Ok(())
+    }
+
+    /// Drive the window from a session log instead of live input, until the log runs out.
+    /// The window takes the recorded sizes. What the replay changes is not saved as the settings.
+    pub fn replay(&mut self, path: &Path) -> Result<(), SessionError> {
+        let session = session::load(path)?;
+        self.replay = Some(session.frames.into_iter());
+        self.apply_settings(&Settings::default());
+        self.settings_path = None;
+        self.tools.seed(session.seed);
+        if session.window_size != Vector2::zero() {
+            self.resize_window(session.window_size);
+        }
+        Ok(())
     }
 
     fn update(&mut self) {
-        self.mouse_position = WindowVector2(self.rl.get_mouse_position());
+        // A replay keeps the recorded size until it ends
+        let resized = self.rl.is_window_resized() || ui_scale(&self.rl) != self.scale;
+        if resized && self.replay.is_none() {
+            self.fit_window();
+        }
+
+        // Always capture, so the live input does not pile up during a replay
+        let mut live = FrameInput::capture(&mut self.rl);
+        live.mouse_position /= self.scale;
+        if resized {
+            live.window_size = Some(self.screen_size);
+        }
+        let mut expected_switches = None;
+        self.input = match self.replay.as_mut().map(|frames| frames.next()) {
+            Some(Some(frame)) => {
+                if let Some(size) = frame.input.window_size {
+                    self.resize_window(size);
+                }
+                expected_switches = Some(frame.tool_switches);
+                frame.input
+            }
+            Some(None) => {
+                eprintln!("Replay finished after {} frames", self.frame_count);
+                self.replay = None;
+                live
+            }
+            None => live,
+        };
+
+        self.tool_switches.clear();
+        self.update_frame();
+        self.canvas_view.update(&mut self.rl, &self.thread, self.document.canvas_mut());
+        self.update_cursor();
+        self.frame_count += 1;
+
+        if let Some(expected) = expected_switches {
+            if expected != self.tool_switches {
+                eprintln!("Replay diverged at frame {}: tools {:?} were selected instead of {:?}",
+                          self.frame_count, self.tool_switches, expected);
+            }
+        }
+
+        if let Some(recorder) = &mut self.recorder {
+            let frame = RecordedFrame { input: self.input.clone(), tool_switches: self.tool_switches.clone() };
+            if let Err(error) = recorder.record(&frame) {
+                eprintln!("Could not record the session, stopped recording: {}", error);
+                self.recorder = None;
+            }
+        }
+    }
+
+    fn update_frame(&mut self) {
+        // Stay None and false while a dialog or a menu takes the input
+        let hovered_before = self.hovered_tool.take();
+        self.mouse_in_canvas = false;
+        let previous_mouse_position = self.mouse_position;
+        self.mouse_position = WindowVector2(self.input.mouse_position);
+        if self.input.is_mouse_button_down(MouseButton::MOUSE_BUTTON_MIDDLE) {
+            // Drag the canvas around with the middle button
+            self.canvas_view.scroll(previous_mouse_position.0 - self.mouse_position.0, self.document.canvas());
+        }
+
         self.user_state = UserState {
             mouse_position: self.mouse_position,
             current_colors: self.current_colors,
-            canvas_position: WindowVector2(self.canvas_position),
-            spray_size: SpraySize::SizeOne,
+            canvas_position: WindowVector2(self.canvas_view.canvas_position()),
+            spray_size: self.tool_options.spray_size,
+            spray_density: self.tool_options.spray_density,
             brush_size: BrushSize::Two,
             brush_type: BrushType::Circle,
-            canvas_image: self.canvas_image.clone(),
+            antialiasing: self.tool_options.antialiasing,
+            line_width: self.tool_options.line_width,
+            fill_mode: self.tool_options.fill_mode,
+            corner_radius: self.tool_options.corner_radius,
+            sample_size: self.tool_options.sample_size,
         };
 
-        for i in 0..TEXTURE_NUMBER {
-            let button_pressed = self.button_bounds[i].check_collision_point_rec(self.mouse_position.0)
-                && self.rl.is_mouse_button_released(MouseButton::MOUSE_BUTTON_LEFT);
+        if let Some(dialog) = &mut self.edit_colors {
+            match dialog.update(&self.input) {
+                DialogResult::Open => return,
+                DialogResult::Cancelled => {}
+                DialogResult::Accepted(color, palette_index) => {
+                    self.current_colors[0] = color;
+                    if let Some(i) = palette_index {
+                        self.palette_bar.colors[i] = color;
+                    }
+                }
+            }
+            self.custom_colors = dialog.custom_colors;
+            self.edit_colors = None;
+            return;
+        }
 
-            if button_pressed {
-                let button = ActionState::try_from(i as u32).unwrap();
-                println!("pressed {:?}", button);
-                self.current_pressed = Option::from(button);
+        if let Some((purpose, mut dialog)) = self.dialog.take() {
+            match dialog.update(&self.input) {
+                DialogEvent::Open => self.dialog = Some((purpose, dialog)),
+                DialogEvent::Cancelled => {}
+                DialogEvent::Clicked(_) => self.accept_dialog(purpose, dialog),
             }
+            return;
         }
 
-        let mouse_in_canvas = self.canvas_rectangle.check_collision_point_rec(self.mouse_position.0);
-        let canvas_pressed = mouse_in_canvas
-            && self.rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT);
+        match self.menu_bar.update(&self.menus(), &self.input) {
+            MenuResult::Closed => {}
+            MenuResult::Open => return,
+            MenuResult::Chosen(command) => {
+                self.run_menu_command(command);
+                return;
+            }
+        }
 
-        //TODO change to previous tool after color picker
-        if let Some(generic_state) = self.current_pressed.as_deref_mut() {
-            specify_state!(generic_state, specific_state, {
-                if canvas_pressed {
-                    specific_state.update_pressed(&self.user_state, &mut self.rl, &self.thread);
-                } else {
-                    specific_state.update_unpressed(&self.user_state, &mut self.rl, &self.thread);
+        if let Some(action) = self.keymap.pressed(&self.input).cloned() {
+            self.run_action(action);
+        }
+
+        for path in self.input.dropped_files.clone() {
+            if PaletteFormat::from_path(&path).is_some() {
+                self.import_palette(&path);
+            }
+        }
+
+        if self.show_color_box {
+            self.recent_colors.update(&self.input, &mut self.current_colors);
+            let palette_event = self.palette_bar.update(&self.input, &mut self.current_colors);
+            if let Some(PaletteEvent::EditColor(palette_index)) = palette_event {
+                self.open_edit_colors(palette_index);
+                return;
+            }
+        }
+
+        if let Some(entry) = self.current_tool.and_then(|i| self.tools.get(i)) {
+            self.tool_options.update(entry.options, &self.input);
+        }
+
+        let hovered = self.button_bounds.iter()
+            .position(|bounds| bounds.check_collision_point_rec(self.mouse_position.0));
+        self.hovered_tool = hovered.map(|i| match hovered_before {
+            Some((before, since)) if before == i => (i, since),
+            _ => (i, self.input.time),
+        });
+        if self.input.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+            self.held_tool = hovered;
+        }
+        if self.input.is_mouse_button_released(MouseButton::MOUSE_BUTTON_LEFT) {
+            if let Some(i) = self.held_tool.take().filter(|i| hovered == Some(*i)) {
+                self.select_tool(i);
+            }
+        }
+
+        self.mouse_in_canvas = self.canvas_view.visible_area(self.document.canvas())
+            .check_collision_point_rec(self.mouse_position.0);
+        let canvas_pressed = self.mouse_in_canvas
+            && self.input.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT);
+
+        let input = InputEvent {
+            pressed: canvas_pressed,
+            modifiers: self.input.modifiers,
+            elapsed: self.input.frame_time,
+        };
+
+        let mut picked_color = None;
+        let tool = self.current_tool
+            .and_then(|i| self.tools.get_mut(i))
+            .and_then(|entry| entry.tool.as_deref_mut());
+        if let Some(tool) = tool {
+            // Keep the canvas from before the stroke, sharing its tiles until the tool draws
+            if canvas_pressed && !self.stroke_open && self.stroke_before.is_none() {
+                self.stroke_before = Some(self.document.canvas().clone());
+            }
+            let canvas_dirty = update_execute_action::apply_input(tool, &self.user_state, &input,
+                                                                  self.document.canvas_mut());
+
+            if canvas_dirty {
+                if let Some(before) = self.stroke_before.take() {
+                    self.document.push_undo(before);
+                }
+                self.stroke_open = true;
+                for color in tool.get_drawn_colors() {
+                    self.recent_colors.record(color);
+                }
+            }
+            picked_color = tool.get_color();
+            if !canvas_pressed && !canvas_dirty {
+                self.stroke_open = false;
+            }
+        }
+
+        if let Some(color) = picked_color {
+            self.current_colors[0] = color;
+            self.recent_colors.record(color);
+
+            // Go back to the tool used before the color picker, or start a new pick
+            if let Some(entry) = self.current_tool.and_then(|i| self.tools.get_mut(i)) {
+                entry.reset();
+            }
+            if let Some(previous) = self.previous_tool.take() {
+                self.current_tool = Some(previous);
+                self.tool_switches.push(previous);
+            }
+        }
+
+        if let Some(tool) = self.current_tool
+            .and_then(|i| self.tools.get_mut(i))
+            .and_then(|entry| entry.tool.as_deref_mut()) {
+            tool.prepare(&mut self.rl, &self.thread);
+        }
+    }
+
+    /// Cursor the current tool draws over the canvas, when the mouse is there
+    fn tool_cursor(&self) -> Option<ToolCursor> {
+        if !self.mouse_in_canvas {
+            return None;
+        }
+        self.current_tool
+            .and_then(|i| self.tools.get(i))
+            .and_then(|entry| entry.tool.as_deref())
+            .and_then(|tool| tool.cursor(&self.user_state))
+    }
+
+    /// Hide the system cursor while the tool's own is shown, so only one follows the mouse
+    fn update_cursor(&mut self) {
+        let hide = self.tool_cursor().is_some();
+        if hide != self.cursor_hidden {
+            if hide {
+                self.rl.hide_cursor();
+            } else {
+                self.rl.show_cursor();
+            }
+            self.cursor_hidden = hide;
+        }
+    }
+
+    fn open_edit_colors(&mut self, palette_index: Option<usize>) {
+        let color = match palette_index {
+            Some(i) => self.palette_bar.colors[i],
+            None => self.current_colors[0],
+        };
+        self.edit_colors = Some(EditColorsDialog::new(self.screen_size, color, palette_index, self.custom_colors));
+    }
+
+    fn open_new_image(&mut self) {
+        let canvas = self.document.canvas();
+        let field = |y: f32| Rectangle::new(80f32, y, 80f32, 18f32);
+        let label = |y: f32| Rectangle::new(12f32, y, 60f32, 18f32);
+        self.dialog = Some((
+            DialogPurpose::NewImage,
+            Dialog::new("New Image", Vector2::new(224f32, 150f32), self.screen_size)
+                .with_label("Width:", label(32f32))
+                .with_number_field("width", field(32f32), canvas.width(), 1, MAX_IMAGE_SIZE)
+                .with_label("Height:", label(56f32))
+                .with_number_field("height", field(56f32), canvas.height(), 1, MAX_IMAGE_SIZE)
+                .with_check_box("transparent", "Transparent background", Rectangle::new(12f32, 82f32, 190f32, 18f32),
+                                true)
+                .with_button("ok", "OK", Rectangle::new(44f32, 114f32, 80f32, 24f32))
+                .with_button("cancel", "Cancel", Rectangle::new(132f32, 114f32, 80f32, 24f32))
+                .with_default_button("ok")
+                .with_cancel_button("cancel")
+        ));
+    }
+
+    /// Asks for the file name of an image or a palette to open or save
+    fn path_dialog(&self, title: &str, path: &str) -> Dialog {
+        Dialog::new(title, Vector2::new(320f32, 110f32), self.screen_size)
+            .with_label("File name:", Rectangle::new(12f32, 28f32, 296f32, 18f32))
+            .with_text_field("path", Rectangle::new(12f32, 48f32, 296f32, 18f32), path)
+            .with_button("ok", "OK", Rectangle::new(140f32, 76f32, 80f32, 24f32))
+            .with_button("cancel", "Cancel", Rectangle::new(228f32, 76f32, 80f32, 24f32))
+            .with_default_button("ok")
+            .with_cancel_button("cancel")
+    }
+
+    fn open_open_image(&mut self) {
+        let path = self.image_path.as_ref().map(|path| path.display().to_string()).unwrap_or_default();
+        self.dialog = Some((DialogPurpose::OpenImage, self.path_dialog("Open", &path)));
+    }
+
+    fn open_save_image_as(&mut self) {
+        let path = self.image_path.as_ref().map_or_else(|| DEFAULT_IMAGE_FILE.to_string(),
+                                                        |path| path.display().to_string());
+        self.dialog = Some((DialogPurpose::SaveImageAs, self.path_dialog("Save As", &path)));
+    }
+
+    fn open_import_palette(&mut self) {
+        let path = self.palette_bar.path.as_ref().map(|path| path.display().to_string()).unwrap_or_default();
+        self.dialog = Some((DialogPurpose::ImportPalette, self.path_dialog("Import Palette", &path)));
+    }
+
+    fn open_export_palette(&mut self) {
+        let path = self.palette_bar.path.as_ref().map_or_else(|| DEFAULT_PALETTE_FILE.to_string(),
+                                                              |path| path.display().to_string());
+        self.dialog = Some((DialogPurpose::ExportPalette, self.path_dialog("Export Palette", &path)));
+    }
+
+    /// Yes or No question, No being the default
+    fn confirm_dialog(&self, title: &str, message: &str, question: &str) -> Dialog {
+        Dialog::new(title, Vector2::new(320f32, 110f32), self.screen_size)
+            .with_label(message, Rectangle::new(12f32, 28f32, 296f32, 18f32))
+            .with_label(question, Rectangle::new(12f32, 46f32, 296f32, 18f32))
+            .with_button("yes", "Yes", Rectangle::new(140f32, 76f32, 80f32, 24f32))
+            .with_button("no", "No", Rectangle::new(228f32, 76f32, 80f32, 24f32))
+            .with_default_button("no")
+            .with_cancel_button("no")
+    }
+
+    fn replace_dialog(&self, title: &str, path: &Path) -> Dialog {
+        let name = path.file_name().map_or_else(|| path.display().to_string(),
+                                                |name| name.to_string_lossy().into_owned());
+        self.confirm_dialog(title, &format!("{} already exists.", name), "Do you want to replace it?")
+    }
+
+    /// Start over on a blank canvas
+    fn new_image(&mut self, width: i32, height: i32, background: Color) {
+        self.document = Document::new(width, height, background);
+        self.image_path = None;
+        self.canvas_view.reset();
+        self.reset_current_tool();
+    }
+
+    /// Replace the image, asking first when that drops changes that were not saved
+    fn switch_image(&mut self, title: &str, next: NextImage) {
+        if self.document.is_modified() {
+            let discard = self.confirm_dialog(title, "The image has changes that are not saved.",
+                                              "Do you want to discard them?");
+            self.dialog = Some((DialogPurpose::DiscardChanges(next), discard));
+        } else {
+            self.load_next_image(next);
+        }
+    }
+
+    fn load_next_image(&mut self, next: NextImage) {
+        match next {
+            NextImage::Blank { width, height, background } => self.new_image(width, height, background),
+            NextImage::File(path) => {
+                if let Err(error) = self.open_image(&path) {
+                    self.show_message(format!("Could not open image {}: {}", path.display(), error));
                 }
+            },
+        }
+    }
 
-                // TODO use a layer system to allow for do-undo
-                self.canvas_dirty = specific_state.draw(&mut self.canvas_image);
+    /// Do what the dialog was opened for, once its OK button is clicked
+    fn accept_dialog(&mut self, purpose: DialogPurpose, dialog: Dialog) {
+        let path = PathBuf::from(dialog.text("path").unwrap_or_default().trim());
+        match purpose {
+            DialogPurpose::NewImage => {
+                let width = dialog.number("width").unwrap_or(NEW_IMAGE_WIDTH);
+                let height = dialog.number("height").unwrap_or(NEW_IMAGE_HEIGHT);
+                let background = if dialog.checked("transparent") == Some(false) {
+                    self.current_colors[1]
+                } else {
+                    TRANSPARENT
+                };
+                self.switch_image("New Image", NextImage::Blank { width, height, background });
+            },
+            DialogPurpose::DiscardChanges(next) => self.load_next_image(next),
+            // Nothing to do without a file name, so wait for one
+            DialogPurpose::OpenImage | DialogPurpose::SaveImageAs | DialogPurpose::ImportPalette
+            | DialogPurpose::ExportPalette if path.as_os_str().is_empty() => {
+                self.dialog = Some((purpose, dialog));
+            },
+            DialogPurpose::OpenImage => self.switch_image("Open", NextImage::File(path)),
+            DialogPurpose::SaveImageAs if path.exists() => {
+                let replace = self.replace_dialog("Save As", &path);
+                self.dialog = Some((DialogPurpose::ReplaceImage(path), replace));
+            },
+            DialogPurpose::SaveImageAs => self.save_image_to(path),
+            DialogPurpose::ReplaceImage(path) => self.save_image_to(path),
+            DialogPurpose::ImportPalette => self.import_palette(&path),
+            DialogPurpose::ExportPalette if path.exists() => {
+                let replace = self.replace_dialog("Export Palette", &path);
+                self.dialog = Some((DialogPurpose::ReplacePalette(path), replace));
+            },
+            DialogPurpose::ExportPalette => self.export_palette(&path),
+            DialogPurpose::ReplacePalette(path) => self.export_palette(&path),
+            DialogPurpose::ExtractPalette => {
+                let size = dialog.number("colors").unwrap_or(DEFAULT_PALETTE.len() as i32) as usize;
+                self.extract_palette(size, dialog.checked("k-means") == Some(true));
+            },
+        }
+    }
+
+    /// Shown in the status bar for a few seconds, and printed to stderr
+    fn show_message(&mut self, message: String) {
+        eprintln!("{}", message);
+        self.status_message = Some((message, self.input.time + STATUS_MESSAGE_SECONDS));
+    }
+
+    /// Start the current tool over, when the canvas it was working on is replaced
+    fn reset_current_tool(&mut self) {
+        if let Some(entry) = self.current_tool.and_then(|i| self.tools.get_mut(i)) {
+            entry.reset();
+        }
+        self.stroke_open = false;
+        self.stroke_before = None;
+    }
+
+    /// Show the document's canvas after it was replaced as a whole, by undo or an image command
+    fn canvas_replaced(&mut self) {
+        self.canvas_view.refresh(self.document.canvas());
+        self.reset_current_tool();
+    }
 
-                if self.canvas_dirty {
-                    println!("{:?}", specific_state);
-                    specific_state.update_after_draw(&self.user_state);
+    /// Edit the image as one undo step, with the current colors and options
+    fn apply_operation(&mut self, operation: Operation) {
+        self.document.options = self.user_state.clone();
+        if self.document.apply(&operation) {
+            self.canvas_replaced();
+        }
+    }
+
+    /// Replace the palette bar's colors, which can change its size
+    fn import_palette(&mut self, path: &Path) {
+        match self.palette_bar.import(path) {
+            Ok(()) => {
+                self.show_message(format!("Loaded palette {}", path.display()));
+                self.layout();
+            },
+            Err(error) => self.show_message(format!("Could not load palette {}: {}", path.display(), error)),
+        }
+    }
+
+    fn export_palette(&mut self, path: &Path) {
+        match self.palette_bar.export(path) {
+            Ok(()) => self.show_message(format!("Saved palette {}", path.display())),
+            Err(error) => self.show_message(format!("Could not save palette {}: {}", path.display(), error)),
+        }
+    }
+
+    /// Write over the file the image came from, or ask for a new one
+    fn save_image(&mut self) {
+        match self.image_path.clone() {
+            Some(path) => self.save_image_to(path),
+            None => self.open_save_image_as(),
+        }
+    }
+
+    fn save_image_to(&mut self, path: PathBuf) {
+        match self.document.save(&path) {
+            Ok(()) => {
+                self.show_message(format!("Saved image {}", path.display()));
+                self.set_image_path(&path);
+            },
+            Err(error) => self.show_message(format!("Could not save image {}: {}", path.display(), error)),
+        }
+    }
+
+    /// Do what the keymap binds to a key, or what a menu item runs
+    fn run_action(&mut self, action: Action) {
+        match action {
+            Action::Tool(_) => {
+                let index = self.tools.iter().position(|entry| Action::tool(entry.name) == action);
+                if let Some(i) = index {
+                    self.select_tool(i);
                 }
-                if let Some(color) =  specific_state.get_color() {
-                    self.current_colors[0] = color;
+            },
+            Action::NewImage => self.open_new_image(),
+            Action::Open => self.open_open_image(),
+            Action::Save => self.save_image(),
+            Action::SaveAs => self.open_save_image_as(),
+            Action::Undo => {
+                if self.document.undo() {
+                    self.canvas_replaced();
                 }
-             });
+            },
+            Action::Redo => {
+                if self.document.redo() {
+                    self.canvas_replaced();
+                }
+            },
+            Action::ToggleColorBox => {
+                self.show_color_box = !self.show_color_box;
+                self.layout();
+            },
+            Action::ToggleStatusBar => {
+                self.show_status_bar = !self.show_status_bar;
+                self.layout();
+            },
+            Action::FlipHorizontal => self.apply_operation(Operation::FlipHorizontal),
+            Action::FlipVertical => self.apply_operation(Operation::FlipVertical),
+            Action::ClearImage => self.apply_operation(Operation::Clear),
+            Action::EditColors => self.open_edit_colors(None),
+            Action::SwapColors => self.current_colors.swap(0, 1),
+            Action::DefaultColors => self.current_colors = [Color::BLACK, Color::WHITE],
+            Action::ImportPalette => self.open_import_palette(),
+            Action::ExportPalette => self.open_export_palette(),
+            Action::ExtractPalette => self.open_extract_palette(),
+        }
+    }
+
+    fn run_menu_command(&mut self, command: MenuCommand) {
+        match command {
+            MenuCommand::Run(action) => self.run_action(action),
+            MenuCommand::OpenRecent(path) => self.switch_image("Open", NextImage::File(path)),
+            MenuCommand::About => {
+                self.show_message(format!("PaintIt {}, a small paint program", env!("CARGO_PKG_VERSION")));
+            },
+            MenuCommand::Exit => self.exit_requested = true,
+        }
+    }
+
+    /// Menu item running the action, with its key from the keymap
+    fn action_item(&self, label: &str, action: Action) -> MenuEntry<MenuCommand> {
+        let binding = self.keymap.binding(&action);
+        MenuEntry::Item(MenuItem::new(label, MenuCommand::Run(action)).with_accelerator(binding))
+    }
+
+    /// The menu bar as it is this frame, with the items that cannot run disabled
+    fn menus(&self) -> Vec<Menu<MenuCommand>> {
+        let item = |label: &str, action: Action| self.action_item(label, action);
+        let with_state = |entry: MenuEntry<MenuCommand>, enabled: bool, checked: bool| match entry {
+            MenuEntry::Item(item) => MenuEntry::Item(item.with_enabled(enabled).with_checked(checked)),
+            separator => separator,
+        };
+
+        let mut file = vec![
+            item("New...", Action::NewImage),
+            item("Open...", Action::Open),
+            item("Save", Action::Save),
+            item("Save As...", Action::SaveAs),
+        ];
+        if !self.recent_files.is_empty() {
+            file.push(MenuEntry::Separator);
+            file.extend(self.recent_files.iter().enumerate().map(|(i, path)| {
+                let name = path.file_name().map_or_else(|| path.display().to_string(),
+                                                        |name| name.to_string_lossy().into_owned());
+                MenuEntry::Item(MenuItem::new(format!("{} {}", i + 1, name), MenuCommand::OpenRecent(path.clone())))
+            }));
+        }
+        file.push(MenuEntry::Separator);
+        file.push(MenuEntry::Item(MenuItem::new("Exit", MenuCommand::Exit)));
+
+        vec![
+            Menu { title: "File", entries: file },
+            Menu {
+                title: "Edit",
+                entries: vec![
+                    with_state(item("Undo", Action::Undo), self.document.can_undo(), false),
+                    with_state(item("Redo", Action::Redo), self.document.can_redo(), false),
+                ],
+            },
+            Menu {
+                title: "View",
+                entries: vec![
+                    with_state(item("Color Box", Action::ToggleColorBox), true, self.show_color_box),
+                    with_state(item("Status Bar", Action::ToggleStatusBar), true, self.show_status_bar),
+                ],
+            },
+            Menu {
+                title: "Image",
+                entries: vec![
+                    item("Flip Horizontal", Action::FlipHorizontal),
+                    item("Flip Vertical", Action::FlipVertical),
+                    MenuEntry::Separator,
+                    item("Clear Image", Action::ClearImage),
+                ],
+            },
+            Menu {
+                title: "Colors",
+                entries: vec![
+                    item("Edit Colors...", Action::EditColors),
+                    item("Swap Colors", Action::SwapColors),
+                    item("Default Colors", Action::DefaultColors),
+                    MenuEntry::Separator,
+                    item("Import Palette...", Action::ImportPalette),
+                    item("Export Palette...", Action::ExportPalette),
+                    item("Extract Palette...", Action::ExtractPalette),
+                ],
+            },
+            Menu {
+                title: "Help",
+                entries: vec![MenuEntry::Item(MenuItem::new("About PaintIt", MenuCommand::About))],
+            },
+        ]
+    }
+
+    /// Start the tool over and make it the current one, unless it is disabled
+    fn select_tool(&mut self, i: usize) {
+        let Some(entry) = self.tools.get_mut(i) else {
+            return;
+        };
+        if !entry.is_enabled() {
+            return;
+        }
+
+        entry.reset();
+        self.tool_switches.push(i);
+        if self.current_tool != Some(i) {
+            self.previous_tool = self.current_tool.replace(i);
         }
+    }
+
+    /// Replace the palette with the main colors of the selection, or of the whole canvas
+    fn open_extract_palette(&mut self) {
+        let colors = self.palette_bar.colors.len().clamp(2, MAX_EXTRACTED_COLORS as usize) as i32;
+        self.dialog = Some((
+            DialogPurpose::ExtractPalette,
+            Dialog::new("Extract Palette", Vector2::new(224f32, 124f32), self.screen_size)
+                .with_label("Colors:", Rectangle::new(12f32, 32f32, 60f32, 18f32))
+                .with_number_field("colors", Rectangle::new(80f32, 32f32, 80f32, 18f32), colors, 2,
+                                   MAX_EXTRACTED_COLORS)
+                .with_check_box("k-means", "Refine with k-means", Rectangle::new(12f32, 58f32, 190f32, 18f32), true)
+                .with_button("ok", "OK", Rectangle::new(44f32, 88f32, 80f32, 24f32))
+                .with_button("cancel", "Cancel", Rectangle::new(132f32, 88f32, 80f32, 24f32))
+                .with_default_button("ok")
+                .with_cancel_button("cancel")
+        ));
+    }
 
-        if self.canvas_dirty {
-            self.canvas_texture = self.rl.load_texture_from_image(&self.thread, &self.canvas_image).unwrap();
-            self.canvas_dirty = false;
+    /// Replace the palette with the main colors of the selection, or of the picture. Median cut
+    /// alone is quicker, k-means then moves the colors closer to the pixels they stand for.
+    fn extract_palette(&mut self, size: usize, k_means: bool) {
+        let selection = self.current_tool
+            .and_then(|i| self.tools.get(i))
+            .and_then(|entry| entry.tool.as_deref())
+            .and_then(|tool| tool.selected_image());
+        let pixels = quantization::sample(selection.unwrap_or(self.document.canvas()), PALETTE_EXTRACTION_SAMPLES);
+
+        let colors = if k_means {
+            quantization::k_means(&pixels, size, PALETTE_EXTRACTION_ITERATIONS)
+        } else {
+            quantization::median_cut(&pixels, size)
+        };
+        if colors.is_empty() {
+            self.show_message("Nothing to extract a palette from".to_string());
+            return;
         }
+        self.palette_bar.colors = colors;
+        self.layout();
     }
 
     fn draw(&mut self) {
+        let menus = self.menus();
+        let tool_cursor = self.tool_cursor();
+        let status_bar_y = self.status_bar_y() as i32;
         let mut handle = self.rl.begin_drawing(&self.thread);
+        raylib_extensions::scale_drawing(self.scale);
+        let screen_width = self.screen_size.x as i32;
+        let menu_bar_height = MENU_BAR_HEIGHT as i32;
 
         handle.clear_background(Color::GRAY);
 
-        handle.draw_rectangle(0, 0,
-                              (BUTTON_SIZE * 2) as i32, SCREEN_HEIGHT - 20,
+        handle.draw_rectangle(0, menu_bar_height,
+                              (BUTTON_SIZE * 2) as i32, status_bar_y - menu_bar_height,
                               Color::LIGHTGRAY);
-        handle.draw_rectangle_lines(0, 0,
-                                    (BUTTON_SIZE * 2) as i32, SCREEN_HEIGHT - 20,
+        handle.draw_rectangle_lines(0, menu_bar_height,
+                                    (BUTTON_SIZE * 2) as i32, status_bar_y - menu_bar_height,
                                     Color::BLACK);
 
-        for i in 0..TEXTURE_NUMBER {
+        let hovered_tool = self.hovered_tool.map(|(i, _)| i);
+        for (i, entry) in self.tools.iter().enumerate() {
+            let bounds = self.button_bounds[i];
+            let enabled = entry.is_enabled();
+            let hovered = enabled && hovered_tool == Some(i);
+            // Pushed in while selected, or while the mouse holds it down
+            let sunken = self.current_tool == Some(i) || (hovered && self.held_tool == Some(i));
+            let face = if hovered {
+                HOVER_COLOR
+            } else if self.current_tool == Some(i) {
+                SELECTED_COLOR
+            } else {
+                Color::LIGHTGRAY
+            };
+            draw_bevel(&mut handle, bounds, face, sunken);
+
+            // The icon moves with the face of the button
+            let offset = if sunken { 1f32 } else { 0f32 };
             let position = Vector2 {
-                x: self.button_positions[i].x + 8f32,
-                y: self.button_positions[i].y + 8f32
+                x: self.button_positions[i].x + 8f32 + offset,
+                y: self.button_positions[i].y + 8f32 + offset
             };
+            // Tools that are not implemented yet are faded out
+            let tint = if enabled { Color::WHITE } else { Color::WHITE.fade(0.3) };
+            handle.draw_texture_rec(&self.textures, self.atlas_sources[entry.icon], position, tint);
+        }
 
-            if self.current_pressed.as_ref().is_some_and(|b| u32::from(b) == (i as u32)) {
-                // Draw the pressed button
-                handle.draw_texture_rec(&self.textures, self.atlas_sources[i], position, Color::WHITE);
-            } else {
-                // Draw the button
-                handle.draw_texture_rec(&self.textures, self.atlas_sources[i], position, Color::WHITE);
-                handle.draw_rectangle_lines_ex(self.button_bounds[i], 1f32, Color::BLACK);
-                handle.draw_line(
-                    (self.button_bounds[i].x + 1f32) as i32,
-                    self.button_bounds[i].y as i32,
-                    (self.button_bounds[i].x + self.button_bounds[i].width - 1f32) as i32,
-                    self.button_bounds[i].y as i32,
-                    Color::WHITE
-                );
-                handle.draw_line(
-                    (self.button_bounds[i].x + 1f32) as i32,
-                    self.button_bounds[i].y as i32,
-                    self.button_bounds[i].x as i32,
-                    (self.button_bounds[i].y + self.button_bounds[i].height - 1f32) as i32,
-                    Color::WHITE
-                );
-            }
+        let current_entry = self.current_tool.and_then(|i| self.tools.get(i));
+        self.tool_options.draw(current_entry.map(|entry| entry.options).unwrap_or_default(), &mut handle);
+        if self.show_color_box {
+            self.palette_bar.draw(&mut handle, self.current_colors);
+            self.recent_colors.draw(&mut handle);
         }
 
-        handle.draw_rectangle(0, SCREEN_HEIGHT - 20, SCREEN_WIDTH, 20, Color::LIGHTGRAY);
-        handle.draw_line(0, SCREEN_HEIGHT - 20, SCREEN_WIDTH, SCREEN_HEIGHT - 20, Color::WHITE);
+        self.canvas_view.draw(self.document.canvas(), &mut handle);
 
-        handle.draw_rectangle_rec(self.canvas_rectangle, Color::WHITE);
-        handle.draw_texture_v(&self.canvas_texture, self.canvas_position, Color::WHITE); // TODO fix tint
+        let message = self.status_message.as_ref()
+            .filter(|(_, until)| self.input.time < *until)
+            .map(|(message, _)| message.clone());
+        let hovered_entry = hovered_tool.and_then(|i| self.tools.get(i));
+        let mut status_text = message.or_else(|| hovered_entry.map(|entry| entry.tooltip.to_string()));
+        if let Some(tool) = current_entry.and_then(|entry| entry.tool.as_deref()) {
+            tool.draw_state(&self.user_state, &mut handle);
+            status_text = status_text.or_else(|| tool.status_text(&self.user_state));
+        }
 
-        if let Some(generic_state) = self.current_pressed.as_deref_mut() {
-            specify_state!(generic_state, specific_state, {
-                    specific_state.draw_state(&self.user_state, &mut handle, &self.thread);
-                });
+        if self.show_status_bar {
+            handle.draw_rectangle(0, status_bar_y, screen_width, STATUS_BAR_HEIGHT as i32, Color::LIGHTGRAY);
+            handle.draw_line(0, status_bar_y, screen_width, status_bar_y, Color::WHITE);
+            if let Some(text) = status_text {
+                handle.draw_text(&text, 10, status_bar_y + 5, 12, Color::BLACK);
+            }
+
+            if self.mouse_in_canvas {
+                let position = self.user_state.to_canvas(self.mouse_position).0;
+                let text = format!("{},{}", position.x.floor(), position.y.floor());
+                handle.draw_text(&text, screen_width - 150, status_bar_y + 5,
+                                 12, Color::BLACK);
+            }
+        }
+
+        if let Some(cursor) = tool_cursor {
+            cursor.draw(&self.user_state, &mut handle);
+        }
+
+        if let Some((i, since)) = self.hovered_tool {
+            let entry = self.tools.get(i).filter(|_| self.input.time - since >= TOOLTIP_DELAY);
+            if let Some(entry) = entry {
+                draw_tooltip(&mut handle, self.screen_size, self.mouse_position.0, &tooltip(entry, &self.keymap));
+            }
         }
 
-        if self.mouse_in_canvas {
-            let text = format!("{},{}", self.mouse_position.0.x, self.mouse_position.0.y);
-            handle.draw_text(&text, SCREEN_WIDTH - 150, SCREEN_HEIGHT - 15,
-                             12, Color::BLACK);
+        self.menu_bar.draw(&menus, self.screen_size.x, &mut handle);
+
+        if let Some(dialog) = &self.edit_colors {
+            dialog.draw(&mut handle);
+        }
+        if let Some((_, dialog)) = &self.dialog {
+            dialog.draw(&mut handle);
         }
     }
 
     pub fn run(&mut self) {
-        while !self.rl.window_should_close() {
+        while !self.rl.window_should_close() && !self.exit_requested {
             self.update();
             self.draw();
         }
+
+        if let Some(recorder) = self.recorder.take() {
+            if let Err(error) = recorder.finish() {
+                eprintln!("Could not finish recording the session: {}", error);
+            }
+        }
+        if let Some(path) = &self.settings_path {
+            if let Err(error) = self.settings().save(path) {
+                eprintln!("Could not save settings to {}: {}", path.display(), error);
+            }
+        }
     }
 }
\ No newline at end of file
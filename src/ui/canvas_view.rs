@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use raylib::prelude::*;
+use crate::pixel_buffer::PixelBuffer;
+use crate::raylib_extensions;
+
+/// The part of the canvas shown in the window, drawn from one texture per visible tile.
+/// Tiles scrolled out of view give their texture back, so the canvas can be larger than a texture.
+pub struct CanvasView {
+    /// Area of the window showing the canvas
+    bounds: Rectangle,
+    /// Canvas pixel shown at the top left corner of the bounds
+    offset: Vector2,
+    /// By tile index
+    textures: HashMap<usize, Texture2D>,
+}
+
+impl CanvasView {
+    pub fn new(bounds: Rectangle) -> Self {
+        CanvasView { bounds, offset: Vector2::zero(), textures: HashMap::new() }
+    }
+
+    /// Show the canvas in another area of the window, keeping the scroll position where it fits
+    pub fn set_bounds(&mut self, bounds: Rectangle, canvas: &PixelBuffer) {
+        self.bounds = bounds;
+        self.scroll(Vector2::zero(), canvas);
+    }
+
+    /// Back to the top left corner, with the textures uploaded again, for a new canvas
+    pub fn reset(&mut self) {
+        self.offset = Vector2::zero();
+        self.textures.clear();
+    }
+
+    /// Upload the textures again and keep the scroll position where it fits, for a canvas
+    /// replaced as a whole, such as by undo
+    pub fn refresh(&mut self, canvas: &PixelBuffer) {
+        self.textures.clear();
+        self.scroll(Vector2::zero(), canvas);
+    }
+
+    /// Window position of the canvas' top left corner, outside the bounds once scrolled
+    pub fn canvas_position(&self) -> Vector2 {
+        Vector2::new(self.bounds.x - self.offset.x, self.bounds.y - self.offset.y)
+    }
+
+    /// Canvas pixels inside the bounds
+    fn visible_pixels(&self) -> Rectangle {
+        Rectangle::new(self.offset.x, self.offset.y, self.bounds.width, self.bounds.height)
+    }
+
+    /// Part of the window covered by the canvas
+    pub fn visible_area(&self, canvas: &PixelBuffer) -> Rectangle {
+        let position = self.canvas_position();
+        let canvas_area = Rectangle::new(position.x, position.y, canvas.width() as f32, canvas.height() as f32);
+        self.bounds.get_collision_rec(&canvas_area).unwrap_or_default()
+    }
+
+    /// Scroll by the distance in pixels, without going past the edges of the canvas
+    pub fn scroll(&mut self, delta: Vector2, canvas: &PixelBuffer) {
+        let max_x = (canvas.width() as f32 - self.bounds.width).max(0f32);
+        let max_y = (canvas.height() as f32 - self.bounds.height).max(0f32);
+        self.offset = Vector2::new(
+            (self.offset.x + delta.x).round().clamp(0f32, max_x),
+            (self.offset.y + delta.y).round().clamp(0f32, max_y),
+        );
+    }
+
+    /// Upload the tiles that came into view, and the parts drawn on since the last update of
+    /// the ones already shown
+    pub fn update(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread, canvas: &mut PixelBuffer) {
+        let visible = canvas.tiles_in(self.visible_pixels());
+        self.textures.retain(|index, _| visible.contains(index));
+
+        let damage = canvas.take_damage();
+        for index in visible {
+            let tile = canvas.tile_area(index);
+            match self.textures.get_mut(&index) {
+                Some(texture) => {
+                    let Some(area) = damage.and_then(|damage| damage.get_collision_rec(&tile)) else {
+                        continue;
+                    };
+                    let in_tile = Rectangle { x: area.x - tile.x, y: area.y - tile.y, ..area };
+                    raylib_extensions::update_texture_rec(texture, in_tile, &canvas.area_to_rgba(area));
+                },
+                None => {
+                    let image = raylib_extensions::image_from_rgba(tile.width as i32, tile.height as i32,
+                                                                   &canvas.area_to_rgba(tile));
+                    match rl.load_texture_from_image(thread, &image) {
+                        Ok(texture) => {
+                            self.textures.insert(index, texture);
+                        },
+                        Err(error) => eprintln!("Could not upload canvas tile {}: {}", index, error),
+                    }
+                },
+            }
+        }
+    }
+
+    pub fn draw(&self, canvas: &PixelBuffer, handle: &mut RaylibDrawHandle) {
+        handle.draw_rectangle_rec(self.visible_area(canvas), Color::WHITE);
+
+        let visible = self.visible_pixels();
+        let position = self.canvas_position();
+        for (index, texture) in &self.textures {
+            let tile = canvas.tile_area(*index);
+            let Some(area) = tile.get_collision_rec(&visible) else {
+                continue;
+            };
+            let source = Rectangle::new(area.x - tile.x, area.y - tile.y, area.width, area.height);
+            handle.draw_texture_rec(texture, source, Vector2::new(position.x + area.x, position.y + area.y),
+                                    Color::WHITE);
+        }
+    }
+}
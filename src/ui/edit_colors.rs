@@ -0,0 +1,350 @@
+use raylib::prelude::*;
+use crate::color_space::{parse_hex, to_hex, Hsv};
+use crate::session::FrameInput;
+use crate::ui::widgets::{Dialog, DialogEvent};
+
+pub const CUSTOM_COLORS: usize = 16;
+
+const WIDTH: f32 = 470f32;
+const HEIGHT: f32 = 320f32;
+
+const FIELD_HEIGHT: f32 = 18f32;
+const FIELD_WIDTH: f32 = 60f32;
+
+const CUSTOM_SIZE: f32 = 20f32;
+const CUSTOM_COLUMNS: usize = 8;
+
+/// Number fields in tab order, with their label and largest value, before the hex field
+const NUMBER_FIELDS: [(&str, &str, i32); 7] = [
+    ("red", "R", 255), ("green", "G", 255), ("blue", "B", 255), ("alpha", "A", 255),
+    ("hue", "H", 359), ("saturation", "S", 100), ("value", "V", 100),
+];
+
+/// Text box of the field at the index, in two columns of four, relative to the dialog
+fn field_bounds(index: usize) -> Rectangle {
+    let (column, row) = (index / 4, index % 4);
+    Rectangle {
+        x: 260f32 + column as f32 * 108f32,
+        y: 84f32 + row as f32 * (FIELD_HEIGHT + 4f32),
+        width: if index == NUMBER_FIELDS.len() { 90f32 } else { FIELD_WIDTH },
+        height: FIELD_HEIGHT,
+    }
+}
+
+/// Label left of the text box of the field at the index
+fn field_label_bounds(index: usize) -> Rectangle {
+    let bounds = field_bounds(index);
+    Rectangle { x: bounds.x - 12f32, width: 10f32, ..bounds }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum Drag {
+    HueSaturation,
+    Value,
+}
+
+pub enum DialogResult {
+    Open,
+    Cancelled,
+    /// The edited color, and the palette entry it replaces
+    Accepted(Color, Option<usize>),
+}
+
+/// Modal dialog to pick an arbitrary color, by mouse or by typing its components
+pub struct EditColorsDialog {
+    dialog: Dialog,
+    hsv: Hsv,
+    alpha: u8,
+    /// Palette entry being edited, None only changes the primary color
+    palette_index: Option<usize>,
+    pub custom_colors: [Option<Color>; CUSTOM_COLORS],
+    /// Custom slot that "Add to Custom Colors" writes to next
+    next_custom: usize,
+    drag: Option<Drag>,
+}
+
+impl EditColorsDialog {
+    pub fn new(screen_size: Vector2, color: Color, palette_index: Option<usize>,
+               custom_colors: [Option<Color>; CUSTOM_COLORS]) -> Self {
+        let mut dialog = Dialog::new("Edit Colors", Vector2::new(WIDTH, HEIGHT), screen_size);
+        for (i, (name, label, max)) in NUMBER_FIELDS.into_iter().enumerate() {
+            dialog = dialog
+                .with_label(label, field_label_bounds(i))
+                .with_number_field(name, field_bounds(i), 0, 0, max);
+        }
+        let hex = NUMBER_FIELDS.len();
+        let dialog = dialog
+            .with_label("#", field_label_bounds(hex))
+            .with_text_field("hex", field_bounds(hex), "")
+            .with_slider("alpha-slider", Rectangle::new(12f32, 196f32, 208f32, 14f32), 0f32, 0f32, 255f32)
+            .with_label("Custom colors:", Rectangle::new(12f32, 216f32, 100f32, 10f32))
+            .with_button("add-custom", "Add to Custom Colors", Rectangle::new(240f32, 232f32, 150f32, 22f32))
+            .with_button("ok", "OK", Rectangle::new(290f32, 286f32, 80f32, 24f32))
+            .with_button("cancel", "Cancel", Rectangle::new(378f32, 286f32, 80f32, 24f32))
+            .with_default_button("ok")
+            .with_cancel_button("cancel");
+
+        let mut dialog = EditColorsDialog {
+            dialog,
+            hsv: Hsv::from_color(color),
+            alpha: color.a,
+            palette_index,
+            next_custom: custom_colors.iter().position(Option::is_none).unwrap_or(0),
+            custom_colors,
+            drag: None,
+        };
+        dialog.write_fields(None);
+        dialog
+    }
+
+    /// Center the dialog in the window again
+    pub fn resize(&mut self, screen_size: Vector2) {
+        self.dialog.resize(screen_size);
+    }
+
+    pub fn color(&self) -> Color {
+        self.hsv.to_color(self.alpha)
+    }
+
+    /// Keep the hue and saturation when the new color is gray or black, where HSV loses them
+    fn set_color(&mut self, color: Color) {
+        let hsv = Hsv::from_color(color);
+        self.hsv = Hsv {
+            hue: if hsv.saturation == 0f32 || hsv.value == 0f32 { self.hsv.hue } else { hsv.hue },
+            saturation: if hsv.value == 0f32 { self.hsv.saturation } else { hsv.saturation },
+            value: hsv.value,
+        };
+        self.alpha = color.a;
+    }
+
+    fn relative(&self, rectangle: Rectangle) -> Rectangle {
+        let position = self.dialog.position();
+        Rectangle {
+            x: position.x + rectangle.x,
+            y: position.y + rectangle.y,
+            ..rectangle
+        }
+    }
+
+    fn hue_saturation_bounds(&self) -> Rectangle {
+        self.relative(Rectangle { x: 12f32, y: 32f32, width: 180f32, height: 150f32 })
+    }
+
+    fn value_bounds(&self) -> Rectangle {
+        self.relative(Rectangle { x: 204f32, y: 32f32, width: 16f32, height: 150f32 })
+    }
+
+    /// Strip of the color from transparent to opaque, above the alpha slider
+    fn alpha_bounds(&self) -> Rectangle {
+        self.relative(Rectangle { x: 12f32, y: 188f32, width: 208f32, height: 8f32 })
+    }
+
+    fn preview_bounds(&self) -> Rectangle {
+        self.relative(Rectangle { x: 240f32, y: 32f32, width: 80f32, height: 40f32 })
+    }
+
+    fn custom_bounds(&self, i: usize) -> Rectangle {
+        self.relative(Rectangle {
+            x: 12f32 + (i % CUSTOM_COLUMNS) as f32 * (CUSTOM_SIZE + 6f32),
+            y: 232f32 + (i / CUSTOM_COLUMNS) as f32 * (CUSTOM_SIZE + 6f32),
+            width: CUSTOM_SIZE,
+            height: CUSTOM_SIZE,
+        })
+    }
+
+    /// What the number field shows for the color
+    fn field_value(&self, name: &str) -> i32 {
+        let color = self.color();
+        match name {
+            "red" => color.r as i32,
+            "green" => color.g as i32,
+            "blue" => color.b as i32,
+            "alpha" => self.alpha as i32,
+            "hue" => self.hsv.hue.round() as i32 % 360,
+            "saturation" => (self.hsv.saturation * 100f32).round() as i32,
+            "value" => (self.hsv.value * 100f32).round() as i32,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Apply a number field, kept between its bounds by the dialog
+    fn apply_field(&mut self, name: &str, number: i32) {
+        let mut color = self.color();
+        match name {
+            "red" => color.r = number as u8,
+            "green" => color.g = number as u8,
+            "blue" => color.b = number as u8,
+            "alpha" => self.alpha = number as u8,
+            "hue" => self.hsv.hue = number as f32,
+            "saturation" => self.hsv.saturation = number as f32 / 100f32,
+            "value" => self.hsv.value = number as f32 / 100f32,
+            _ => unreachable!(),
+        }
+
+        if matches!(name, "red" | "green" | "blue") {
+            self.set_color(color);
+        }
+    }
+
+    /// Apply the control the user changed, invalid hex is ignored. Returns the name of the control.
+    fn read_fields(&mut self) -> Option<&'static str> {
+        for (name, _, _) in NUMBER_FIELDS {
+            let number = self.dialog.number(name).unwrap();
+            if number != self.field_value(name) {
+                self.apply_field(name, number);
+                return Some(name);
+            }
+        }
+
+        let hex = self.dialog.text("hex").and_then(parse_hex);
+        if let Some(color) = hex.filter(|color| *color != self.color()) {
+            self.set_color(color);
+            return Some("hex");
+        }
+
+        let alpha = self.dialog.slider("alpha-slider").unwrap().round() as u8;
+        if alpha != self.alpha {
+            self.alpha = alpha;
+            return Some("alpha-slider");
+        }
+        None
+    }
+
+    /// Show the color in every control but the one named
+    fn write_fields(&mut self, skip: Option<&str>) {
+        for (name, _, _) in NUMBER_FIELDS {
+            if skip != Some(name) {
+                let number = self.field_value(name);
+                self.dialog.set_number(name, number);
+            }
+        }
+        if skip != Some("hex") {
+            let hex = to_hex(self.color());
+            self.dialog.set_text("hex", &hex);
+        }
+        if skip != Some("alpha-slider") {
+            self.dialog.set_slider("alpha-slider", self.alpha as f32);
+        }
+    }
+
+    pub fn update(&mut self, input: &FrameInput) -> DialogResult {
+        let before = (self.hsv, self.alpha);
+        let event = self.dialog.update(input);
+        let changed_control = self.read_fields();
+
+        let mouse_position = input.mouse_position;
+        if input.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+            self.drag = if self.hue_saturation_bounds().check_collision_point_rec(mouse_position) {
+                Some(Drag::HueSaturation)
+            } else if self.value_bounds().check_collision_point_rec(mouse_position) {
+                Some(Drag::Value)
+            } else {
+                None
+            };
+        }
+
+        if let Some(drag) = self.drag {
+            let fraction = |position: f32, start: f32, size: f32| ((position - start) / size).clamp(0f32, 1f32);
+            match drag {
+                Drag::HueSaturation => {
+                    let bounds = self.hue_saturation_bounds();
+                    let x = fraction(mouse_position.x, bounds.x, bounds.width - 1f32);
+                    let y = fraction(mouse_position.y, bounds.y, bounds.height - 1f32);
+                    // Hue left to right, saturation top to bottom
+                    self.hsv.hue = x * 359f32;
+                    self.hsv.saturation = 1f32 - y;
+                }
+                Drag::Value => {
+                    let bounds = self.value_bounds();
+                    self.hsv.value = 1f32 - fraction(mouse_position.y, bounds.y, bounds.height - 1f32);
+                }
+            }
+        }
+
+        if input.is_mouse_button_released(MouseButton::MOUSE_BUTTON_LEFT) {
+            self.drag = None;
+            if let Some(i) = (0..CUSTOM_COLORS)
+                .find(|i| self.custom_bounds(*i).check_collision_point_rec(mouse_position)) {
+                if let Some(color) = self.custom_colors[i] {
+                    self.set_color(color);
+                }
+                self.next_custom = i;
+            }
+        }
+
+        // A field being typed in keeps its text, unless the color changed some other way
+        let changed = (self.hsv, self.alpha) != before;
+        self.write_fields(if changed { changed_control } else { self.dialog.focused() });
+
+        match event {
+            DialogEvent::Cancelled => DialogResult::Cancelled,
+            DialogEvent::Clicked("ok") => DialogResult::Accepted(self.color(), self.palette_index),
+            DialogEvent::Clicked("add-custom") => {
+                self.custom_colors[self.next_custom] = Some(self.color());
+                self.next_custom = (self.next_custom + 1) % CUSTOM_COLORS;
+                DialogResult::Open
+            },
+            _ => DialogResult::Open,
+        }
+    }
+
+    pub fn draw(&self, handle: &mut RaylibDrawHandle) {
+        self.dialog.draw(handle);
+
+        // Hue and saturation field, one column of pixels per hue
+        let field = self.hue_saturation_bounds();
+        for x in 0..field.width as i32 {
+            let hue = x as f32 / (field.width - 1f32) * 359f32;
+            let top = Hsv { hue, saturation: 1f32, value: 1f32 }.to_color(255);
+            let bottom = Hsv { hue, saturation: 0f32, value: 1f32 }.to_color(255);
+            handle.draw_rectangle_gradient_v(
+                field.x as i32 + x, field.y as i32, 1, field.height as i32, top, bottom
+            );
+        }
+        handle.draw_rectangle_lines_ex(field, 1f32, Color::BLACK);
+        let cross = Vector2 {
+            x: field.x + self.hsv.hue / 359f32 * (field.width - 1f32),
+            y: field.y + (1f32 - self.hsv.saturation) * (field.height - 1f32),
+        };
+        handle.draw_circle_lines(cross.x as i32, cross.y as i32, 4f32, Color::BLACK);
+
+        // Value slider for the current hue and saturation
+        let slider = self.value_bounds();
+        let top = Hsv { value: 1f32, ..self.hsv }.to_color(255);
+        handle.draw_rectangle_gradient_v(
+            slider.x as i32, slider.y as i32, slider.width as i32, slider.height as i32, top, Color::BLACK
+        );
+        handle.draw_rectangle_lines_ex(slider, 1f32, Color::BLACK);
+        let value_y = slider.y + (1f32 - self.hsv.value) * (slider.height - 1f32);
+        handle.draw_triangle(
+            Vector2 { x: slider.x + slider.width + 1f32, y: value_y },
+            Vector2 { x: slider.x + slider.width + 7f32, y: value_y + 4f32 },
+            Vector2 { x: slider.x + slider.width + 7f32, y: value_y - 4f32 },
+            Color::BLACK
+        );
+
+        // Alpha strip over white, as under the canvas
+        let strip = self.alpha_bounds();
+        handle.draw_rectangle_rec(strip, Color::WHITE);
+        handle.draw_rectangle_gradient_h(
+            strip.x as i32, strip.y as i32, strip.width as i32, strip.height as i32,
+            self.hsv.to_color(0), self.hsv.to_color(255)
+        );
+        handle.draw_rectangle_lines_ex(strip, 1f32, Color::BLACK);
+
+        let preview = self.preview_bounds();
+        handle.draw_rectangle_rec(preview, Color::WHITE);
+        handle.draw_rectangle_rec(preview, self.color());
+        handle.draw_rectangle_lines_ex(preview, 1f32, Color::BLACK);
+
+        for (i, color) in self.custom_colors.iter().enumerate() {
+            let bounds = self.custom_bounds(i);
+            handle.draw_rectangle_rec(bounds, Color::WHITE);
+            if let Some(color) = color {
+                handle.draw_rectangle_rec(bounds, *color);
+            }
+            let outline = if i == self.next_custom { Color::BLUE } else { Color::DARKGRAY };
+            handle.draw_rectangle_lines_ex(bounds, 1f32, outline);
+        }
+    }
+}
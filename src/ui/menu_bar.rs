@@ -0,0 +1,285 @@
+use raylib::prelude::*;
+use crate::bitmap_font;
+use crate::keymap;
+use crate::session::FrameInput;
+
+pub const MENU_BAR_HEIGHT: f32 = 20f32;
+
+const FONT_SIZE: i32 = 10;
+const TITLE_PADDING: f32 = 8f32;
+const ITEM_HEIGHT: f32 = 20f32;
+const SEPARATOR_HEIGHT: f32 = 8f32;
+/// Left of the labels, where the check marks go
+const CHECK_WIDTH: f32 = 18f32;
+/// Between the longest label and the accelerators
+const ACCELERATOR_GAP: f32 = 24f32;
+
+/// Entry of a dropdown, built again every frame so its state is always current
+pub struct MenuItem<C> {
+    pub label: String,
+    /// Keys that run the command without opening the menu, shown right of the label
+    pub accelerator: Option<String>,
+    pub enabled: bool,
+    /// Drawn with a check mark, for options that are on
+    pub checked: bool,
+    pub command: C,
+}
+
+impl<C> MenuItem<C> {
+    pub fn new(label: impl Into<String>, command: C) -> Self {
+        MenuItem { label: label.into(), accelerator: None, enabled: true, checked: false, command }
+    }
+
+    pub fn with_accelerator(mut self, accelerator: Option<impl ToString>) -> Self {
+        self.accelerator = accelerator.map(|accelerator| accelerator.to_string());
+        self
+    }
+
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    pub fn with_checked(mut self, checked: bool) -> Self {
+        self.checked = checked;
+        self
+    }
+}
+
+pub enum MenuEntry<C> {
+    Item(MenuItem<C>),
+    Separator,
+}
+
+impl<C> MenuEntry<C> {
+    fn height(&self) -> f32 {
+        match self {
+            MenuEntry::Item(_) => ITEM_HEIGHT,
+            MenuEntry::Separator => SEPARATOR_HEIGHT,
+        }
+    }
+
+    fn enabled_item(&self) -> Option<&MenuItem<C>> {
+        match self {
+            MenuEntry::Item(item) if item.enabled => Some(item),
+            _ => None,
+        }
+    }
+}
+
+/// Title in the bar and its dropdown. Alt and the title's first letter open it from the keyboard.
+pub struct Menu<C> {
+    pub title: &'static str,
+    pub entries: Vec<MenuEntry<C>>,
+}
+
+pub enum MenuResult<C> {
+    /// No menu is open, the input is for the rest of the window
+    Closed,
+    /// A menu is open or just closed, the rest of the window ignores the input
+    Open,
+    Chosen(C),
+}
+
+fn text_width(text: &str) -> f32 {
+    bitmap_font::measure_text(text, FONT_SIZE) as f32
+}
+
+/// Menu titles along the top of the window, with at most one dropdown open
+#[derive(Default)]
+pub struct MenuBar {
+    /// Index of the open menu
+    open: Option<usize>,
+    /// Entry under the mouse or picked with the arrow keys
+    highlighted: Option<usize>,
+    /// The click that closed a menu is held until released, so it does not reach the canvas
+    swallow_click: bool,
+}
+
+impl MenuBar {
+    fn title_bounds<C>(menus: &[Menu<C>], index: usize) -> Rectangle {
+        let x: f32 = menus[..index].iter().map(|menu| text_width(menu.title) + TITLE_PADDING * 2f32).sum();
+        Rectangle::new(x, 0f32, text_width(menus[index].title) + TITLE_PADDING * 2f32, MENU_BAR_HEIGHT)
+    }
+
+    fn dropdown_bounds<C>(menus: &[Menu<C>], index: usize) -> Rectangle {
+        let entries = &menus[index].entries;
+        let items = || entries.iter().filter_map(|entry| match entry {
+            MenuEntry::Item(item) => Some(item),
+            MenuEntry::Separator => None,
+        });
+        let label_width = items().map(|item| text_width(&item.label)).fold(0f32, f32::max);
+        let accelerator_width = items()
+            .filter_map(|item| item.accelerator.as_deref())
+            .map(|accelerator| text_width(accelerator) + ACCELERATOR_GAP)
+            .fold(0f32, f32::max);
+        let title = MenuBar::title_bounds(menus, index);
+        Rectangle::new(
+            title.x,
+            MENU_BAR_HEIGHT,
+            CHECK_WIDTH + label_width + accelerator_width + TITLE_PADDING * 2f32,
+            entries.iter().map(MenuEntry::height).sum::<f32>() + 2f32,
+        )
+    }
+
+    fn entry_bounds<C>(menus: &[Menu<C>], index: usize, entry: usize) -> Rectangle {
+        let dropdown = MenuBar::dropdown_bounds(menus, index);
+        let entries = &menus[index].entries;
+        let y: f32 = entries[..entry].iter().map(MenuEntry::height).sum();
+        Rectangle::new(dropdown.x + 1f32, dropdown.y + 1f32 + y, dropdown.width - 2f32, entries[entry].height())
+    }
+
+    fn open_menu(&mut self, index: Option<usize>) {
+        self.open = index;
+        self.highlighted = None;
+    }
+
+    /// Next enabled item from the highlighted one, going down or up and wrapping around
+    fn step<C>(&mut self, menu: &Menu<C>, down: bool) {
+        let count = menu.entries.len();
+        if count == 0 {
+            return;
+        }
+        let start = self.highlighted.unwrap_or(if down { count - 1 } else { 0 });
+        self.highlighted = (1..=count)
+            .map(|i| if down { (start + i) % count } else { (start + count - i) % count })
+            .find(|i| menu.entries[*i].enabled_item().is_some());
+    }
+
+    /// Open menus with the mouse or Alt and a letter, move with the arrow keys, and choose with
+    /// a click or Enter
+    pub fn update<C: Clone>(&mut self, menus: &[Menu<C>], input: &FrameInput) -> MenuResult<C> {
+        if self.swallow_click {
+            self.swallow_click = input.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT);
+            return MenuResult::Open;
+        }
+
+        if input.modifiers.alt {
+            let letter = menus.iter().position(|menu| {
+                menu.title.get(..1)
+                    .and_then(keymap::key_named)
+                    .is_some_and(|key| input.is_key_pressed(key))
+            });
+            if letter.is_some() {
+                self.open_menu(letter);
+                return MenuResult::Open;
+            }
+        }
+
+        let mouse = input.mouse_position;
+        let pressed = input.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT);
+        let hovered_title = (0..menus.len()).find(|i| MenuBar::title_bounds(menus, *i).check_collision_point_rec(mouse));
+        let Some(open) = self.open.filter(|open| *open < menus.len()) else {
+            self.open = None;
+            if pressed && hovered_title.is_some() {
+                self.open_menu(hovered_title);
+                return MenuResult::Open;
+            }
+            return MenuResult::Closed;
+        };
+
+        if hovered_title.is_some() && hovered_title != Some(open) {
+            self.open_menu(hovered_title);
+            return MenuResult::Open;
+        }
+        let menu = &menus[open];
+
+        if input.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+            self.open_menu(None);
+            return MenuResult::Open;
+        }
+        if input.is_key_pressed(KeyboardKey::KEY_DOWN) {
+            self.step(menu, true);
+        }
+        if input.is_key_pressed(KeyboardKey::KEY_UP) {
+            self.step(menu, false);
+        }
+        if input.is_key_pressed(KeyboardKey::KEY_LEFT) || input.is_key_pressed(KeyboardKey::KEY_RIGHT) {
+            let offset = if input.is_key_pressed(KeyboardKey::KEY_LEFT) { menus.len() - 1 } else { 1 };
+            self.open_menu(Some((open + offset) % menus.len()));
+            return MenuResult::Open;
+        }
+
+        let hovered_entry = (0..menu.entries.len())
+            .find(|i| MenuBar::entry_bounds(menus, open, *i).check_collision_point_rec(mouse));
+        if hovered_entry.is_some() {
+            self.highlighted = hovered_entry.filter(|i| menu.entries[*i].enabled_item().is_some());
+        }
+
+        let clicked = hovered_entry.is_some() && input.is_mouse_button_released(MouseButton::MOUSE_BUTTON_LEFT);
+        if clicked || input.is_key_pressed(KeyboardKey::KEY_ENTER) {
+            if let Some(item) = self.highlighted.and_then(|i| menu.entries[i].enabled_item()) {
+                self.open_menu(None);
+                return MenuResult::Chosen(item.command.clone());
+            }
+        }
+
+        let outside = hovered_title.is_none() && hovered_entry.is_none()
+            && !MenuBar::dropdown_bounds(menus, open).check_collision_point_rec(mouse);
+        if pressed && (outside || hovered_title == Some(open)) {
+            self.open_menu(None);
+            self.swallow_click = true;
+        }
+        MenuResult::Open
+    }
+
+    pub fn draw<C>(&self, menus: &[Menu<C>], screen_width: f32, handle: &mut RaylibDrawHandle) {
+        handle.draw_rectangle_rec(Rectangle::new(0f32, 0f32, screen_width, MENU_BAR_HEIGHT), Color::LIGHTGRAY);
+        handle.draw_line(0, MENU_BAR_HEIGHT as i32 - 1, screen_width as i32, MENU_BAR_HEIGHT as i32 - 1,
+                         Color::DARKGRAY);
+
+        for (i, menu) in menus.iter().enumerate() {
+            let bounds = MenuBar::title_bounds(menus, i);
+            let color = if self.open == Some(i) {
+                handle.draw_rectangle_rec(bounds, Color::DARKBLUE);
+                Color::WHITE
+            } else {
+                Color::BLACK
+            };
+            handle.draw_text(menu.title, (bounds.x + TITLE_PADDING) as i32, (bounds.y + 5f32) as i32, FONT_SIZE,
+                             color);
+        }
+
+        let Some(open) = self.open.filter(|open| *open < menus.len()) else {
+            return;
+        };
+        let dropdown = MenuBar::dropdown_bounds(menus, open);
+        handle.draw_rectangle_rec(dropdown, Color::LIGHTGRAY);
+        handle.draw_rectangle_lines_ex(dropdown, 1f32, Color::BLACK);
+
+        for (i, entry) in menus[open].entries.iter().enumerate() {
+            let bounds = MenuBar::entry_bounds(menus, open, i);
+            let item = match entry {
+                MenuEntry::Item(item) => item,
+                MenuEntry::Separator => {
+                    let y = (bounds.y + bounds.height / 2f32) as i32;
+                    handle.draw_line(bounds.x as i32 + 2, y, (bounds.x + bounds.width) as i32 - 2, y, Color::GRAY);
+                    handle.draw_line(bounds.x as i32 + 2, y + 1, (bounds.x + bounds.width) as i32 - 2, y + 1,
+                                     Color::WHITE);
+                    continue;
+                },
+            };
+
+            let color = if !item.enabled {
+                Color::GRAY
+            } else if self.highlighted == Some(i) {
+                handle.draw_rectangle_rec(bounds, Color::DARKBLUE);
+                Color::WHITE
+            } else {
+                Color::BLACK
+            };
+            let text_y = (bounds.y + 5f32) as i32;
+            if item.checked {
+                let x = bounds.x + 6f32;
+                let y = bounds.y + bounds.height / 2f32;
+                handle.draw_line_ex(Vector2::new(x, y), Vector2::new(x + 3f32, y + 3f32), 2f32, color);
+                handle.draw_line_ex(Vector2::new(x + 3f32, y + 3f32), Vector2::new(x + 9f32, y - 4f32), 2f32, color);
+            }
+            handle.draw_text(&item.label, (bounds.x + CHECK_WIDTH) as i32, text_y, FONT_SIZE, color);
+            if let Some(accelerator) = &item.accelerator {
+                let x = bounds.x + bounds.width - TITLE_PADDING - text_width(accelerator);
+                handle.draw_text(accelerator, x as i32, text_y, FONT_SIZE, color);
+            }
+        }
+    }
+}
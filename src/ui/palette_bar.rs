@@ -0,0 +1,160 @@
+use std::path::{Path, PathBuf};
+use raylib::prelude::*;
+use crate::palette::{self, PaletteError, DEFAULT_PALETTE};
+use crate::session::FrameInput;
+
+const SWATCH_SIZE: f32 = 16f32;
+/// Rows of swatches, more when a large palette does not fit in the width
+const MIN_ROWS: usize = 2;
+/// Space taken by the primary and secondary color indicator, left of the swatches
+const CURRENT_COLORS_SIZE: f32 = 2f32 * SWATCH_SIZE;
+
+/// Seconds between two clicks on the same swatch to count as a double click
+const DOUBLE_CLICK_TIME: f64 = 0.4;
+
+pub enum PaletteEvent {
+    /// Open the color editor for a palette entry, or for the primary color when None
+    EditColor(Option<usize>),
+}
+
+/// Bar of color swatches, left click picks the primary color and right click the secondary
+pub struct PaletteBar {
+    position: Vector2,
+    /// Room for the bar, which wraps the swatches to stay inside
+    max_width: f32,
+    pub colors: Vec<Color>,
+    /// File the palette was last imported from or exported to
+    pub path: Option<PathBuf>,
+    /// Swatch and time of the last left click
+    last_click: Option<(usize, f64)>,
+}
+
+impl PaletteBar {
+    pub fn new(position: Vector2) -> Self {
+        PaletteBar {
+            position,
+            max_width: f32::INFINITY,
+            colors: DEFAULT_PALETTE.to_vec(),
+            path: None,
+            last_click: None,
+        }
+    }
+
+    pub fn set_position(&mut self, position: Vector2) {
+        self.position = position;
+    }
+
+    /// The swatches take two rows, or wrap into more to keep the bar inside the width
+    pub fn set_max_width(&mut self, max_width: f32) {
+        self.max_width = max_width;
+    }
+
+    /// Replace the colors with the ones in a palette file, the format comes from the extension
+    pub fn import(&mut self, path: &Path) -> Result<(), PaletteError> {
+        self.colors = palette::load(path)?;
+        self.path = Some(path.to_path_buf());
+        self.last_click = None;
+        Ok(())
+    }
+
+    pub fn export(&mut self, path: &Path) -> Result<(), PaletteError> {
+        palette::save(path, &self.colors)?;
+        self.path = Some(path.to_path_buf());
+        Ok(())
+    }
+
+    fn columns(&self) -> usize {
+        let fitting = ((self.max_width - CURRENT_COLORS_SIZE - 4f32) / SWATCH_SIZE).floor().max(1f32) as usize;
+        self.colors.len().div_ceil(MIN_ROWS).clamp(1, fitting)
+    }
+
+    /// Depends on the colors, so lay out again when they are replaced
+    pub fn height(&self) -> f32 {
+        self.colors.len().div_ceil(self.columns()).max(MIN_ROWS) as f32 * SWATCH_SIZE
+    }
+
+    pub fn width(&self) -> f32 {
+        CURRENT_COLORS_SIZE + 4f32 + self.columns() as f32 * SWATCH_SIZE
+    }
+
+    fn swatch_bounds(&self, i: usize) -> Rectangle {
+        let columns = self.columns();
+        Rectangle {
+            x: self.position.x + CURRENT_COLORS_SIZE + 4f32 + (i % columns) as f32 * SWATCH_SIZE,
+            y: self.position.y + (i / columns) as f32 * SWATCH_SIZE,
+            width: SWATCH_SIZE,
+            height: SWATCH_SIZE,
+        }
+    }
+
+    fn current_colors_bounds(&self) -> Rectangle {
+        Rectangle {
+            x: self.position.x,
+            y: self.position.y,
+            width: CURRENT_COLORS_SIZE,
+            height: CURRENT_COLORS_SIZE,
+        }
+    }
+
+    pub fn update(&mut self, input: &FrameInput, current_colors: &mut [Color; 2]) -> Option<PaletteEvent> {
+        let mouse_position = input.mouse_position;
+        let left = input.is_mouse_button_released(MouseButton::MOUSE_BUTTON_LEFT);
+        let right = input.is_mouse_button_released(MouseButton::MOUSE_BUTTON_RIGHT);
+
+        if left && self.current_colors_bounds().check_collision_point_rec(mouse_position) {
+            return Some(PaletteEvent::EditColor(None));
+        }
+
+        let index = (0..self.colors.len())
+            .find(|i| self.swatch_bounds(*i).check_collision_point_rec(mouse_position))?;
+
+        if right {
+            current_colors[1] = self.colors[index];
+        }
+
+        if left {
+            current_colors[0] = self.colors[index];
+
+            let now = input.time;
+            let double_click = self.last_click
+                .is_some_and(|(last, time)| last == index && now - time < DOUBLE_CLICK_TIME);
+            if double_click {
+                self.last_click = None;
+                return Some(PaletteEvent::EditColor(Some(index)));
+            }
+            self.last_click = Some((index, now));
+        }
+
+        None
+    }
+
+    pub fn draw(&self, handle: &mut RaylibDrawHandle, current_colors: [Color; 2]) {
+        // Secondary color behind the primary, offset to the bottom right
+        let bounds = self.current_colors_bounds();
+        handle.draw_rectangle_rec(bounds, Color::LIGHTGRAY);
+        let secondary = Rectangle {
+            x: bounds.x + bounds.width / 3f32,
+            y: bounds.y + bounds.height / 3f32,
+            width: bounds.width / 2f32,
+            height: bounds.height / 2f32,
+        };
+        let primary = Rectangle {
+            x: bounds.x + bounds.width / 6f32,
+            y: bounds.y + bounds.height / 6f32,
+            width: bounds.width / 2f32,
+            height: bounds.height / 2f32,
+        };
+        for (rectangle, color) in [(secondary, current_colors[1]), (primary, current_colors[0])] {
+            handle.draw_rectangle_rec(rectangle, Color::WHITE);
+            handle.draw_rectangle_rec(rectangle, color);
+            handle.draw_rectangle_lines_ex(rectangle, 1f32, Color::BLACK);
+        }
+
+        for (i, color) in self.colors.iter().enumerate() {
+            let swatch = self.swatch_bounds(i);
+            handle.draw_rectangle_rec(swatch, Color::WHITE);
+            handle.draw_rectangle_rec(swatch, *color);
+            handle.draw_rectangle_lines_ex(swatch, 1f32, Color::DARKGRAY);
+        }
+    }
+}
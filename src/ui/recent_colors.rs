@@ -0,0 +1,80 @@
+use raylib::prelude::*;
+use crate::session::FrameInput;
+use crate::settings::RECENT_COLORS_LIMIT;
+
+const SWATCH_SIZE: f32 = 16f32;
+const COLUMNS: usize = 8;
+
+/// Strip with the last colors put onto the canvas, most recent first
+pub struct RecentColors {
+    position: Vector2,
+    colors: Vec<Color>,
+}
+
+impl RecentColors {
+    pub fn new(position: Vector2) -> Self {
+        RecentColors { position, colors: Vec::new() }
+    }
+
+    /// Most recent first
+    pub fn colors(&self) -> &[Color] {
+        &self.colors
+    }
+
+    pub fn set_colors(&mut self, colors: &[Color]) {
+        self.colors = colors.iter().copied().take(RECENT_COLORS_LIMIT).collect();
+    }
+
+    pub const fn width() -> f32 {
+        COLUMNS as f32 * SWATCH_SIZE
+    }
+
+    pub fn set_position(&mut self, position: Vector2) {
+        self.position = position;
+    }
+
+    pub fn record(&mut self, color: Color) {
+        if self.colors.first() == Some(&color) {
+            return;
+        }
+
+        self.colors.retain(|recent| *recent != color);
+        self.colors.insert(0, color);
+        self.colors.truncate(RECENT_COLORS_LIMIT);
+    }
+
+    fn swatch_bounds(&self, i: usize) -> Rectangle {
+        Rectangle {
+            x: self.position.x + (i % COLUMNS) as f32 * SWATCH_SIZE,
+            y: self.position.y + (i / COLUMNS) as f32 * SWATCH_SIZE,
+            width: SWATCH_SIZE,
+            height: SWATCH_SIZE,
+        }
+    }
+
+    /// Left click picks the primary color and right click the secondary
+    pub fn update(&self, input: &FrameInput, current_colors: &mut [Color; 2]) {
+        let Some(index) = (0..self.colors.len())
+            .find(|i| self.swatch_bounds(*i).check_collision_point_rec(input.mouse_position)) else {
+            return;
+        };
+
+        if input.is_mouse_button_released(MouseButton::MOUSE_BUTTON_LEFT) {
+            current_colors[0] = self.colors[index];
+        }
+        if input.is_mouse_button_released(MouseButton::MOUSE_BUTTON_RIGHT) {
+            current_colors[1] = self.colors[index];
+        }
+    }
+
+    pub fn draw(&self, handle: &mut RaylibDrawHandle) {
+        for i in 0..RECENT_COLORS_LIMIT {
+            let swatch = self.swatch_bounds(i);
+            handle.draw_rectangle_rec(swatch, Color::WHITE);
+            if let Some(color) = self.colors.get(i) {
+                handle.draw_rectangle_rec(swatch, *color);
+            }
+            handle.draw_rectangle_lines_ex(swatch, 1f32, Color::DARKGRAY);
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use raylib::prelude::*;
+use crate::actions::color_picker::SampleSize;
+use crate::actions::fill_mode::FillMode;
+use crate::actions::rounded_rectangle::{CORNER_RADIUS_DEFAULT, CORNER_RADIUS_MAX};
+use crate::actions::tool_registry::ToolOption;
+use crate::actions::spray::{SpraySize, SPRAY_DENSITY_DEFAULT, SPRAY_DENSITY_MAX, SPRAY_DENSITY_MIN};
+use crate::session::FrameInput;
+use crate::user_state::LINE_WIDTHS;
+
+const ROW_HEIGHT: f32 = 24f32;
+const ROWS: usize = 9;
+
+/// Spray density change per mouse wheel step over the options box
+const SPRAY_DENSITY_STEP: f32 = 60f32;
+
+const ANTIALIASING_LABELS: [&str; 2] = ["Aliased", "Smooth"];
+
+/// Rows shown for the option
+fn rows(option: ToolOption) -> usize {
+    match option {
+        ToolOption::SpraySize => SpraySize::ALL.len(),
+        ToolOption::SampleSize => SampleSize::ALL.len(),
+        ToolOption::Antialiasing => ANTIALIASING_LABELS.len(),
+        ToolOption::LineWidth => LINE_WIDTHS.len(),
+        ToolOption::FillMode => FillMode::ALL.len(),
+        ToolOption::CornerRadius => 1,
+    }
+}
+
+/// Every row of the tool's options, as its option and index inside the option
+fn option_rows(options: &[ToolOption]) -> impl Iterator<Item = (ToolOption, usize)> + '_ {
+    options.iter().flat_map(|option| (0..rows(*option)).map(move |i| (*option, i)))
+}
+
+/// Box under the tool buttons that shows the options of the current tool
+pub struct ToolOptions {
+    bounds: Rectangle,
+    pub spray_size: SpraySize,
+    /// Spray particles per second
+    pub spray_density: f32,
+    pub antialiasing: bool,
+    pub line_width: i32,
+    pub fill_mode: FillMode,
+    pub corner_radius: i32,
+    pub sample_size: SampleSize,
+}
+
+impl ToolOptions {
+    pub fn new(position: Vector2, width: f32) -> Self {
+        ToolOptions {
+            bounds: Rectangle {
+                x: position.x,
+                y: position.y,
+                width,
+                height: ROWS as f32 * ROW_HEIGHT,
+            },
+            spray_size: SpraySize::SizeOne,
+            spray_density: SPRAY_DENSITY_DEFAULT,
+            antialiasing: false,
+            line_width: LINE_WIDTHS[0],
+            fill_mode: FillMode::Outline,
+            corner_radius: CORNER_RADIUS_DEFAULT,
+            sample_size: SampleSize::One,
+        }
+    }
+
+    pub fn set_position(&mut self, position: Vector2) {
+        self.bounds.x = position.x;
+        self.bounds.y = position.y;
+    }
+
+    fn row_bounds(&self, i: usize) -> Rectangle {
+        Rectangle {
+            x: self.bounds.x,
+            y: self.bounds.y + i as f32 * ROW_HEIGHT,
+            width: self.bounds.width,
+            height: ROW_HEIGHT,
+        }
+    }
+
+    fn is_selected(&self, option: ToolOption, i: usize) -> bool {
+        match option {
+            ToolOption::SpraySize => SpraySize::ALL[i] == self.spray_size,
+            ToolOption::SampleSize => SampleSize::ALL[i] == self.sample_size,
+            ToolOption::Antialiasing => (i == 1) == self.antialiasing,
+            ToolOption::LineWidth => LINE_WIDTHS[i] == self.line_width,
+            ToolOption::FillMode => FillMode::ALL[i] == self.fill_mode,
+            ToolOption::CornerRadius => false,
+        }
+    }
+
+    fn select(&mut self, option: ToolOption, i: usize) {
+        match option {
+            ToolOption::SpraySize => self.spray_size = SpraySize::ALL[i],
+            ToolOption::SampleSize => self.sample_size = SampleSize::ALL[i],
+            ToolOption::Antialiasing => self.antialiasing = i == 1,
+            ToolOption::LineWidth => self.line_width = LINE_WIDTHS[i],
+            ToolOption::FillMode => self.fill_mode = FillMode::ALL[i],
+            ToolOption::CornerRadius => {}
+        }
+    }
+
+    /// Handle clicks and the mouse wheel over the box
+    pub fn update(&mut self, options: &[ToolOption], input: &FrameInput) {
+        let mouse_position = input.mouse_position;
+        if !self.bounds.check_collision_point_rec(mouse_position) {
+            return;
+        }
+
+        if input.is_mouse_button_released(MouseButton::MOUSE_BUTTON_LEFT) {
+            let row = ((mouse_position.y - self.bounds.y) / ROW_HEIGHT) as usize;
+            if let Some((option, i)) = option_rows(options).nth(row) {
+                self.select(option, i);
+            }
+        }
+
+        let wheel = input.wheel;
+        if wheel != 0f32 {
+            for option in options {
+                match option {
+                    ToolOption::SpraySize => {
+                        self.spray_density = (self.spray_density + wheel * SPRAY_DENSITY_STEP)
+                            .clamp(SPRAY_DENSITY_MIN, SPRAY_DENSITY_MAX);
+                    }
+                    ToolOption::CornerRadius => {
+                        self.corner_radius = (self.corner_radius + wheel as i32)
+                            .clamp(0, CORNER_RADIUS_MAX);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    pub fn draw(&self, options: &[ToolOption], handle: &mut RaylibDrawHandle) {
+        handle.draw_rectangle_lines_ex(self.bounds, 1f32, Color::DARKGRAY);
+
+        for (row, (option, i)) in option_rows(options).enumerate() {
+            let bounds = self.row_bounds(row);
+            let center_x = (bounds.x + bounds.width / 2f32) as i32;
+            let center_y = (bounds.y + bounds.height / 2f32) as i32;
+
+            let color = if self.is_selected(option, i) {
+                handle.draw_rectangle_rec(bounds, Color::DARKBLUE);
+                Color::WHITE
+            } else {
+                Color::BLACK
+            };
+
+            match option {
+                ToolOption::SpraySize => {
+                    handle.draw_circle_lines(center_x, center_y, SpraySize::ALL[i].radius() / 2f32,
+                                             color);
+                }
+                ToolOption::SampleSize => {
+                    handle.draw_text(SampleSize::ALL[i].label(), bounds.x as i32 + 4, center_y - 5, 10,
+                                     color);
+                }
+                ToolOption::Antialiasing => {
+                    handle.draw_text(ANTIALIASING_LABELS[i], bounds.x as i32 + 4, center_y - 5, 10,
+                                     color);
+                }
+                ToolOption::LineWidth => {
+                    let width = LINE_WIDTHS[i];
+                    handle.draw_rectangle(bounds.x as i32 + 6, center_y - width / 2,
+                                          bounds.width as i32 - 12, width, color);
+                }
+                ToolOption::FillMode => {
+                    let icon = Rectangle {
+                        x: bounds.x + 10f32,
+                        y: bounds.y + 6f32,
+                        width: bounds.width - 20f32,
+                        height: bounds.height - 12f32,
+                    };
+                    let (outline, fill) = FillMode::ALL[i].colors([color, Color::GRAY]);
+                    if let Some(fill) = fill {
+                        handle.draw_rectangle_rec(icon, fill);
+                    }
+                    if let Some(outline) = outline {
+                        handle.draw_rectangle_lines_ex(icon, 1f32, outline);
+                    }
+                }
+                ToolOption::CornerRadius => {
+                    let text = format!("r = {}", self.corner_radius);
+                    handle.draw_text(&text, bounds.x as i32 + 4, center_y - 5, 10, color);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,484 @@
+use raylib::prelude::*;
+use crate::bitmap_font;
+use crate::session::FrameInput;
+
+pub const FONT_SIZE: i32 = 10;
+pub const TITLE_HEIGHT: f32 = 20f32;
+
+const CHECK_BOX_SIZE: f32 = 12f32;
+const SLIDER_THUMB_WIDTH: f32 = 6f32;
+/// Arrow key presses from one end of a slider to the other
+const SLIDER_STEPS: f32 = 20f32;
+const NUMBER_FIELD_LENGTH: usize = 9;
+const TOOLTIP_PADDING: f32 = 4f32;
+/// Below the mouse cursor, so the tooltip does not cover it
+const TOOLTIP_OFFSET: Vector2 = Vector2 { x: 4f32, y: 18f32 };
+
+/// Window behind a modal dialog, dimmed so it looks out of reach
+fn draw_modal_frame(handle: &mut RaylibDrawHandle, screen_size: Vector2, bounds: Rectangle, title: &str) {
+    handle.draw_rectangle_rec(Rectangle::new(0f32, 0f32, screen_size.x, screen_size.y), Color::new(0, 0, 0, 64));
+    handle.draw_rectangle_rec(bounds, Color::LIGHTGRAY);
+    handle.draw_rectangle_lines_ex(bounds, 1f32, Color::BLACK);
+    handle.draw_rectangle(bounds.x as i32, bounds.y as i32, bounds.width as i32, TITLE_HEIGHT as i32, Color::DARKBLUE);
+    handle.draw_text(title, bounds.x as i32 + 6, bounds.y as i32 + 5, FONT_SIZE, Color::WHITE);
+}
+
+/// Black outline with a light top left edge and a dark bottom right one, swapped when sunken
+pub fn draw_bevel(handle: &mut RaylibDrawHandle, bounds: Rectangle, face: Color, sunken: bool) {
+    let (light, shadow) = if sunken { (Color::DARKGRAY, Color::WHITE) } else { (Color::WHITE, Color::DARKGRAY) };
+    let (left, top) = (bounds.x as i32, bounds.y as i32);
+    let (right, bottom) = ((bounds.x + bounds.width) as i32 - 1, (bounds.y + bounds.height) as i32 - 1);
+    handle.draw_rectangle_rec(bounds, face);
+    handle.draw_rectangle_lines_ex(bounds, 1f32, Color::BLACK);
+    handle.draw_line(left + 1, top + 1, right, top + 1, light);
+    handle.draw_line(left + 1, top + 1, left + 1, bottom, light);
+    handle.draw_line(left + 1, bottom - 1, right, bottom - 1, shadow);
+    handle.draw_line(right - 1, top + 1, right - 1, bottom, shadow);
+}
+
+/// Raised button, sunken while the mouse holds it down, with a frame when it has the focus
+fn draw_button(handle: &mut RaylibDrawHandle, bounds: Rectangle, label: &str, pressed: bool, focused: bool) {
+    draw_bevel(handle, bounds, Color::LIGHTGRAY, pressed);
+    if focused {
+        let inner = Rectangle::new(bounds.x + 3f32, bounds.y + 3f32, bounds.width - 6f32, bounds.height - 6f32);
+        handle.draw_rectangle_lines_ex(inner, 1f32, Color::BLUE);
+    }
+
+    // The label moves with the face of the button
+    let offset = if pressed { 1 } else { 0 };
+    let text_width = bitmap_font::measure_text(label, FONT_SIZE);
+    handle.draw_text(
+        label,
+        (bounds.x + (bounds.width - text_width as f32) / 2f32) as i32 + offset,
+        (bounds.y + (bounds.height - FONT_SIZE as f32) / 2f32) as i32 + offset,
+        FONT_SIZE,
+        Color::BLACK
+    );
+}
+
+/// Pale yellow box with the text, next to the mouse and kept inside the window
+pub fn draw_tooltip(handle: &mut RaylibDrawHandle, screen_size: Vector2, mouse_position: Vector2, text: &str) {
+    let width = bitmap_font::measure_text(text, FONT_SIZE) as f32 + TOOLTIP_PADDING * 2f32;
+    let height = FONT_SIZE as f32 + TOOLTIP_PADDING * 2f32;
+    let position = mouse_position + TOOLTIP_OFFSET;
+    let bounds = Rectangle {
+        x: position.x.min(screen_size.x - width).max(0f32).floor(),
+        y: position.y.min(screen_size.y - height).max(0f32).floor(),
+        width,
+        height,
+    };
+    handle.draw_rectangle_rec(bounds, Color::new(255, 255, 225, 255));
+    handle.draw_rectangle_lines_ex(bounds, 1f32, Color::BLACK);
+    handle.draw_text(text, (bounds.x + TOOLTIP_PADDING) as i32, (bounds.y + TOOLTIP_PADDING) as i32, FONT_SIZE,
+                     Color::BLACK);
+}
+
+/// White box of a text field, with a cursor after the text while it has the focus. Text too
+/// long for the box shows its end, where the typing happens.
+fn draw_text_box(handle: &mut RaylibDrawHandle, bounds: Rectangle, text: &str, focused: bool) {
+    handle.draw_rectangle_rec(bounds, Color::WHITE);
+    handle.draw_rectangle_lines_ex(bounds, 1f32, if focused { Color::BLUE } else { Color::DARKGRAY });
+    let text = if focused { format!("{}_", text) } else { text.to_string() };
+    let room = bounds.width as i32 - 8;
+    let shown = text.char_indices()
+        .map(|(i, _)| &text[i..])
+        .find(|tail| bitmap_font::measure_text(tail, FONT_SIZE) <= room)
+        .unwrap_or("");
+    let y = (bounds.y + (bounds.height - FONT_SIZE as f32) / 2f32) as i32;
+    handle.draw_text(shown, bounds.x as i32 + 4, y, FONT_SIZE, Color::BLACK);
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Widget {
+    Label(String),
+    Button(String),
+    /// Whole number typed in or stepped with the arrow keys, kept between its bounds
+    NumberField { value: i32, min: i32, max: i32, text: String },
+    TextField(String),
+    CheckBox { label: String, checked: bool },
+    Slider { value: f32, min: f32, max: f32 },
+}
+
+impl Widget {
+    fn focusable(&self) -> bool {
+        !matches!(self, Widget::Label(_))
+    }
+
+    /// Make the typed text the value, or show the value again when the text is not a number
+    fn commit(&mut self) {
+        if let Widget::NumberField { value, min, max, text } = self {
+            if let Ok(number) = text.parse::<i64>() {
+                *value = number.clamp(*min as i64, *max as i64) as i32;
+            }
+            *text = value.to_string();
+        }
+    }
+
+    /// Add to the typed number, or to the value when the text is not a number
+    fn step_number(&mut self, step: i32) {
+        self.commit();
+        if let Widget::NumberField { value, .. } = self {
+            let number = value.saturating_add(step);
+            self.set_number(number);
+        }
+    }
+
+    fn set_number(&mut self, number: i32) {
+        if let Widget::NumberField { value, min, max, text } = self {
+            *value = number.clamp(*min, *max);
+            *text = value.to_string();
+        }
+    }
+}
+
+struct Control {
+    /// How the owner of the dialog refers to the control
+    name: &'static str,
+    /// Relative to the dialog
+    bounds: Rectangle,
+    widget: Widget,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DialogEvent {
+    Open,
+    /// Escape, or the cancel button
+    Cancelled,
+    /// A button, by name. Enter clicks the default button.
+    Clicked(&'static str),
+}
+
+/// Modal dialog built from widgets, centered in the window. Tab moves the focus, Space presses
+/// the focused button or check box, and the arrow keys step numbers and sliders.
+pub struct Dialog {
+    title: String,
+    size: Vector2,
+    /// Size of the window, dimmed behind the dialog
+    screen_size: Vector2,
+    position: Vector2,
+    controls: Vec<Control>,
+    /// Control that gets the keys
+    focus: Option<usize>,
+    /// Control under the mouse when its button went down
+    held: Option<usize>,
+    default_button: Option<&'static str>,
+    cancel_button: Option<&'static str>,
+}
+
+impl Dialog {
+    pub fn new(title: impl Into<String>, size: Vector2, screen_size: Vector2) -> Self {
+        let mut dialog = Dialog {
+            title: title.into(),
+            size,
+            screen_size,
+            position: Vector2::zero(),
+            controls: Vec::new(),
+            focus: None,
+            held: None,
+            default_button: None,
+            cancel_button: None,
+        };
+        dialog.resize(screen_size);
+        dialog
+    }
+
+    fn with_control(mut self, name: &'static str, bounds: Rectangle, widget: Widget) -> Self {
+        if self.focus.is_none() && widget.focusable() {
+            self.focus = Some(self.controls.len());
+        }
+        self.controls.push(Control { name, bounds, widget });
+        self
+    }
+
+    /// Bounds are relative to the top left corner of the dialog, title bar included
+    pub fn with_label(self, text: &str, bounds: Rectangle) -> Self {
+        self.with_control("", bounds, Widget::Label(text.to_string()))
+    }
+
+    pub fn with_button(self, name: &'static str, label: &str, bounds: Rectangle) -> Self {
+        self.with_control(name, bounds, Widget::Button(label.to_string()))
+    }
+
+    pub fn with_number_field(self, name: &'static str, bounds: Rectangle, value: i32, min: i32, max: i32) -> Self {
+        let value = value.clamp(min, max);
+        self.with_control(name, bounds, Widget::NumberField { value, min, max, text: value.to_string() })
+    }
+
+    pub fn with_text_field(self, name: &'static str, bounds: Rectangle, text: &str) -> Self {
+        self.with_control(name, bounds, Widget::TextField(text.to_string()))
+    }
+
+    pub fn with_check_box(self, name: &'static str, label: &str, bounds: Rectangle, checked: bool) -> Self {
+        self.with_control(name, bounds, Widget::CheckBox { label: label.to_string(), checked })
+    }
+
+    pub fn with_slider(self, name: &'static str, bounds: Rectangle, value: f32, min: f32, max: f32) -> Self {
+        self.with_control(name, bounds, Widget::Slider { value: value.clamp(min, max), min, max })
+    }
+
+    /// Button clicked by Enter
+    pub fn with_default_button(mut self, name: &'static str) -> Self {
+        self.default_button = Some(name);
+        self
+    }
+
+    /// Button that does the same as Escape
+    pub fn with_cancel_button(mut self, name: &'static str) -> Self {
+        self.cancel_button = Some(name);
+        self
+    }
+
+    /// Center the dialog in the window again
+    pub fn resize(&mut self, screen_size: Vector2) {
+        self.screen_size = screen_size;
+        self.position = Vector2 {
+            x: ((screen_size.x - self.size.x) / 2f32).floor(),
+            y: ((screen_size.y - self.size.y) / 2f32).floor(),
+        };
+    }
+
+    fn widget(&self, name: &str) -> Option<&Widget> {
+        self.controls.iter().find(|control| control.name == name).map(|control| &control.widget)
+    }
+
+    pub fn number(&self, name: &str) -> Option<i32> {
+        match self.widget(name) {
+            Some(Widget::NumberField { value, .. }) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn widget_mut(&mut self, name: &str) -> Option<&mut Widget> {
+        self.controls.iter_mut().find(|control| control.name == name).map(|control| &mut control.widget)
+    }
+
+    pub fn set_number(&mut self, name: &str, number: i32) {
+        if let Some(widget) = self.widget_mut(name) {
+            widget.set_number(number);
+        }
+    }
+
+    pub fn text(&self, name: &str) -> Option<&str> {
+        match self.widget(name) {
+            Some(Widget::TextField(text)) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn set_text(&mut self, name: &str, text: &str) {
+        if let Some(Widget::TextField(field)) = self.widget_mut(name) {
+            text.clone_into(field);
+        }
+    }
+
+    pub fn checked(&self, name: &str) -> Option<bool> {
+        match self.widget(name) {
+            Some(Widget::CheckBox { checked, .. }) => Some(*checked),
+            _ => None,
+        }
+    }
+
+    pub fn slider(&self, name: &str) -> Option<f32> {
+        match self.widget(name) {
+            Some(Widget::Slider { value, .. }) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn set_slider(&mut self, name: &str, number: f32) {
+        if let Some(Widget::Slider { value, min, max }) = self.widget_mut(name) {
+            *value = number.clamp(*min, *max);
+        }
+    }
+
+    /// Top left corner of the dialog, in the window
+    pub fn position(&self) -> Vector2 {
+        self.position
+    }
+
+    /// Name of the control that gets the keys
+    pub fn focused(&self) -> Option<&'static str> {
+        self.focus.map(|i| self.controls[i].name)
+    }
+
+    fn bounds(&self, control: &Control) -> Rectangle {
+        Rectangle { x: self.position.x + control.bounds.x, y: self.position.y + control.bounds.y, ..control.bounds }
+    }
+
+    fn focus_control(&mut self, index: Option<usize>) {
+        if let Some(i) = self.focus {
+            self.controls[i].widget.commit();
+        }
+        self.focus = index;
+    }
+
+    /// Next control that takes the focus, forwards or backwards and wrapping around
+    fn step_focus(&mut self, forwards: bool) {
+        let count = self.controls.len();
+        let start = self.focus.unwrap_or(if forwards { count - 1 } else { 0 });
+        let next = (1..=count)
+            .map(|i| if forwards { (start + i) % count } else { (start + count - i) % count })
+            .find(|i| self.controls[*i].widget.focusable());
+        self.focus_control(next);
+    }
+
+    fn click(&mut self, index: usize) -> DialogEvent {
+        let control = &mut self.controls[index];
+        match &mut control.widget {
+            Widget::Button(_) if self.cancel_button == Some(control.name) => DialogEvent::Cancelled,
+            Widget::Button(_) => {
+                self.focus_control(self.focus);
+                DialogEvent::Clicked(self.controls[index].name)
+            },
+            Widget::CheckBox { checked, .. } => {
+                *checked = !*checked;
+                DialogEvent::Open
+            },
+            _ => DialogEvent::Open,
+        }
+    }
+
+    fn drag_slider(&mut self, index: usize, mouse_position: Vector2) {
+        let bounds = self.bounds(&self.controls[index]);
+        if let Widget::Slider { value, min, max } = &mut self.controls[index].widget {
+            let fraction = ((mouse_position.x - bounds.x) / (bounds.width - 1f32)).clamp(0f32, 1f32);
+            *value = *min + fraction * (*max - *min);
+        }
+    }
+
+    /// Keys for the focused control
+    fn control_keys(&mut self, index: usize, input: &FrameInput) -> DialogEvent {
+        let space = input.is_key_pressed(KeyboardKey::KEY_SPACE);
+        let step = |up: KeyboardKey, down: KeyboardKey| {
+            (input.is_key_pressed(up) as i32) - (input.is_key_pressed(down) as i32)
+        };
+        match &mut self.controls[index].widget {
+            Widget::Button(_) | Widget::CheckBox { .. } if space => return self.click(index),
+            Widget::NumberField { min, text, .. } => {
+                for &c in &input.chars {
+                    let sign = c == '-' && *min < 0 && text.is_empty();
+                    if (c.is_ascii_digit() || sign) && text.len() < NUMBER_FIELD_LENGTH {
+                        text.push(c);
+                    }
+                }
+                if input.is_key_pressed(KeyboardKey::KEY_BACKSPACE) {
+                    text.pop();
+                }
+                let step = step(KeyboardKey::KEY_UP, KeyboardKey::KEY_DOWN);
+                if step != 0 {
+                    self.controls[index].widget.step_number(step);
+                }
+            },
+            Widget::TextField(text) => {
+                text.extend(input.chars.iter().filter(|c| !c.is_control()));
+                if input.is_key_pressed(KeyboardKey::KEY_BACKSPACE) {
+                    text.pop();
+                }
+            },
+            Widget::Slider { value, min, max } => {
+                let step = step(KeyboardKey::KEY_RIGHT, KeyboardKey::KEY_LEFT)
+                    + step(KeyboardKey::KEY_UP, KeyboardKey::KEY_DOWN);
+                *value = (*value + step as f32 * (*max - *min) / SLIDER_STEPS).clamp(*min, *max);
+            },
+            _ => {}
+        }
+        DialogEvent::Open
+    }
+
+    pub fn update(&mut self, input: &FrameInput) -> DialogEvent {
+        if input.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+            return DialogEvent::Cancelled;
+        }
+        if input.is_key_pressed(KeyboardKey::KEY_TAB) && !self.controls.is_empty() {
+            self.step_focus(!input.modifiers.shift);
+        }
+
+        let mouse_position = input.mouse_position;
+        let hovered = self.controls.iter().position(|control| {
+            control.widget.focusable() && self.bounds(control).check_collision_point_rec(mouse_position)
+        });
+        if input.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) {
+            self.held = hovered;
+            if hovered.is_some() && hovered != self.focus {
+                self.focus_control(hovered);
+            }
+        }
+        if let Some(held) = self.held {
+            if matches!(self.controls[held].widget, Widget::Slider { .. }) {
+                self.drag_slider(held, mouse_position);
+            }
+            if input.is_mouse_button_released(MouseButton::MOUSE_BUTTON_LEFT) {
+                self.held = None;
+                if hovered == Some(held) {
+                    return self.click(held);
+                }
+            }
+        }
+
+        if let Some(focus) = self.focus {
+            let event = self.control_keys(focus, input);
+            if event != DialogEvent::Open {
+                return event;
+            }
+        }
+
+        if input.is_key_pressed(KeyboardKey::KEY_ENTER) {
+            let default = self.controls.iter().position(|control| Some(control.name) == self.default_button);
+            if let Some(i) = default {
+                return self.click(i);
+            }
+        }
+        DialogEvent::Open
+    }
+
+    pub fn draw(&self, handle: &mut RaylibDrawHandle) {
+        let frame = Rectangle::new(self.position.x, self.position.y, self.size.x, self.size.y);
+        draw_modal_frame(handle, self.screen_size, frame, &self.title);
+
+        for (i, control) in self.controls.iter().enumerate() {
+            let bounds = self.bounds(control);
+            let focused = self.focus == Some(i);
+            let text_y = (bounds.y + (bounds.height - FONT_SIZE as f32) / 2f32) as i32;
+            match &control.widget {
+                Widget::Label(text) => handle.draw_text(text, bounds.x as i32, text_y, FONT_SIZE, Color::BLACK),
+                Widget::Button(label) => draw_button(handle, bounds, label, self.held == Some(i), focused),
+                Widget::NumberField { text, .. } | Widget::TextField(text) => {
+                    draw_text_box(handle, bounds, text, focused)
+                },
+                Widget::CheckBox { label, checked } => {
+                    let check_box = Rectangle {
+                        x: bounds.x,
+                        y: bounds.y + ((bounds.height - CHECK_BOX_SIZE) / 2f32).floor(),
+                        width: CHECK_BOX_SIZE,
+                        height: CHECK_BOX_SIZE,
+                    };
+                    handle.draw_rectangle_rec(check_box, Color::WHITE);
+                    handle.draw_rectangle_lines_ex(check_box, 1f32, if focused { Color::BLUE } else { Color::DARKGRAY });
+                    if *checked {
+                        let (x, y) = (check_box.x + 2f32, check_box.y + 6f32);
+                        handle.draw_line_ex(Vector2::new(x, y), Vector2::new(x + 3f32, y + 3f32), 2f32, Color::BLACK);
+                        handle.draw_line_ex(Vector2::new(x + 3f32, y + 3f32), Vector2::new(x + 8f32, y - 3f32), 2f32,
+                                            Color::BLACK);
+                    }
+                    handle.draw_text(label, (bounds.x + CHECK_BOX_SIZE + 6f32) as i32, text_y, FONT_SIZE, Color::BLACK);
+                },
+                Widget::Slider { value, min, max } => {
+                    let middle = (bounds.y + bounds.height / 2f32) as i32;
+                    handle.draw_line(bounds.x as i32, middle, (bounds.x + bounds.width) as i32, middle, Color::DARKGRAY);
+                    handle.draw_line(bounds.x as i32, middle + 1, (bounds.x + bounds.width) as i32, middle + 1,
+                                     Color::WHITE);
+                    let fraction = if max > min { (value - min) / (max - min) } else { 0f32 };
+                    let thumb = Rectangle {
+                        x: bounds.x + fraction * (bounds.width - 1f32) - SLIDER_THUMB_WIDTH / 2f32,
+                        width: SLIDER_THUMB_WIDTH,
+                        ..bounds
+                    };
+                    draw_button(handle, thumb, "", false, false);
+                    if focused {
+                        handle.draw_rectangle_lines_ex(thumb, 1f32, Color::BLUE);
+                    }
+                },
+            }
+        }
+    }
+}
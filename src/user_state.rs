@@ -1,8 +1,13 @@
 use raylib::color::Color;
 use raylib::math::Vector2;
-use raylib::texture::{Image};
 use crate::actions::brush::{BrushSize, BrushType};
-use crate::actions::spray::SpraySize;
+use crate::actions::color_picker::SampleSize;
+use crate::actions::fill_mode::FillMode;
+use crate::actions::rounded_rectangle::CORNER_RADIUS_DEFAULT;
+use crate::actions::spray::{SpraySize, SPRAY_DENSITY_DEFAULT};
+
+/// Stroke widths the options box offers, in pixels
+pub const LINE_WIDTHS: [i32; 3] = [1, 3, 5];
 
 /// Vector2 in the Canvas coordinate system
 #[derive(Debug, PartialEq, Copy, Clone, Default)]
@@ -12,15 +17,25 @@ pub struct CanvasVector2(pub Vector2);
 #[derive(Debug, PartialEq, Copy, Clone, Default)]
 pub struct WindowVector2(pub Vector2);
 
-#[derive(Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct UserState {
     pub current_colors: [Color; 2],
     pub mouse_position: WindowVector2,
     pub canvas_position: WindowVector2,
     pub spray_size: SpraySize,
+    /// Spray particles per second
+    pub spray_density: f32,
     pub brush_size: BrushSize,
     pub brush_type: BrushType,
-    pub canvas_image: Image,
+    /// Shape tools blend their edges instead of setting whole pixels
+    pub antialiasing: bool,
+    /// Stroke width of the shape tools, in pixels
+    pub line_width: i32,
+    pub fill_mode: FillMode,
+    /// Corner radius of the rounded rectangle tool, in pixels
+    pub corner_radius: i32,
+    /// Area averaged by the color picker
+    pub sample_size: SampleSize,
 }
 
 impl UserState {
@@ -31,4 +46,24 @@ impl UserState {
     pub fn to_window(&self, vector: CanvasVector2) -> WindowVector2 {
         WindowVector2(vector.0 + self.canvas_position.0)
     }
-}
\ No newline at end of file
+}
+
+/// The options the window starts with
+impl Default for UserState {
+    fn default() -> Self {
+        UserState {
+            current_colors: [Color::BLACK, Color::WHITE],
+            mouse_position: WindowVector2::default(),
+            canvas_position: WindowVector2::default(),
+            spray_size: SpraySize::SizeOne,
+            spray_density: SPRAY_DENSITY_DEFAULT,
+            brush_size: BrushSize::Two,
+            brush_type: BrushType::Circle,
+            antialiasing: false,
+            line_width: LINE_WIDTHS[0],
+            fill_mode: FillMode::Outline,
+            corner_radius: CORNER_RADIUS_DEFAULT,
+            sample_size: SampleSize::One,
+        }
+    }
+}
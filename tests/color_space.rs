@@ -0,0 +1,70 @@
+use raylib::color::Color;
+use PaintIt::color_space::{parse_hex, to_hex, Hsv};
+
+fn assert_close(actual: f32, expected: f32) {
+    assert!((actual - expected).abs() < 1e-4, "{} is not {}", actual, expected);
+}
+
+#[test]
+fn colors_survive_a_round_trip_through_hsv() {
+    for r in (0..=255).step_by(15) {
+        for g in (0..=255).step_by(15) {
+            for b in (0..=255).step_by(15) {
+                let color = Color::new(r as u8, g as u8, b as u8, 200);
+                assert_eq!(Hsv::from_color(color).to_color(200), color);
+            }
+        }
+    }
+}
+
+#[test]
+fn primaries_have_their_hues() {
+    for (color, hue) in [(Color::new(255, 0, 0, 255), 0f32), (Color::new(0, 255, 0, 255), 120f32),
+                         (Color::new(0, 0, 255, 255), 240f32), (Color::new(255, 0, 255, 255), 300f32)] {
+        let hsv = Hsv::from_color(color);
+        assert_close(hsv.hue, hue);
+        assert_close(hsv.saturation, 1f32);
+        assert_close(hsv.value, 1f32);
+    }
+}
+
+#[test]
+fn hues_wrap_around_at_360() {
+    let red = Hsv { hue: 0f32, saturation: 1f32, value: 1f32 };
+    assert_eq!(Hsv { hue: 360f32, ..red }.to_color(255), red.to_color(255));
+    assert_eq!(Hsv { hue: 480f32, ..red }.to_color(255), Color::new(0, 255, 0, 255));
+    assert_eq!(Hsv { hue: -120f32, ..red }.to_color(255), Color::new(0, 0, 255, 255));
+    assert_eq!(Hsv { hue: 359.999, ..red }.to_color(255), Color::new(255, 0, 0, 255));
+}
+
+#[test]
+fn greys_have_no_saturation() {
+    let grey = Hsv::from_color(Color::new(128, 128, 128, 255));
+    assert_eq!(grey.hue, 0f32);
+    assert_eq!(grey.saturation, 0f32);
+    assert_close(grey.value, 128f32 / 255f32);
+    assert_eq!(Hsv::from_color(Color::BLACK), Hsv::default());
+
+    // Any hue gives the same grey without saturation
+    let grey = Hsv { hue: 200f32, ..grey };
+    assert_eq!(grey.to_color(255), Color::new(128, 128, 128, 255));
+}
+
+#[test]
+fn hex_keeps_the_alpha_when_not_opaque() {
+    assert_eq!(to_hex(Color::new(18, 52, 86, 255)), "#123456");
+    assert_eq!(to_hex(Color::new(18, 52, 86, 120)), "#12345678");
+    assert_eq!(parse_hex("#12345678"), Some(Color::new(18, 52, 86, 120)));
+    assert_eq!(parse_hex(" abcdef "), Some(Color::new(171, 205, 239, 255)));
+
+    for color in [Color::new(0, 0, 0, 0), Color::new(255, 128, 1, 255), Color::new(9, 10, 11, 254)] {
+        assert_eq!(parse_hex(&to_hex(color)), Some(color));
+    }
+}
+
+#[test]
+fn bad_hex_is_rejected() {
+    for text in ["", "#", "#12345", "#1234567", "#123456789", "#GG0000", "12 456", "#ééé"] {
+        assert_eq!(parse_hex(text), None, "{}", text);
+    }
+}
@@ -0,0 +1,134 @@
+use std::path::Path;
+use raylib::color::Color;
+use raylib::math::Vector2;
+use PaintIt::actions::pencil::PencilState;
+use PaintIt::document::{Document, DocumentError, UNDO_LIMIT};
+use PaintIt::operation::Operation;
+use PaintIt::user_state::CanvasVector2;
+
+fn line(x: f32) -> Operation {
+    Operation::Line { from: Vector2::new(x, 0f32), to: Vector2::new(x, 7f32) }
+}
+
+#[test]
+fn operations_are_undone_and_redone() {
+    let mut document = Document::new(8, 8, Color::WHITE);
+    assert!(!document.can_undo());
+    assert!(document.apply(&line(2f32)));
+    assert!(document.apply(&line(5f32)));
+    assert_eq!(document.get_pixel(5, 3), Some(Color::BLACK));
+
+    assert!(document.undo());
+    assert_eq!(document.get_pixel(5, 3), Some(Color::WHITE));
+    assert_eq!(document.get_pixel(2, 3), Some(Color::BLACK));
+    assert!(document.can_redo());
+    assert!(document.redo());
+    assert_eq!(document.get_pixel(5, 3), Some(Color::BLACK));
+    assert!(!document.redo());
+
+    // A new change forgets what was undone
+    document.undo();
+    document.apply(&Operation::FlipHorizontal);
+    assert!(!document.can_redo());
+}
+
+#[test]
+fn strokes_drawn_outside_are_one_undo_step() {
+    let mut document = Document::new(8, 8, Color::WHITE);
+    let before = document.canvas().clone();
+    document.canvas_mut().draw_pixel(1, 1, Color::RED);
+    document.canvas_mut().draw_pixel(2, 2, Color::RED);
+    document.push_undo(before);
+
+    assert!(document.apply(&Operation::Clear));
+    assert_eq!(document.get_pixel(1, 1), Some(Color::WHITE));
+    assert!(document.undo());
+    assert_eq!(document.get_pixel(2, 2), Some(Color::RED));
+    assert!(document.undo());
+    assert_eq!(document.get_pixel(1, 1), Some(Color::WHITE));
+    assert!(!document.can_undo());
+}
+
+#[test]
+fn unchanged_pixels_are_not_an_undo_step() {
+    let mut document = Document::new(8, 8, Color::WHITE);
+    document.options.current_colors[0] = Color::WHITE;
+    assert!(!document.apply(&Operation::Fill(Vector2::new(1f32, 1f32))));
+    assert!(!document.can_undo());
+    assert_eq!(document.get_pixel(8, 0), None);
+}
+
+#[test]
+fn old_undo_steps_are_forgotten() {
+    let mut document = Document::new(8, 8, Color::WHITE);
+    for i in 0..UNDO_LIMIT + 5 {
+        document.options.current_colors[0] = Color::new(i as u8, 0, 0, 255);
+        assert!(document.apply(&line(0f32)));
+    }
+    let mut undone = 0;
+    while document.undo() {
+        undone += 1;
+    }
+    assert_eq!(undone, UNDO_LIMIT);
+}
+
+#[test]
+fn any_tool_can_draw() {
+    let mut document = Document::new(8, 8, Color::WHITE);
+    let frames = [
+        (CanvasVector2(Vector2::new(1f32, 1f32)), true),
+        (CanvasVector2(Vector2::new(6f32, 1f32)), true),
+        (CanvasVector2(Vector2::new(6f32, 1f32)), false),
+    ];
+    assert!(document.apply_tool(&mut PencilState::default(), &frames));
+    assert_eq!(document.get_pixel(4, 1), Some(Color::BLACK));
+    assert!(document.undo());
+    assert_eq!(document.get_pixel(4, 1), Some(Color::WHITE));
+}
+
+#[test]
+fn changes_since_the_last_save_are_tracked() {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("modified.png");
+    let mut document = Document::new(8, 8, Color::WHITE);
+    assert!(!document.is_modified());
+    document.apply(&line(2f32));
+    assert!(document.is_modified());
+    document.undo();
+    assert!(!document.is_modified());
+    document.redo();
+    assert!(document.is_modified());
+
+    document.save(&path).unwrap();
+    assert!(!document.is_modified());
+    document.undo();
+    assert!(document.is_modified());
+    // The redo steps are gone, so the saved canvas can't come back
+    document.apply(&line(5f32));
+    document.undo();
+    assert!(document.is_modified());
+}
+
+#[test]
+fn saved_documents_open_the_same() {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("document.png");
+    let mut document = Document::new(12, 6, Color::WHITE);
+    document.apply(&Operation::Ellipse { from: Vector2::new(1f32, 1f32), to: Vector2::new(10f32, 5f32) });
+    document.save(&path).unwrap();
+    assert_eq!(Document::open(&path).unwrap().canvas(), document.canvas());
+
+    assert!(matches!(document.save(&path.with_extension("gif")), Err(DocumentError::UnsupportedFormat)));
+    assert!(matches!(Document::open(&path.with_extension("missing.png")), Err(DocumentError::Load(_))));
+}
+
+#[test]
+fn flood_fill_crosses_tiles() {
+    use PaintIt::pixel_buffer::TILE_SIZE;
+    let corner = TILE_SIZE as f32;
+    let mut document = Document::new(TILE_SIZE * 2, TILE_SIZE * 2, Color::WHITE);
+    document.apply(&Operation::Rectangle { from: Vector2::new(corner - 10f32, corner - 10f32),
+        to: Vector2::new(corner + 10f32, corner + 10f32) });
+    document.options.current_colors[0] = Color::RED;
+    assert!(document.apply(&Operation::Fill(Vector2::new(corner - 5f32, corner - 5f32))));
+    assert_eq!(document.get_pixel(TILE_SIZE + 5, TILE_SIZE + 5), Some(Color::RED));
+    assert_eq!(document.get_pixel(TILE_SIZE + 12, TILE_SIZE), Some(Color::WHITE));
+}
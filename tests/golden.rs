@@ -0,0 +1,355 @@
+//! Golden image tests: every tool is fed scripted pointer events over a small canvas, and the
+//! pixels it leaves are compared with the PNG of the same name in `tests/golden`.
+//!
+//! Run `UPDATE_GOLDEN=1 cargo test --test golden` to write the current output as the new goldens.
+//! When a test fails, its output and an image of the differing pixels are written to `target/tmp/golden`.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use raylib::color::Color;
+use raylib::texture::Image;
+use PaintIt::actions::fill_mode::FillMode;
+use PaintIt::actions::input::InputEvent;
+use PaintIt::actions::preview::Tool;
+use PaintIt::actions::spray::{SpraySize, SprayState, SPRAY_DENSITY_DEFAULT};
+use PaintIt::actions::tool_registry::ToolRegistry;
+use PaintIt::actions::update_execute_action::apply_input;
+use PaintIt::actions::brush::{BrushSize, BrushType};
+use PaintIt::actions::color_picker::SampleSize;
+use PaintIt::pixel_buffer::PixelBuffer;
+use PaintIt::raylib_extensions::{buffer_from_image, image_from_buffer};
+use PaintIt::user_state::{UserState, WindowVector2};
+
+const WIDTH: i32 = 48;
+const HEIGHT: i32 = 32;
+
+/// Time between two frames of a script, in seconds
+const FRAME_TIME: f32 = 1f32 / 60f32;
+
+/// Frames used to move between two points of a stroke
+const STROKE_STEPS: usize = 4;
+
+const SQUARE_COLOR: Color = Color::new(0, 128, 255, 255);
+
+/// Pointer state during one frame, in canvas coordinates
+#[derive(Debug, Copy, Clone)]
+struct Frame {
+    x: f32,
+    y: f32,
+    pressed: bool,
+}
+
+/// Press on the first point, move through the others with the button held, then release
+fn stroke(points: &[(f32, f32)]) -> Vec<Frame> {
+    let (x, y) = points[0];
+    let mut frames = vec![Frame { x, y, pressed: true }];
+    for pair in points.windows(2) {
+        let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+        for step in 1..=STROKE_STEPS {
+            // The window reports the pointer on whole pixels
+            let t = step as f32 / STROKE_STEPS as f32;
+            let (x, y) = ((x0 + (x1 - x0) * t).round(), (y0 + (y1 - y0) * t).round());
+            frames.push(Frame { x, y, pressed: true });
+        }
+    }
+
+    let (x, y) = points[points.len() - 1];
+    frames.push(Frame { x, y, pressed: false });
+    frames
+}
+
+fn click(x: f32, y: f32) -> Vec<Frame> {
+    stroke(&[(x, y)])
+}
+
+/// Keep the button held on the same point
+fn hold(x: f32, y: f32, frames: usize) -> Vec<Frame> {
+    let mut script = vec![Frame { x, y, pressed: true }; frames];
+    script.push(Frame { x, y, pressed: false });
+    script
+}
+
+fn user_state() -> UserState {
+    UserState {
+        current_colors: [Color::BLACK, Color::WHITE],
+        mouse_position: WindowVector2::default(),
+        canvas_position: WindowVector2::default(),
+        spray_size: SpraySize::SizeOne,
+        spray_density: SPRAY_DENSITY_DEFAULT,
+        brush_size: BrushSize::Two,
+        brush_type: BrushType::Circle,
+        antialiasing: false,
+        line_width: 1,
+        fill_mode: FillMode::Outline,
+        corner_radius: 4,
+        sample_size: SampleSize::One,
+    }
+}
+
+fn blank_canvas() -> PixelBuffer {
+    PixelBuffer::new(WIDTH, HEIGHT, Color::WHITE)
+}
+
+/// White canvas with a square outlined in black, for the tools working on existing pixels
+fn square_canvas() -> PixelBuffer {
+    let mut canvas = blank_canvas();
+    canvas.draw_rectangle(12, 8, 16, 16, SQUARE_COLOR);
+    canvas.draw_rectangle_lines(raylib::math::Rectangle::new(10f32, 6f32, 20f32, 20f32), 1, Color::BLACK);
+    canvas
+}
+
+/// The tool as created by its toolbar button
+fn registered_tool(name: &str) -> Box<dyn Tool> {
+    let mut registry = ToolRegistry::with_default_tools();
+    let index = (0..registry.len())
+        .find(|&i| registry.get(i).unwrap().name == name)
+        .unwrap_or_else(|| panic!("no tool named {}", name));
+    registry.get_mut(index).unwrap().tool.take().unwrap()
+}
+
+/// Run the script, one frame per step, the same way the window does
+fn run(tool: &mut dyn Tool, mut user_state: UserState, mut canvas: PixelBuffer, script: &[Frame]) -> PixelBuffer {
+    for frame in script {
+        user_state.mouse_position = WindowVector2(raylib::math::Vector2::new(frame.x, frame.y));
+        let input = InputEvent { pressed: frame.pressed, elapsed: FRAME_TIME, ..Default::default() };
+        apply_input(tool, &user_state, &input, &mut canvas);
+    }
+    canvas
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden").join(format!("{}.png", name))
+}
+
+/// Matching pixels are washed out, differing ones are red
+fn diff_image(expected: &PixelBuffer, actual: &PixelBuffer) -> PixelBuffer {
+    let width = expected.width().max(actual.width());
+    let height = expected.height().max(actual.height());
+    let mut diff = PixelBuffer::new(width, height, Color::RED);
+    for y in 0..height {
+        for x in 0..width {
+            let both_inside = expected.contains(x, y) && actual.contains(x, y);
+            let color = expected.get_color(x, y);
+            if both_inside && color == actual.get_color(x, y) {
+                let luminance = (color.r as u32 * 299 + color.g as u32 * 587 + color.b as u32 * 114) / 1000;
+                let washed = (192 + luminance / 4) as u8;
+                diff.draw_pixel(x, y, Color::new(washed, washed, washed, 255));
+            }
+        }
+    }
+    diff
+}
+
+fn assert_golden(name: &str, actual: &PixelBuffer) {
+    let path = golden_path(name);
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        image_from_buffer(actual).export_image(path.to_str().unwrap());
+        return;
+    }
+
+    let expected = match Image::load_image(path.to_str().unwrap()) {
+        Ok(image) => buffer_from_image(&image),
+        Err(_) => panic!("missing golden image {}, run with UPDATE_GOLDEN=1 to create it", path.display()),
+    };
+    if expected == *actual {
+        return;
+    }
+
+    let output = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    std::fs::create_dir_all(&output).unwrap();
+    let actual_path = output.join(format!("{}.actual.png", name));
+    let diff_path = output.join(format!("{}.diff.png", name));
+    image_from_buffer(actual).export_image(actual_path.to_str().unwrap());
+    image_from_buffer(&diff_image(&expected, actual)).export_image(diff_path.to_str().unwrap());
+
+    let differing = if expected.width() == actual.width() && expected.height() == actual.height() {
+        expected.pixels().zip(actual.pixels()).filter(|(e, a)| e != a).count()
+    } else {
+        (expected.width() * expected.height()).max(actual.width() * actual.height()) as usize
+    };
+    panic!("{}: {} pixels differ from {}\noutput: {}\ndiff: {}", name, differing, path.display(),
+           actual_path.display(), diff_path.display());
+}
+
+#[test]
+fn pencil() {
+    let script = stroke(&[(4f32, 4f32), (20f32, 26f32), (30f32, 6f32), (44f32, 20f32)]);
+    let canvas = run(registered_tool("Pencil").as_mut(), user_state(), blank_canvas(), &script);
+    assert_golden("pencil", &canvas);
+}
+
+#[test]
+fn brush() {
+    let script = stroke(&[(6f32, 6f32), (24f32, 24f32), (42f32, 8f32)]);
+    let canvas = run(registered_tool("Brush").as_mut(), user_state(), blank_canvas(), &script);
+    assert_golden("brush", &canvas);
+}
+
+#[test]
+fn eraser() {
+    let script = stroke(&[(2f32, 16f32), (46f32, 16f32)]);
+    let canvas = run(registered_tool("Eraser").as_mut(), user_state(), square_canvas(), &script);
+    assert_golden("eraser", &canvas);
+}
+
+#[test]
+fn bucket() {
+    let mut state = user_state();
+    state.current_colors[0] = Color::RED;
+    let canvas = run(registered_tool("Fill With Color").as_mut(), state, square_canvas(), &click(20f32, 16f32));
+    assert_golden("bucket", &canvas);
+}
+
+#[test]
+fn color_picker() {
+    let mut tool = registered_tool("Pick Color");
+    let canvas = run(tool.as_mut(), user_state(), square_canvas(), &click(20f32, 16f32));
+    assert_eq!(tool.get_color(), Some(SQUARE_COLOR));
+    assert_golden("color_picker", &canvas);
+}
+
+#[test]
+fn spray() {
+    let mut tool = SprayState::with_seed(7);
+    let canvas = run(&mut tool, user_state(), blank_canvas(), &hold(24f32, 16f32, 10));
+    assert_golden("spray", &canvas);
+}
+
+#[test]
+fn spray_repeats_from_the_registry_seed() {
+    let seeded_spray = |seed: u64| {
+        let mut registry = ToolRegistry::with_default_tools();
+        registry.seed(seed);
+        let index = registry.iter().position(|entry| entry.name == "Airbrush").unwrap();
+        // Pressing the button again makes the next tool of the same sequence
+        registry.get_mut(index).unwrap().reset();
+        let mut tool = registry.get_mut(index).unwrap().tool.take().unwrap();
+        run(tool.as_mut(), user_state(), blank_canvas(), &hold(24f32, 16f32, 10))
+    };
+
+    assert!(seeded_spray(3) == seeded_spray(3));
+    assert!(seeded_spray(3) != seeded_spray(4));
+}
+
+#[test]
+fn line() {
+    let script = stroke(&[(4f32, 28f32), (44f32, 4f32)]);
+    let canvas = run(registered_tool("Line").as_mut(), user_state(), blank_canvas(), &script);
+    assert_golden("line", &canvas);
+}
+
+#[test]
+fn line_antialiased() {
+    let mut state = user_state();
+    state.antialiasing = true;
+    let script = stroke(&[(4f32, 28f32), (44f32, 4f32)]);
+    let canvas = run(registered_tool("Line").as_mut(), state, blank_canvas(), &script);
+    assert_golden("line_antialiased", &canvas);
+}
+
+#[test]
+fn rectangle() {
+    let mut state = user_state();
+    state.line_width = 2;
+    let script = stroke(&[(6f32, 4f32), (40f32, 26f32)]);
+    let canvas = run(registered_tool("Rectangle").as_mut(), state, blank_canvas(), &script);
+    assert_golden("rectangle", &canvas);
+}
+
+#[test]
+fn rectangle_filled() {
+    let mut state = user_state();
+    state.current_colors = [Color::BLACK, SQUARE_COLOR];
+    state.fill_mode = FillMode::OutlineFill;
+    let script = stroke(&[(40f32, 26f32), (6f32, 4f32)]);
+    let canvas = run(registered_tool("Rectangle").as_mut(), state, blank_canvas(), &script);
+    assert_golden("rectangle_filled", &canvas);
+}
+
+#[test]
+fn polygon() {
+    let script = [click(6f32, 26f32), click(24f32, 4f32), click(42f32, 26f32), click(6f32, 26f32)].concat();
+    let canvas = run(registered_tool("Polygon").as_mut(), user_state(), blank_canvas(), &script);
+    assert_golden("polygon", &canvas);
+}
+
+#[test]
+fn ellipse() {
+    let script = stroke(&[(4f32, 4f32), (44f32, 28f32)]);
+    let canvas = run(registered_tool("Ellipse").as_mut(), user_state(), blank_canvas(), &script);
+    assert_golden("ellipse", &canvas);
+}
+
+#[test]
+fn ellipse_antialiased_fill() {
+    let mut state = user_state();
+    state.current_colors = [Color::BLACK, SQUARE_COLOR];
+    state.antialiasing = true;
+    state.fill_mode = FillMode::Fill;
+    let script = stroke(&[(4f32, 4f32), (44f32, 28f32)]);
+    let canvas = run(registered_tool("Ellipse").as_mut(), state, blank_canvas(), &script);
+    assert_golden("ellipse_antialiased_fill", &canvas);
+}
+
+#[test]
+fn thick_antialiased_ellipse_keeps_its_middle_empty() {
+    let mut state = user_state();
+    state.antialiasing = true;
+    state.line_width = 5;
+    let script = stroke(&[(4f32, 12f32), (44f32, 20f32)]);
+    let canvas = run(registered_tool("Ellipse").as_mut(), state, blank_canvas(), &script);
+    assert_eq!(canvas.get_color(24, 16), Color::WHITE);
+    assert_ne!(canvas.get_color(4, 16), Color::WHITE);
+}
+
+#[test]
+fn thick_antialiased_ellipse_blends_every_pixel_once() {
+    let mut state = user_state();
+    state.antialiasing = true;
+    state.line_width = 8;
+    // Half transparent, so a pixel drawn twice comes out darker
+    state.current_colors[0] = Color::new(0, 0, 0, 128);
+    let script = stroke(&[(4f32, 4f32), (44f32, 28f32)]);
+    let canvas = run(registered_tool("Ellipse").as_mut(), state, blank_canvas(), &script);
+    let darkest = (0..32).flat_map(|y| (0..48).map(move |x| (x, y)))
+        .map(|(x, y)| canvas.get_color(x, y).r)
+        .min();
+    assert_eq!(darkest, Some(127));
+}
+
+#[test]
+fn rounded_rectangle() {
+    let mut state = user_state();
+    state.current_colors = [Color::BLACK, SQUARE_COLOR];
+    state.fill_mode = FillMode::OutlineFill;
+    let script = stroke(&[(4f32, 4f32), (44f32, 28f32)]);
+    let canvas = run(registered_tool("Rounded Rectangle").as_mut(), state, blank_canvas(), &script);
+    assert_golden("rounded_rectangle", &canvas);
+}
+
+#[test]
+fn thick_antialiased_rounded_rectangle_stops_at_its_middle() {
+    let draw = |line_width: i32| {
+        let mut state = user_state();
+        state.antialiasing = true;
+        state.line_width = line_width;
+        // Half transparent, so a pixel drawn twice comes out darker
+        state.current_colors[0] = Color::new(0, 0, 0, 128);
+        let script = stroke(&[(4f32, 14f32), (44f32, 18f32)]);
+        run(registered_tool("Rounded Rectangle").as_mut(), state, blank_canvas(), &script)
+    };
+    // Only 4 pixels high, so the rings meet in the middle at a width of 3
+    assert!(draw(3) == draw(5));
+}
+
+#[test]
+fn select_and_move() {
+    // Select the square, drag it to the right, then click outside to drop it
+    let script = [
+        stroke(&[(10f32, 6f32), (30f32, 26f32)]),
+        stroke(&[(20f32, 16f32), (34f32, 12f32)]),
+        click(2f32, 2f32),
+    ].concat();
+    let canvas = run(registered_tool("Select").as_mut(), user_state(), square_canvas(), &script);
+    assert_golden("select_and_move", &canvas);
+}
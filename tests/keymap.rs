@@ -0,0 +1,69 @@
+use raylib::prelude::*;
+use PaintIt::actions::input::Modifiers;
+use PaintIt::actions::tool_registry::ToolRegistry;
+use PaintIt::keymap::{Action, KeyBinding, Keymap, KeymapError};
+use PaintIt::session::FrameInput;
+
+fn defaults() -> Keymap {
+    Keymap::defaults(&ToolRegistry::with_default_tools())
+}
+
+#[test]
+fn bindings_survive_a_round_trip() {
+    let binding = KeyBinding::parse("shift + ctrl+p").unwrap();
+    assert_eq!(binding, KeyBinding::control_shift(KeyboardKey::KEY_P));
+    assert_eq!(binding.to_string(), "Ctrl+Shift+P");
+    assert_eq!(KeyBinding::parse("F5"), Some(KeyBinding::key(KeyboardKey::KEY_F5)));
+    assert_eq!(KeyBinding::parse("Hyper+P"), None);
+
+    let keymap = defaults();
+    assert_eq!(keymap.parse(&keymap.serialize()).unwrap(), keymap);
+}
+
+#[test]
+fn the_file_overrides_the_defaults() {
+    let keymap = defaults().parse("# mine\ntool.pencil = none\ntool.brush = P\n").unwrap();
+    assert_eq!(keymap.binding(&Action::tool("Pencil")), None);
+    assert_eq!(keymap.binding(&Action::tool("Brush")), Some(KeyBinding::key(KeyboardKey::KEY_P)));
+
+    let input = FrameInput { keys_pressed: vec![KeyboardKey::KEY_P], ..Default::default() };
+    assert_eq!(keymap.pressed(&input), Some(&Action::tool("Brush")));
+    let input = FrameInput {
+        keys_pressed: vec![KeyboardKey::KEY_P],
+        modifiers: Modifiers { control: true, shift: false, alt: false },
+        ..Default::default()
+    };
+    assert_eq!(keymap.pressed(&input), None);
+}
+
+#[test]
+fn conflicts_point_at_the_later_line() {
+    let error = defaults().parse("swap-colors = Ctrl+X\n\ntool.line = P\n").unwrap_err();
+    match error {
+        KeymapError::Line(line, message) => {
+            assert_eq!(line, 3);
+            assert!(message.contains("tool.pencil"), "{}", message);
+        },
+        error => panic!("{}", error),
+    }
+}
+
+#[test]
+fn unknown_actions_and_keys_are_errors() {
+    assert!(matches!(defaults().parse("tool.magnifier = M"), Err(KeymapError::Line(1, _))));
+    assert!(matches!(defaults().parse("undo-everything = Z"), Err(KeymapError::Line(1, _))));
+    assert!(matches!(defaults().parse("\nswap-colors = Ctrl+Banana"), Err(KeymapError::Line(2, _))));
+    assert!(matches!(defaults().parse("swap-colors X"), Err(KeymapError::Line(1, _))));
+}
+
+#[test]
+fn menu_commands_have_default_shortcuts() {
+    let keymap = defaults();
+    assert_eq!(keymap.binding(&Action::Undo), Some(KeyBinding::control(KeyboardKey::KEY_Z)));
+    assert_eq!(keymap.binding(&Action::FlipVertical), Some(KeyBinding::control_shift(KeyboardKey::KEY_V)));
+    assert_eq!(keymap.binding(&Action::EditColors), None);
+
+    let keymap = keymap.parse("edit-colors = Ctrl+E\nundo = none\n").unwrap();
+    assert_eq!(keymap.binding(&Action::EditColors), Some(KeyBinding::control(KeyboardKey::KEY_E)));
+    assert_eq!(keymap.binding(&Action::Undo), None);
+}
@@ -0,0 +1,67 @@
+use raylib::color::Color;
+use PaintIt::palette::{parse, serialize, PaletteError, PaletteFormat};
+
+fn colors() -> Vec<Color> {
+    vec![
+        Color::new(0, 0, 0, 255),
+        Color::new(255, 128, 64, 255),
+        Color::new(18, 52, 86, 255),
+    ]
+}
+
+#[test]
+fn round_trip_every_format() {
+    for format in PaletteFormat::ALL {
+        let data = serialize(format, &colors());
+        assert_eq!(parse(format, &data).unwrap(), colors(), "{:?}", format);
+    }
+}
+
+#[test]
+fn gimp_palette_with_comments_and_names() {
+    let text = "GIMP Palette\nName: Brand\nColumns: 4\n# comment\n\n  0   0   0\tBlack\n255 128  64 Orange\n";
+    assert_eq!(parse(PaletteFormat::Gpl, text.as_bytes()).unwrap(), colors()[..2]);
+}
+
+#[test]
+fn gimp_palette_reports_the_bad_line() {
+    let text = "GIMP Palette\n0 0 0\n255 300 0\n";
+    match parse(PaletteFormat::Gpl, text.as_bytes()) {
+        Err(PaletteError::Line(3, _)) => {}
+        result => panic!("unexpected {:?}", result),
+    }
+
+    match parse(PaletteFormat::Gpl, b"0 0 0\n") {
+        Err(PaletteError::Line(1, _)) => {}
+        result => panic!("unexpected {:?}", result),
+    }
+}
+
+#[test]
+fn jasc_palette_count_must_match() {
+    let text = "JASC-PAL\r\n0100\r\n3\r\n0 0 0\r\n255 128 64\r\n";
+    match parse(PaletteFormat::JascPal, text.as_bytes()) {
+        Err(PaletteError::Line(5, _)) => {}
+        result => panic!("unexpected {:?}", result),
+    }
+}
+
+#[test]
+fn hex_list_accepts_a_leading_hash() {
+    let text = "000000\n#FF8040\n\n123456\n";
+    assert_eq!(parse(PaletteFormat::Hex, text.as_bytes()).unwrap(), colors());
+
+    match parse(PaletteFormat::Hex, b"000000\nnope\n") {
+        Err(PaletteError::Line(2, _)) => {}
+        result => panic!("unexpected {:?}", result),
+    }
+}
+
+#[test]
+fn truncated_aco_reports_the_offset() {
+    let data = serialize(PaletteFormat::Aco, &colors());
+    match parse(PaletteFormat::Aco, &data[..9]) {
+        Err(PaletteError::Offset(8, _)) => {}
+        result => panic!("unexpected {:?}", result),
+    }
+}
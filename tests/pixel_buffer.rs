@@ -0,0 +1,116 @@
+use raylib::color::Color;
+use raylib::math::Rectangle;
+use PaintIt::pixel_buffer::{PixelBuffer, TILE_SIZE};
+
+#[test]
+fn damage_covers_everything_drawn_since_it_was_taken() {
+    let mut buffer = PixelBuffer::new(32, 32, Color::WHITE);
+    assert_eq!(buffer.take_damage(), None);
+
+    buffer.draw_pixel(4, 6, Color::BLACK);
+    buffer.draw_line(10, 2, 12, 9, Color::BLACK);
+    assert_eq!(buffer.take_damage(), Some(Rectangle::new(4f32, 2f32, 9f32, 8f32)));
+    assert_eq!(buffer.take_damage(), None);
+}
+
+#[test]
+fn damage_is_clipped_to_the_buffer() {
+    let mut buffer = PixelBuffer::new(16, 16, Color::WHITE);
+    buffer.draw_circle(14, 1, 4, Color::BLACK);
+    buffer.draw_pixel(-1, 3, Color::BLACK);
+    assert_eq!(buffer.take_damage(), Some(Rectangle::new(10f32, 0f32, 6f32, 6f32)));
+}
+
+#[test]
+fn damage_does_not_count_for_equality() {
+    let mut drawn = PixelBuffer::new(4, 4, Color::WHITE);
+    drawn.draw_pixel(1, 1, Color::WHITE);
+    assert_eq!(drawn, PixelBuffer::new(4, 4, Color::WHITE));
+}
+
+/// Spans three tiles across and two down
+fn large_buffer() -> PixelBuffer {
+    PixelBuffer::new(TILE_SIZE * 2 + 40, TILE_SIZE + 8, Color::WHITE)
+}
+
+#[test]
+fn pixels_survive_being_split_into_tiles() {
+    let (width, height) = (TILE_SIZE + 3, 5);
+    let pixels: Vec<Color> = (0..width * height).map(|i| Color::new(i as u8, (i / 256) as u8, 0, 255)).collect();
+    let buffer = PixelBuffer::from_pixels(width, height, pixels.clone()).unwrap();
+    assert_eq!(buffer.tile_count(), 2);
+    assert_eq!(buffer.pixels().collect::<Vec<Color>>(), pixels);
+    assert_eq!(buffer.get_color(TILE_SIZE + 1, 2), pixels[(2 * width + TILE_SIZE + 1) as usize]);
+}
+
+#[test]
+fn drawing_crosses_tile_boundaries() {
+    let mut buffer = large_buffer();
+    buffer.draw_rectangle(TILE_SIZE - 2, TILE_SIZE - 2, 4, 4, Color::BLACK);
+    buffer.draw_line(0, 3, TILE_SIZE * 2 + 39, 3, Color::BLACK);
+    for (x, y) in [(TILE_SIZE - 2, TILE_SIZE - 2), (TILE_SIZE + 1, TILE_SIZE + 1), (TILE_SIZE * 2 + 39, 3)] {
+        assert_eq!(buffer.get_color(x, y), Color::BLACK);
+    }
+    assert_eq!(buffer.get_color(TILE_SIZE + 2, TILE_SIZE + 2), Color::WHITE);
+    assert_eq!(buffer.tiles_in(Rectangle::new(TILE_SIZE as f32 - 1f32, 0f32, 2f32, 1f32)), vec![0, 1]);
+}
+
+#[test]
+fn areas_are_copied_across_tiles() {
+    let mut buffer = large_buffer();
+    // Leaves the other tiles solid
+    buffer.draw_rectangle(TILE_SIZE - 2, TILE_SIZE - 2, 4, 4, Color::RED);
+    buffer.draw_pixel(TILE_SIZE * 2 + 1, 1, Color::BLUE);
+
+    for area in [Rectangle::new(TILE_SIZE as f32 - 5f32, TILE_SIZE as f32 - 3f32, 10f32, 6f32),
+                 Rectangle::new(0f32, 0f32, (TILE_SIZE * 2 + 40) as f32, 3f32),
+                 Rectangle::new(3f32, 4f32, 0f32, 2f32)] {
+        assert_eq!(buffer.area_to_rgba(area), buffer.crop(area).to_rgba(), "{:?}", area);
+    }
+}
+
+#[test]
+fn clones_do_not_see_later_drawing() {
+    let mut buffer = large_buffer();
+    buffer.draw_pixel(TILE_SIZE * 2, 0, Color::BLACK);
+    let before = buffer.clone();
+    buffer.draw_pixel(TILE_SIZE * 2, 1, Color::BLACK);
+    assert_eq!(before.get_color(TILE_SIZE * 2, 1), Color::WHITE);
+    assert_ne!(before, buffer);
+}
+
+#[test]
+fn debug_output_is_only_the_size() {
+    let buffer = PixelBuffer::new(TILE_SIZE * 3, 5, Color::RED);
+    assert_eq!(format!("{:?}", buffer), format!("PixelBuffer({}x5)", TILE_SIZE * 3));
+}
+
+#[test]
+fn flood_fill_stops_at_other_colors() {
+    let mut buffer = PixelBuffer::new(TILE_SIZE + 20, 30, Color::WHITE);
+    // A ring whose inside touches two tiles, with a gap on the right that lets the fill out
+    buffer.draw_rectangle_lines(Rectangle::new((TILE_SIZE - 10) as f32, 5f32, 20f32, 20f32), 1, Color::BLACK);
+    buffer.draw_pixel(TILE_SIZE + 9, 15, Color::WHITE);
+    buffer.draw_rectangle(0, 0, TILE_SIZE + 20, 1, Color::BLACK);
+
+    assert!(buffer.flood_fill(TILE_SIZE - 5, 10, Color::RED));
+    assert_eq!(buffer.get_color(TILE_SIZE + 5, 20), Color::RED);
+    assert_eq!(buffer.get_color(0, 29), Color::RED);
+    assert_eq!(buffer.get_color(TILE_SIZE - 10, 10), Color::BLACK);
+    assert_eq!(buffer.get_color(5, 0), Color::BLACK);
+    assert!(!buffer.flood_fill(3, 3, Color::RED));
+}
+
+#[test]
+fn flood_fill_handles_the_largest_canvases() {
+    // 16k x 16k, as big as the tiles allow; the filled tiles stay solid so this needs no pixel memory
+    let size = 16384;
+    let mut buffer = PixelBuffer::new(size, size, Color::WHITE);
+    buffer.draw_line(10000, 0, 10000, size - 1, Color::BLACK);
+    buffer.take_damage();
+    assert!(buffer.flood_fill(0, 0, Color::RED));
+    assert_eq!(buffer.get_color(9999, size - 1), Color::RED);
+    assert_eq!(buffer.get_color(10000, 5000), Color::BLACK);
+    assert_eq!(buffer.get_color(10001, 0), Color::WHITE);
+    assert_eq!(buffer.take_damage(), Some(Rectangle::new(0f32, 0f32, 10000f32, size as f32)));
+}
@@ -0,0 +1,41 @@
+use PaintIt::actions::brush::{BrushSize, BrushType};
+use PaintIt::actions::preview::ToolCursor;
+use PaintIt::actions::spray::SpraySize;
+use PaintIt::actions::tool_registry::ToolRegistry;
+use PaintIt::user_state::UserState;
+
+fn cursor(registry: &ToolRegistry, name: &str, user_state: &UserState) -> Option<ToolCursor> {
+    let entry = registry.iter().find(|entry| entry.name == name).unwrap();
+    entry.tool.as_deref().and_then(|tool| tool.cursor(user_state))
+}
+
+#[test]
+fn painting_tools_show_their_footprint() {
+    let registry = ToolRegistry::with_default_tools();
+    let mut user_state = UserState {
+        spray_size: SpraySize::SizeThree,
+        brush_size: BrushSize::Three,
+        brush_type: BrushType::Square,
+        ..Default::default()
+    };
+
+    assert_eq!(cursor(&registry, "Pencil", &user_state), Some(ToolCursor::Square(1)));
+    assert_eq!(cursor(&registry, "Eraser", &user_state), Some(ToolCursor::Square(8)));
+    assert_eq!(cursor(&registry, "Brush", &user_state), Some(ToolCursor::Square(16)));
+    assert_eq!(cursor(&registry, "Airbrush", &user_state), Some(ToolCursor::Circle(20f32)));
+
+    user_state.brush_type = BrushType::Circle;
+    assert_eq!(cursor(&registry, "Brush", &user_state), Some(ToolCursor::Circle(8f32)));
+}
+
+#[test]
+fn shape_tools_show_a_crosshair() {
+    let registry = ToolRegistry::with_default_tools();
+    let user_state = UserState::default();
+    for name in ["Select", "Line", "Rectangle", "Polygon", "Ellipse", "Rounded Rectangle"] {
+        assert_eq!(cursor(&registry, name, &user_state), Some(ToolCursor::Crosshair), "{}", name);
+    }
+    for name in ["Fill With Color", "Pick Color", "Magnifier"] {
+        assert_eq!(cursor(&registry, name, &user_state), None, "{}", name);
+    }
+}
@@ -0,0 +1,59 @@
+use raylib::color::Color;
+use PaintIt::pixel_buffer::PixelBuffer;
+use PaintIt::quantization::{k_means, median_cut, sample};
+
+/// Two shades of red and two of blue, with more red pixels
+fn picture() -> Vec<Color> {
+    let mut pixels = Vec::new();
+    pixels.extend([Color::new(250, 0, 0, 255); 30]);
+    pixels.extend([Color::new(240, 10, 10, 255); 10]);
+    pixels.extend([Color::new(0, 0, 250, 255); 5]);
+    pixels.extend([Color::new(10, 10, 240, 255); 5]);
+    pixels.extend([Color::new(0, 255, 0, 0); 100]);
+    pixels
+}
+
+#[test]
+fn fewer_colors_than_requested_are_kept_as_is() {
+    let colors = median_cut(&[Color::BLACK, Color::WHITE, Color::BLACK], 8);
+    assert_eq!(colors, [Color::BLACK, Color::WHITE]);
+    assert_eq!(k_means(&[Color::BLACK, Color::WHITE], 8, 10), [Color::BLACK, Color::WHITE]);
+}
+
+#[test]
+fn transparent_pixels_are_ignored() {
+    assert!(median_cut(&[Color::new(0, 0, 0, 0)], 4).is_empty());
+    assert!(median_cut(&picture(), 4).iter().all(|color| color.g < 100));
+}
+
+#[test]
+fn median_cut_splits_at_the_median_pixel() {
+    let mut pixels = Vec::new();
+    for color in [Color::new(250, 0, 0, 255), Color::new(240, 10, 10, 255),
+                  Color::new(0, 0, 250, 255), Color::new(10, 10, 240, 255)] {
+        pixels.extend([color; 10]);
+    }
+    assert_eq!(median_cut(&pixels, 2), [Color::new(5, 5, 245, 255), Color::new(245, 5, 5, 255)]);
+
+    // Most pixels are red, so the median falls between the reds
+    let colors = median_cut(&picture(), 2);
+    assert_eq!(colors[1], Color::new(250, 0, 0, 255));
+}
+
+#[test]
+fn k_means_groups_similar_colors() {
+    // Starts from the median cut above and moves the reds together
+    let colors = k_means(&picture(), 2, 10);
+    assert_eq!(colors, [Color::new(5, 5, 245, 255), Color::new(248, 3, 3, 255)]);
+}
+
+#[test]
+fn samples_are_spread_over_the_picture() {
+    let mut image = PixelBuffer::new(1000, 500, Color::WHITE);
+    image.draw_rectangle(900, 400, 100, 100, Color::BLACK);
+
+    let pixels = sample(&image, 5000);
+    assert!(pixels.len() <= 5000 && pixels.len() >= 4000, "{}", pixels.len());
+    assert!(pixels.contains(&Color::BLACK));
+    assert_eq!(sample(&PixelBuffer::new(4, 4, Color::BLACK), 5000).len(), 16);
+}
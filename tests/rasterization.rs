@@ -0,0 +1,182 @@
+use PaintIt::rasterization::{circle, circle_spans, ellipse, ellipse_spans, ellipse_stroke, rounded_rectangle,
+                             rounded_rectangle_stroke, Span};
+
+/// Render the pixels as rows of '#' and '.', covering x in 0..width and y in 0..height
+fn render(pixels: &[(i32, i32)], width: i32, height: i32) -> Vec<String> {
+    (0..height)
+        .map(|y| {
+            (0..width)
+                .map(|x| if pixels.contains(&(x, y)) { '#' } else { '.' })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn circle_of_radius_one() {
+    assert_eq!(render(&circle(1, 1, 1), 3, 3), [
+        ".#.",
+        "#.#",
+        ".#.",
+    ]);
+}
+
+#[test]
+fn circle_of_radius_five() {
+    assert_eq!(render(&circle(5, 5, 5), 11, 11), [
+        "...#####...",
+        "..#.....#..",
+        ".#.......#.",
+        "#.........#",
+        "#.........#",
+        "#.........#",
+        "#.........#",
+        "#.........#",
+        ".#.......#.",
+        "..#.....#..",
+        "...#####...",
+    ]);
+}
+
+#[test]
+fn ellipse_with_even_size() {
+    assert_eq!(render(&ellipse(0, 0, 3, 3), 4, 4), [
+        ".##.",
+        "#..#",
+        "#..#",
+        ".##.",
+    ]);
+
+    assert_eq!(render(&ellipse(0, 0, 9, 5), 10, 6), [
+        "..######..",
+        ".#......#.",
+        "#........#",
+        "#........#",
+        ".#......#.",
+        "..######..",
+    ]);
+}
+
+#[test]
+fn ellipse_corners_in_any_order() {
+    assert_eq!(ellipse(0, 0, 9, 5), ellipse(9, 5, 0, 0));
+    assert_eq!(ellipse(0, 0, 9, 5), ellipse(0, 5, 9, 0));
+}
+
+#[test]
+fn degenerate_ellipses() {
+    assert_eq!(ellipse(2, 3, 2, 3), [(2, 3)]);
+    assert_eq!(render(&ellipse(0, 0, 0, 4), 1, 5), ["#", "#", "#", "#", "#"]);
+    assert_eq!(render(&ellipse(0, 0, 6, 1), 7, 2), ["#######", "#######"]);
+}
+
+#[test]
+fn ellipse_with_odd_size() {
+    assert_eq!(render(&ellipse(0, 0, 6, 4), 7, 5), [
+        "..###..",
+        ".#...#.",
+        "#.....#",
+        ".#...#.",
+        "..###..",
+    ]);
+
+    assert_eq!(render(&ellipse(0, 0, 12, 6), 13, 7), [
+        "...#######...",
+        ".##.......##.",
+        "#...........#",
+        "#...........#",
+        "#...........#",
+        ".##.......##.",
+        "...#######...",
+    ]);
+}
+
+#[test]
+fn filled_circle_spans() {
+    assert_eq!(circle_spans(1, 1, 1), [
+        Span { y: 0, x_start: 1, x_end: 1 },
+        Span { y: 1, x_start: 0, x_end: 2 },
+        Span { y: 2, x_start: 1, x_end: 1 },
+    ]);
+    assert_eq!(ellipse_spans(0, 0, 9, 5).len(), 6);
+}
+
+#[test]
+fn stroke_of_width_one_is_the_outline() {
+    assert_eq!(ellipse_stroke(0, 0, 9, 5, 1), ellipse(0, 0, 9, 5));
+}
+
+#[test]
+fn thick_stroke() {
+    assert_eq!(render(&ellipse_stroke(0, 0, 14, 10, 3), 15, 11), [
+        ".....#####.....",
+        "...#########...",
+        "..###########..",
+        ".####.....####.",
+        "####.......####",
+        "###.........###",
+        "####.......####",
+        ".####.....####.",
+        "..###########..",
+        "...#########...",
+        ".....#####.....",
+    ]);
+}
+
+#[test]
+fn stroke_thicker_than_the_ellipse_is_filled() {
+    let filled: Vec<(i32, i32)> = ellipse_spans(0, 0, 4, 4)
+        .iter()
+        .flat_map(|span| (span.x_start..=span.x_end).map(move |x| (x, span.y)))
+        .collect();
+    assert_eq!(ellipse_stroke(0, 0, 4, 4, 3), filled);
+}
+
+#[test]
+fn rounded_rectangle_corners_are_circle_quadrants() {
+    assert_eq!(render(&rounded_rectangle(0, 0, 15, 9, 3), 16, 10), [
+        "..############..",
+        ".#............#.",
+        "#..............#",
+        "#..............#",
+        "#..............#",
+        "#..............#",
+        "#..............#",
+        "#..............#",
+        ".#............#.",
+        "..############..",
+    ]);
+}
+
+#[test]
+fn rounded_rectangle_without_radius_is_a_rectangle() {
+    assert_eq!(render(&rounded_rectangle(0, 0, 4, 3, 0), 5, 4), [
+        "#####",
+        "#...#",
+        "#...#",
+        "#####",
+    ]);
+}
+
+#[test]
+fn rounded_rectangle_radius_is_clamped() {
+    assert_eq!(rounded_rectangle(0, 0, 15, 9, 20), rounded_rectangle(0, 0, 15, 9, 4));
+}
+
+#[test]
+fn thick_rounded_rectangle() {
+    assert_eq!(render(&rounded_rectangle_stroke(0, 0, 15, 11, 5, 2), 16, 12), [
+        "...##########...",
+        "..############..",
+        ".###........###.",
+        "###..........###",
+        "##............##",
+        "##............##",
+        "##............##",
+        "##............##",
+        "###..........###",
+        ".###........###.",
+        "..############..",
+        "...##########...",
+    ]);
+}
@@ -0,0 +1,101 @@
+use raylib::color::Color;
+use raylib::math::Vector2;
+use PaintIt::actions::fill_mode::FillMode;
+use PaintIt::bitmap_font;
+use PaintIt::operation::Operation;
+use PaintIt::pixel_buffer::PixelBuffer;
+use PaintIt::script::{parse, run, Command, ScriptError};
+use PaintIt::user_state::UserState;
+
+fn run_script(text: &str, canvas: PixelBuffer) -> PixelBuffer {
+    let mut canvas = canvas;
+    run(&parse(text).unwrap(), &mut UserState::default(), &mut canvas);
+    canvas
+}
+
+#[test]
+fn commands_are_parsed() {
+    let script = "# Title\n\
+                  color #FF000080\n\
+                  fill-mode outline-fill\n\
+                  \n\
+                  polygon 1 2 3 4 5 6\n\
+                  text 4 8 20 \"Hello world\"\n\
+                  flip vertical\n";
+    assert_eq!(parse(script).unwrap(), vec![
+        Command::Color(Color::new(255, 0, 0, 128)),
+        Command::FillMode(FillMode::OutlineFill),
+        Command::Operation(Operation::Polygon(vec![
+            Vector2::new(1f32, 2f32), Vector2::new(3f32, 4f32), Vector2::new(5f32, 6f32),
+        ])),
+        Command::Operation(Operation::Text {
+            position: Vector2::new(4f32, 8f32),
+            text: "Hello world".to_string(),
+            size: 20,
+        }),
+        Command::Operation(Operation::FlipVertical),
+    ]);
+}
+
+#[test]
+fn errors_point_at_the_line() {
+    assert!(matches!(parse("line 0 0 10"), Err(ScriptError::Line(1, _))));
+    assert!(matches!(parse("# comment\nrotate 90"), Err(ScriptError::Line(2, _))));
+    assert!(matches!(parse("fill 1 1\npolygon 0 0 4 4"), Err(ScriptError::Line(2, _))));
+    assert!(matches!(parse("fill 1 1 1"), Err(ScriptError::Line(1, _))));
+    assert!(matches!(parse("fill-mode dotted"), Err(ScriptError::Line(1, _))));
+}
+
+#[test]
+fn small_polygons_are_drawn_from_their_points() {
+    let red = Color::new(255, 0, 0, 255);
+    let canvas = run_script("color #FF0000\npolygon 0 0 3 3 10 0", PixelBuffer::new(16, 16, Color::WHITE));
+    assert_eq!(canvas.get_color(1, 1), red);
+    assert_eq!(canvas.get_color(6, 2), red);
+    assert_eq!(canvas.get_color(5, 0), red);
+    assert_eq!(canvas.get_color(5, 1), Color::WHITE);
+}
+
+#[test]
+fn shapes_are_drawn_by_the_tools() {
+    let red = Color::new(255, 0, 0, 255);
+    let canvas = run_script("color #FF0000\nline 2 2 12 2\nrectangle 2 6 12 12\nfill 6 9",
+                            PixelBuffer::new(16, 16, Color::WHITE));
+    assert_eq!(canvas.get_color(7, 2), red);
+    assert_eq!(canvas.get_color(2, 9), red);
+    assert_eq!(canvas.get_color(6, 9), red);
+    assert_eq!(canvas.get_color(14, 14), Color::WHITE);
+}
+
+#[test]
+fn text_matches_its_measure() {
+    let canvas = run_script("text 1 1 10 Hi", PixelBuffer::new(32, 16, Color::WHITE));
+    let inked: Vec<i32> = (0..32)
+        .filter(|&x| (0..16).any(|y| canvas.get_color(x, y) == Color::BLACK))
+        .collect();
+    assert_eq!(inked.first(), Some(&1));
+    assert_eq!(inked.last(), Some(&bitmap_font::measure_text("Hi", 10)));
+}
+
+#[test]
+fn flips_and_resizes() {
+    let mut canvas = PixelBuffer::new(4, 3, Color::WHITE);
+    canvas.draw_pixel(0, 0, Color::RED);
+    let canvas = run_script("flip horizontal\nflip vertical\nresize 8 6", canvas);
+    assert_eq!((canvas.width(), canvas.height()), (8, 6));
+    assert_eq!(canvas.get_color(7, 5), Color::RED);
+    assert_eq!(canvas.get_color(6, 4), Color::RED);
+    assert_eq!(canvas.get_color(5, 5), Color::WHITE);
+}
+
+#[test]
+fn oversized_resizes_are_refused() {
+    assert!(matches!(parse("resize 200000 200000"), Err(ScriptError::Line(1, _))));
+    assert!(matches!(parse("resize 8 16385"), Err(ScriptError::Line(1, _))));
+    assert!(parse("resize 16384 1").is_ok());
+
+    let mut canvas = PixelBuffer::new(4, 3, Color::WHITE);
+    let resize = Operation::Resize { width: 200000, height: 200000 };
+    run(&[Command::Operation(resize)], &mut UserState::default(), &mut canvas);
+    assert_eq!((canvas.width(), canvas.height()), (4, 3));
+}
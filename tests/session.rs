@@ -0,0 +1,93 @@
+use std::path::PathBuf;
+use raylib::prelude::*;
+use PaintIt::actions::input::Modifiers;
+use PaintIt::session::{load, parse, serialize_frame, FrameInput, RecordedFrame, Session, SessionError, SessionRecorder};
+
+fn session(frames: &[RecordedFrame]) -> String {
+    let mut text = "PaintIt session 2\nseed 42\nwindow 1020x640\n".to_string();
+    for frame in frames {
+        text += &serialize_frame(frame);
+    }
+    text
+}
+
+#[test]
+fn frames_survive_a_round_trip() {
+    let frames = [
+        RecordedFrame {
+            input: FrameInput {
+                time: 0.016666,
+                frame_time: 1f32 / 60f32,
+                mouse_position: Vector2::new(120f32, 48.5),
+                ..Default::default()
+            },
+            tool_switches: Vec::new(),
+        },
+        RecordedFrame {
+            input: FrameInput {
+                time: 0.033333,
+                frame_time: 0.0166,
+                mouse_position: Vector2::new(-3f32, 400f32),
+                buttons_down: 0b011,
+                buttons_pressed: 0b010,
+                buttons_released: 0b100,
+                wheel: -1.5,
+                modifiers: Modifiers { control: true, shift: false, alt: true },
+                keys_pressed: vec![KeyboardKey::KEY_P, KeyboardKey::KEY_ENTER],
+                chars: vec!['#', 'é'],
+                dropped_files: vec![PathBuf::from("/tmp/my palette.gpl")],
+                window_size: Some(Vector2::new(800f32, 600.5)),
+            },
+            tool_switches: vec![4, 6],
+        },
+    ];
+
+    assert_eq!(parse(&session(&frames)).unwrap(), Session {
+        seed: 42,
+        window_size: Vector2::new(1020f32, 640f32),
+        frames: frames.to_vec(),
+    });
+}
+
+#[test]
+fn buttons_are_read_from_their_bits() {
+    let input = FrameInput { buttons_down: 0b001, buttons_released: 0b010, ..Default::default() };
+    assert!(input.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT));
+    assert!(!input.is_mouse_button_down(MouseButton::MOUSE_BUTTON_RIGHT));
+    assert!(input.is_mouse_button_released(MouseButton::MOUSE_BUTTON_RIGHT));
+    assert!(!input.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT));
+}
+
+#[test]
+fn errors_point_at_the_line() {
+    assert!(matches!(parse("f 0 0 0 0 0 0 0"), Err(SessionError::Line(1, _))));
+    assert!(matches!(parse("PaintIt session 1\n"), Err(SessionError::Line(1, _))));
+    assert!(matches!(parse("PaintIt session 2\nseed -1\n"), Err(SessionError::Line(2, _))));
+    assert!(matches!(parse("PaintIt session 2\nseed 1\nwindow 800\n"), Err(SessionError::Line(3, _))));
+    assert!(matches!(parse("PaintIt session 2\nf 0 0 1 2 0 0 0 s8x\n"), Err(SessionError::Line(2, _))));
+    assert!(matches!(parse("PaintIt session 2\nt 2\n"), Err(SessionError::Line(2, _))));
+    assert!(matches!(parse("PaintIt session 2\nf 0 0 1 2 0 0 0\nf 0 0 1 x 0 0 0\n"), Err(SessionError::Line(3, _))));
+    assert!(matches!(parse("PaintIt session 2\nf 0 0 1 2 0 0 0 z4\n"), Err(SessionError::Line(2, _))));
+}
+
+#[test]
+fn recorded_frames_are_written_when_recording_stops() {
+    let path = std::env::temp_dir().join(format!("paintit-session-{}", std::process::id()));
+    let frame = RecordedFrame {
+        input: FrameInput { mouse_position: Vector2::new(5f32, 6f32), ..Default::default() },
+        tool_switches: vec![2],
+    };
+    let window_size = Vector2::new(640f32, 480f32);
+
+    let mut recorder = SessionRecorder::create(&path, 7, window_size).unwrap();
+    recorder.record(&frame).unwrap();
+    recorder.finish().unwrap();
+    assert_eq!(load(&path).unwrap(), Session { seed: 7, window_size, frames: vec![frame.clone()] });
+
+    // Dropping the recorder writes the frames too
+    let mut recorder = SessionRecorder::create(&path, 8, window_size).unwrap();
+    recorder.record(&frame).unwrap();
+    drop(recorder);
+    assert_eq!(load(&path).unwrap().frames, [frame]);
+    std::fs::remove_file(&path).unwrap();
+}
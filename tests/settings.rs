@@ -0,0 +1,69 @@
+use std::path::PathBuf;
+use raylib::prelude::*;
+use PaintIt::actions::fill_mode::FillMode;
+use PaintIt::actions::spray::SpraySize;
+use PaintIt::settings::{Settings, SettingsError};
+
+#[test]
+fn settings_survive_a_round_trip() {
+    let mut settings = Settings::default();
+    settings.options.current_colors = [Color::new(18, 52, 86, 255), Color::new(255, 255, 255, 128)];
+    settings.options.spray_size = SpraySize::SizeThree;
+    settings.options.spray_density = 1250.5;
+    settings.options.antialiasing = true;
+    settings.options.line_width = 5;
+    settings.options.fill_mode = FillMode::OutlineFill;
+    settings.palette = vec![Color::new(1, 2, 3, 255), Color::new(4, 5, 6, 255)];
+    settings.tool = Some("Rounded Rectangle".to_string());
+    settings.recent_files = vec![PathBuf::from("/tmp/my drawing.png"), PathBuf::from("/tmp/b.qoi")];
+    settings.recent_colors = vec![Color::new(200, 0, 0, 255), Color::new(0, 0, 200, 100)];
+    settings.window_size = (1280, 720);
+    settings.show_color_box = false;
+    settings.show_status_bar = false;
+
+    let (parsed, errors) = Settings::parse(&settings.serialize());
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert_eq!(parsed, settings);
+}
+
+#[test]
+fn damaged_lines_are_skipped() {
+    let text = "line-width = 3\nline-width = 0\nfill-mode = sideways\npalette = #123456 nope\ngarbage\n\
+                shoe-size = 43\nantialiasing = on\nwindow-size = 800x\nline-width = 4\ncorner-radius = 101\n\
+                window-size = 800x100000\ncorner-radius = 100\n";
+    let (settings, errors) = Settings::parse(text);
+
+    assert_eq!(settings.options.line_width, 3);
+    assert_eq!(settings.options.corner_radius, 100);
+    assert_eq!(settings.window_size, Settings::default().window_size);
+    assert!(settings.options.antialiasing);
+    assert_eq!(settings.palette, Settings::default().palette);
+    let lines: Vec<usize> = errors.iter()
+        .map(|error| match error {
+            SettingsError::Line(line, _) => *line,
+            SettingsError::Io(error) => panic!("{}", error),
+        })
+        .collect();
+    assert_eq!(lines, [2, 3, 4, 5, 6, 8, 9, 10, 11]);
+}
+
+#[test]
+fn missing_files_give_the_defaults() {
+    let path = std::env::temp_dir().join("paintit-settings-test").join("missing");
+    assert_eq!(Settings::load(&path), Settings::default());
+}
+
+#[test]
+fn saving_replaces_the_file() {
+    let directory = std::env::temp_dir().join(format!("paintit-settings-{}", std::process::id()));
+    let path = directory.join("settings");
+    let mut settings = Settings::default();
+    settings.options.line_width = 3;
+    settings.save(&path).unwrap();
+    settings.options.line_width = 5;
+    settings.save(&path).unwrap();
+
+    assert_eq!(Settings::load(&path), settings);
+    assert_eq!(std::fs::read_dir(&directory).unwrap().count(), 1);
+    std::fs::remove_dir_all(&directory).unwrap();
+}
@@ -0,0 +1,122 @@
+use raylib::prelude::*;
+use PaintIt::actions::input::Modifiers;
+use PaintIt::session::FrameInput;
+use PaintIt::ui::widgets::{Dialog, DialogEvent};
+
+const SIZE: Vector2 = Vector2 { x: 200f32, y: 160f32 };
+
+/// Fills the window, so the positions are the same as the bounds
+fn dialog() -> Dialog {
+    Dialog::new("Test", SIZE, SIZE)
+        .with_label("Width:", Rectangle::new(10f32, 30f32, 40f32, 18f32))
+        .with_number_field("width", Rectangle::new(60f32, 30f32, 60f32, 18f32), 100, 1, 500)
+        .with_check_box("keep", "Keep", Rectangle::new(10f32, 60f32, 100f32, 18f32), false)
+        .with_slider("amount", Rectangle::new(10f32, 90f32, 101f32, 14f32), 50f32, 0f32, 100f32)
+        .with_button("ok", "OK", Rectangle::new(10f32, 120f32, 80f32, 24f32))
+        .with_button("cancel", "Cancel", Rectangle::new(100f32, 120f32, 80f32, 24f32))
+        .with_default_button("ok")
+        .with_cancel_button("cancel")
+}
+
+fn keys(keys: &[KeyboardKey]) -> FrameInput {
+    FrameInput { keys_pressed: keys.to_vec(), ..Default::default() }
+}
+
+fn typed(text: &str) -> FrameInput {
+    FrameInput { chars: text.chars().collect(), ..Default::default() }
+}
+
+fn click(dialog: &mut Dialog, x: f32, y: f32) -> DialogEvent {
+    let mouse_position = Vector2::new(x, y);
+    dialog.update(&FrameInput { mouse_position, buttons_down: 1, buttons_pressed: 1, ..Default::default() });
+    dialog.update(&FrameInput { mouse_position, buttons_released: 1, ..Default::default() })
+}
+
+#[test]
+fn tab_skips_labels_and_wraps_around() {
+    let mut dialog = dialog();
+    assert_eq!(dialog.focused(), Some("width"));
+    for name in ["keep", "amount", "ok", "cancel", "width"] {
+        dialog.update(&keys(&[KeyboardKey::KEY_TAB]));
+        assert_eq!(dialog.focused(), Some(name));
+    }
+
+    let shift_tab = FrameInput {
+        keys_pressed: vec![KeyboardKey::KEY_TAB],
+        modifiers: Modifiers { control: false, shift: true, alt: false },
+        ..Default::default()
+    };
+    dialog.update(&shift_tab);
+    assert_eq!(dialog.focused(), Some("cancel"));
+}
+
+#[test]
+fn typed_numbers_are_kept_in_bounds() {
+    let mut dialog = dialog();
+    dialog.update(&keys(&[KeyboardKey::KEY_BACKSPACE]));
+    dialog.update(&keys(&[KeyboardKey::KEY_BACKSPACE]));
+    dialog.update(&typed("9x00"));
+    // Not committed until the focus moves
+    assert_eq!(dialog.number("width"), Some(100));
+    assert_eq!(dialog.update(&keys(&[KeyboardKey::KEY_ENTER])), DialogEvent::Clicked("ok"));
+    assert_eq!(dialog.number("width"), Some(500));
+
+    dialog.update(&keys(&[KeyboardKey::KEY_DOWN]));
+    assert_eq!(dialog.number("width"), Some(499));
+    dialog.set_number("width", -4);
+    assert_eq!(dialog.number("width"), Some(1));
+}
+
+#[test]
+fn keys_and_clicks_change_the_controls() {
+    let mut dialog = dialog();
+    dialog.update(&keys(&[KeyboardKey::KEY_TAB]));
+    dialog.update(&keys(&[KeyboardKey::KEY_SPACE]));
+    assert_eq!(dialog.checked("keep"), Some(true));
+    assert_eq!(click(&mut dialog, 15f32, 65f32), DialogEvent::Open);
+    assert_eq!(dialog.checked("keep"), Some(false));
+
+    dialog.update(&keys(&[KeyboardKey::KEY_RIGHT]));
+    assert_eq!(dialog.slider("amount"), Some(50f32));
+    dialog.update(&keys(&[KeyboardKey::KEY_TAB]));
+    dialog.update(&keys(&[KeyboardKey::KEY_RIGHT]));
+    assert_eq!(dialog.slider("amount"), Some(55f32));
+    click(&mut dialog, 35f32, 95f32);
+    assert_eq!(dialog.slider("amount"), Some(25f32));
+
+    assert_eq!(click(&mut dialog, 20f32, 130f32), DialogEvent::Clicked("ok"));
+    assert_eq!(click(&mut dialog, 120f32, 130f32), DialogEvent::Cancelled);
+    assert_eq!(dialog.update(&keys(&[KeyboardKey::KEY_ESCAPE])), DialogEvent::Cancelled);
+    assert_eq!(dialog.number("keep"), None);
+}
+
+#[test]
+fn text_fields_take_any_typed_text() {
+    let mut dialog = Dialog::new("Save As", SIZE, SIZE)
+        .with_text_field("path", Rectangle::new(10f32, 30f32, 180f32, 18f32), "untitled.png")
+        .with_button("ok", "OK", Rectangle::new(10f32, 120f32, 80f32, 24f32))
+        .with_default_button("ok");
+    for _ in 0.."png".len() {
+        dialog.update(&keys(&[KeyboardKey::KEY_BACKSPACE]));
+    }
+    dialog.update(&typed("qoi"));
+    dialog.update(&FrameInput { chars: vec![' ', '\u{8}'], ..Default::default() });
+    assert_eq!(dialog.text("path"), Some("untitled.qoi "));
+    assert_eq!(dialog.update(&keys(&[KeyboardKey::KEY_ENTER])), DialogEvent::Clicked("ok"));
+    assert_eq!(dialog.text("ok"), None);
+}
+
+#[test]
+fn values_can_be_set_from_outside() {
+    let mut dialog = dialog().with_text_field("hex", Rectangle::new(120f32, 60f32, 60f32, 18f32), "#000000");
+    dialog.set_text("hex", "#FF0000");
+    dialog.set_slider("amount", 250f32);
+    dialog.set_number("width", 0);
+    assert_eq!(dialog.text("hex"), Some("#FF0000"));
+    assert_eq!(dialog.slider("amount"), Some(100f32));
+    assert_eq!(dialog.number("width"), Some(1));
+
+    // Only to the kind of control it names
+    dialog.set_text("amount", "text");
+    assert_eq!(dialog.slider("amount"), Some(100f32));
+}